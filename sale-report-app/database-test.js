@@ -91,8 +91,9 @@ async function testDatabaseClear() {
       const { dbManager } = await import('./src/utils/database.ts');
       
       console.log('Starting database clear...');
-      await dbManager.clearAllData();
-      console.log('✅ Database clear completed');
+      const preview = await dbManager.prepareClearData({ kind: 'everything' });
+      const result = await dbManager.clearData(preview.scope, preview.token);
+      console.log('✅ Database clear completed, backup at', result?.backup_path);
       
       // Check state after clear
       setTimeout(() => {
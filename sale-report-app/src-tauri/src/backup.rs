@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+/// Daily backups kept in the backups folder unless `daily_backups_kept` is set in the
+/// app's config.json; older ones are deleted
+pub const DEFAULT_DAILY_BACKUPS_KEPT: usize = 7;
+
+/// Folder next to the database file that holds backup snapshots
+pub const BACKUP_DIR: &str = "backups";
+
+/// A backup file of the database
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub kind: BackupKind,
+    pub size_bytes: u64,
+    pub modified_at: String,
+}
+
+/// Why a backup in the backups folder was taken
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// Taken the first time the app opens the database on a day
+    Daily,
+    /// Taken before data was cleared
+    BeforeClear,
+    /// Taken before another backup was restored
+    BeforeRestore,
+    /// Any other database file placed in the folder
+    Other,
+}
+
+impl BackupKind {
+    /// Start of the file names of this kind of backup
+    pub fn label(self) -> &'static str {
+        match self {
+            BackupKind::Daily => "daily",
+            BackupKind::BeforeClear => "before-clear",
+            BackupKind::BeforeRestore => "before-restore",
+            BackupKind::Other => "backup",
+        }
+    }
+}
+
+/// File name of the daily backup for `date` (YYYY-MM-DD)
+pub fn daily_file_name(date: &str) -> String {
+    format!("{}-{date}.db", BackupKind::Daily.label())
+}
+
+pub fn kind(file_name: &str) -> BackupKind {
+    [
+        BackupKind::Daily,
+        BackupKind::BeforeClear,
+        BackupKind::BeforeRestore,
+    ]
+    .into_iter()
+    .find(|kind| {
+        file_name
+            .strip_prefix(kind.label())
+            .is_some_and(|rest| rest.starts_with('-'))
+    })
+    .unwrap_or(BackupKind::Other)
+}
+
+/// Daily backups beyond the newest `keep`, which rotation deletes. Their names sort
+/// by date, so no file times are needed.
+pub fn expired_daily(file_names: &[String], keep: usize) -> Vec<String> {
+    let mut daily: Vec<&String> = file_names
+        .iter()
+        .filter(|name| kind(name) == BackupKind::Daily && name.ends_with(".db"))
+        .collect();
+    daily.sort_unstable_by(|a, b| b.cmp(a));
+    daily.into_iter().skip(keep).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds() {
+        assert_eq!(daily_file_name("2025-05-01"), "daily-2025-05-01.db");
+        assert_eq!(kind("daily-2025-05-01.db"), BackupKind::Daily);
+        assert_eq!(
+            kind("before-clear-20250501-101500.db"),
+            BackupKind::BeforeClear
+        );
+        assert_eq!(
+            kind("before-restore-20250501-101500-2.db"),
+            BackupKind::BeforeRestore
+        );
+        assert_eq!(kind("sales_report.db"), BackupKind::Other);
+    }
+
+    #[test]
+    fn test_expired_daily() {
+        let names: Vec<String> = [
+            "daily-2025-05-03.db",
+            "before-clear-20250501-101500.db",
+            "daily-2025-05-01.db",
+            "daily-2025-04-30.db",
+            "daily-2025-05-02.db",
+            "daily-notes.txt",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+        assert_eq!(
+            expired_daily(&names, 2),
+            vec!["daily-2025-05-01.db", "daily-2025-04-30.db"]
+        );
+        assert!(expired_daily(&names, 7).is_empty());
+        assert_eq!(expired_daily(&names, 0).len(), 4);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::database::{TableRowCount, COMPANY_DATA_TABLES};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What `clear_data` deletes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClearScope {
+    /// Every company with all of its data and settings
+    Everything,
+    /// All data of one company; the company and its settings stay
+    Company { company_id: i32 },
+    /// Sales lines and their invoice mappings, of one company or all of them, with
+    /// invoice dates in an inclusive range
+    Sales {
+        company_id: Option<i32>,
+        date_from: Option<String>,
+        date_to: Option<String>,
+    },
+}
+
+/// What a clear would remove, with the token that confirms it
+#[derive(Debug, Serialize)]
+pub struct ClearDataPreview {
+    pub scope: ClearScope,
+    pub tables: Vec<TableRowCount>,
+    pub total_rows: i64,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClearDataResult {
+    pub deleted: Vec<TableRowCount>,
+    /// Snapshot taken before deleting; restoring it undoes the clear
+    pub backup_path: String,
+}
+
+/// Confirmation token issued by `prepare_clear_data` for one scope
+pub struct PendingClear {
+    pub token: String,
+    pub scope: ClearScope,
+    pub issued_at: std::time::Instant,
+}
+
+/// How long a clear confirmation token stays valid
+pub const TOKEN_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Company tables that hold settings rather than data; clearing a company keeps them
+const COMPANY_SETTINGS_TABLES: &[&str] = &["column_mapping_profiles", "re_year_codes"];
+
+/// Tables a clear deletes from, children before parents, each with the condition
+/// selecting its rows. All conditions take the same parameters.
+pub type Plan = (Vec<(&'static str, String)>, Vec<Box<dyn rusqlite::ToSql>>);
+
+pub fn plan(scope: &ClearScope) -> Plan {
+    match scope {
+        ClearScope::Everything => {
+            let mut tables: Vec<_> = COMPANY_DATA_TABLES
+                .iter()
+                .map(|(table, _)| (*table, "1".to_string()))
+                .collect();
+            tables.push(("companies", "1".to_string()));
+            (tables, Vec::new())
+        }
+        ClearScope::Company { company_id } => (
+            COMPANY_DATA_TABLES
+                .iter()
+                .filter(|(table, _)| !COMPANY_SETTINGS_TABLES.contains(table))
+                .map(|(table, condition)| (*table, condition.to_string()))
+                .collect(),
+            vec![Box::new(*company_id)],
+        ),
+        ClearScope::Sales {
+            company_id,
+            date_from,
+            date_to,
+        } => {
+            let lines = "(?1 IS NULL OR company_id = ?1) \
+                AND (?2 IS NULL OR inv_date >= ?2) AND (?3 IS NULL OR inv_date <= ?3)";
+            (
+                vec![
+                    (
+                        "invoice_mappings",
+                        format!(
+                            "original_invoice_no IN (SELECT s.invno FROM sales_reports s \
+                             WHERE s.company_id = invoice_mappings.company_id AND {lines})"
+                        ),
+                    ),
+                    ("sales_reports", lines.to_string()),
+                ],
+                vec![
+                    Box::new(*company_id),
+                    Box::new(date_from.clone()),
+                    Box::new(date_to.clone()),
+                ],
+            )
+        }
+    }
+}
+
+/// Single-use token confirming a clear, unique per request
+pub fn token(scope: &ClearScope) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{scope:?} {:?}", std::time::SystemTime::now()));
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
@@ -1,7 +1,22 @@
+use crate::backup::{self, BackupInfo, BackupKind, BACKUP_DIR};
+use crate::clear::{self, ClearDataPreview, ClearDataResult, ClearScope, PendingClear};
+use crate::error::{AppError, AppResult};
+use crate::excel;
+use crate::gst_split::{self, GstRates, SplitInvoice};
+use crate::gstin;
+use crate::migrations;
+use crate::money::Money;
+use crate::place_of_supply::{self, TaxType};
+use crate::re_code::{self, YearCode};
+use crate::reconcile::{self, Discrepancy, Rule};
+use crate::tally::{self, TallyLedgers, TallyVoucher};
+use crate::xlsx;
 use rusqlite::{Connection, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::io::Cursor;
-use tauri::AppHandle;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Company {
@@ -9,14 +24,94 @@ pub struct Company {
     pub name: String,
     pub key: String,
     pub created_at: String,
+    /// Largest rounding difference in rupees the reconciliation checks accept
+    pub reconcile_tolerance: f64,
+    pub gstin: Option<String>,
+    /// GST state code the company supplies from; matches `gstin` when that is set
+    pub state_code: Option<String>,
+    pub address: Option<String>,
+    /// Month the fiscal year starts in, 1-12 (April in India)
+    pub fiscal_year_start: u32,
+    /// When the company was archived; archived companies are hidden from the company list
+    pub archived_at: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Column list matching `Company::from_row`
+const COMPANY_COLUMNS: &str = "id, name, key, created_at, reconcile_tolerance, gstin, state_code,
+     address, fiscal_year_start, archived_at";
+
+impl Company {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(Company {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            key: row.get(2)?,
+            created_at: row.get(3)?,
+            reconcile_tolerance: row.get(4)?,
+            gstin: row.get(5)?,
+            state_code: row.get(6)?,
+            address: row.get(7)?,
+            fiscal_year_start: row.get(8)?,
+            archived_at: row.get(9)?,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateCompanyRequest {
+    pub name: String,
+    pub key: String,
+    pub gstin: Option<String>,
+    pub state_code: Option<String>,
+    pub address: Option<String>,
+    pub fiscal_year_start: Option<u32>,
+    pub reconcile_tolerance: Option<f64>,
+}
+
+/// Fields to change; an empty `gstin`, `state_code` or `address` clears it
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct UpdateCompanyRequest {
     pub name: Option<String>,
     pub key: Option<String>,
+    pub reconcile_tolerance: Option<f64>,
+    pub gstin: Option<String>,
+    pub state_code: Option<String>,
+    pub address: Option<String>,
+    pub fiscal_year_start: Option<u32>,
 }
 
+/// Rows of one table that belong to a company
+#[derive(Debug, Serialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+/// What deleting a company would remove
+#[derive(Debug, Serialize)]
+pub struct CompanyDeletionPreview {
+    pub company: Company,
+    pub tables: Vec<TableRowCount>,
+    pub total_rows: i64,
+}
+
+/// Tables holding company data, children before parents, with the condition that
+/// selects the rows of company `?1`
+pub const COMPANY_DATA_TABLES: &[(&str, &str)] = &[
+    ("invoice_mappings", "company_id = ?1"),
+    ("tally_exports", "company_id = ?1"),
+    (
+        "report_rows",
+        "uploaded_report_id IN (SELECT id FROM uploaded_reports WHERE company_id = ?1)",
+    ),
+    ("sales_reports", "company_id = ?1"),
+    ("uploaded_reports", "company_id = ?1"),
+    ("customers", "company_id = ?1"),
+    ("categories", "company_id = ?1"),
+    ("column_mapping_profiles", "company_id = ?1"),
+    ("re_year_codes", "company_id = ?1"),
+];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Category {
     pub id: i32,
@@ -36,23 +131,59 @@ pub struct SalesReport {
     pub inv_date: String,
     pub RE: String,
     pub invno: String,
+    pub line_no: i32,
     pub part_code: Option<String>,
     pub part_name: Option<String>,
     pub tariff: Option<String>,
     pub qty: f64,
     pub bas_price: f64,
-    pub ass_val: f64,
-    pub c_gst: f64,
-    pub s_gst: f64,
-    pub igst: f64,
-    pub amot: f64,
-    pub inv_val: f64,
+    pub ass_val: Money,
+    pub c_gst: Money,
+    pub s_gst: Money,
+    pub igst: Money,
+    pub amot: Money,
+    pub inv_val: Money,
     pub igst_yes_no: String,
     pub percentage: f64,
     pub created_at: String,
 }
 
-#[derive(Debug, Deserialize)]
+// Column list matching `SalesReport::from_row`
+const SALES_REPORT_COLUMNS: &str =
+    "id, company_id, cust_code, cust_name, inv_date, RE, invno, line_no,
+                    part_code, part_name, tariff, qty, bas_price, ass_val,
+                    c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at";
+
+impl SalesReport {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(SalesReport {
+            id: row.get(0)?,
+            company_id: row.get(1)?,
+            cust_code: row.get(2)?,
+            cust_name: row.get(3)?,
+            inv_date: row.get(4)?,
+            RE: row.get(5)?,
+            invno: row.get(6)?,
+            line_no: row.get(7)?,
+            part_code: row.get(8)?,
+            part_name: row.get(9)?,
+            tariff: row.get(10)?,
+            qty: row.get(11)?,
+            bas_price: row.get(12)?,
+            ass_val: row.get(13)?,
+            c_gst: row.get(14)?,
+            s_gst: row.get(15)?,
+            igst: row.get(16)?,
+            amot: row.get(17)?,
+            inv_val: row.get(18)?,
+            igst_yes_no: row.get(19)?,
+            percentage: row.get(20)?,
+            created_at: row.get(21)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct CreateSalesReportRequest {
     pub company_id: i32,
@@ -61,17 +192,20 @@ pub struct CreateSalesReportRequest {
     pub inv_date: String,
     pub RE: String,
     pub invno: String,
+    /// Line number within the invoice; the next free line is used when omitted.
+    #[serde(default)]
+    pub line_no: Option<i32>,
     pub part_code: Option<String>,
     pub part_name: Option<String>,
     pub tariff: Option<String>,
     pub qty: f64,
     pub bas_price: f64,
-    pub ass_val: f64,
-    pub c_gst: f64,
-    pub s_gst: f64,
-    pub igst: f64,
-    pub amot: f64,
-    pub inv_val: f64,
+    pub ass_val: Money,
+    pub c_gst: Money,
+    pub s_gst: Money,
+    pub igst: Money,
+    pub amot: Money,
+    pub inv_val: Money,
     pub igst_yes_no: String,
     pub percentage: f64,
 }
@@ -86,7 +220,7 @@ pub struct SalesReportFilters {
     pub max_amount: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 #[allow(non_snake_case)]
 pub struct UpdateSalesReportRequest {
     pub cust_code: Option<String>,
@@ -94,17 +228,18 @@ pub struct UpdateSalesReportRequest {
     pub inv_date: Option<String>,
     pub RE: Option<String>,
     pub invno: Option<String>,
+    pub line_no: Option<i32>,
     pub part_code: Option<String>,
     pub part_name: Option<String>,
     pub tariff: Option<String>,
     pub qty: Option<f64>,
     pub bas_price: Option<f64>,
-    pub ass_val: Option<f64>,
-    pub c_gst: Option<f64>,
-    pub s_gst: Option<f64>,
-    pub igst: Option<f64>,
-    pub amot: Option<f64>,
-    pub inv_val: Option<f64>,
+    pub ass_val: Option<Money>,
+    pub c_gst: Option<Money>,
+    pub s_gst: Option<Money>,
+    pub igst: Option<Money>,
+    pub amot: Option<Money>,
+    pub inv_val: Option<Money>,
     pub igst_yes_no: Option<String>,
     pub percentage: Option<f64>,
 }
@@ -116,11 +251,33 @@ pub struct Customer {
     pub customer_name: String,
     pub tally_name: String,
     pub gst_no: Option<String>,
+    /// State code of `gst_no`, set when the GSTIN is saved
+    pub gst_state_code: Option<String>,
     pub category_id: Option<i32>,
     pub category_name: Option<String>, // For display purposes
     pub created_at: String,
 }
 
+/// Customer columns; the query must join `categories cat`
+const CUSTOMER_COLUMNS: &str = "c.id, c.company_id, c.customer_name, c.tally_name, c.gst_no,
+     c.gst_state_code, c.category_id, cat.name, c.created_at";
+
+impl Customer {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(Customer {
+            id: row.get(0)?,
+            company_id: row.get(1)?,
+            customer_name: row.get(2)?,
+            tally_name: row.get(3)?,
+            gst_no: row.get(4)?,
+            gst_state_code: row.get(5)?,
+            category_id: row.get(6)?,
+            category_name: row.get(7)?,
+            created_at: row.get(8)?,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateCustomerRequest {
     pub company_id: i32,
@@ -151,1531 +308,6031 @@ pub struct UpdateCategoryRequest {
     pub description: Option<String>,
 }
 
-pub struct DatabaseManager {
-    conn: Connection,
+/// Saved header names for the sales report fields of one source system. A profile's
+/// headers are tried before the built-in aliases of a field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMappingProfile {
+    pub id: i32,
+    pub company_id: i32,
+    pub name: String,
+    /// Sales report field -> file headers it is read from, in order of preference
+    pub mappings: std::collections::BTreeMap<String, Vec<String>>,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
-impl DatabaseManager {
-    pub fn new(_app_handle: &AppHandle) -> Result<Self> {
-        // For now, use a local database file
-        let db_path = "sales_report.db";
-        let conn = Connection::open(db_path)?;
-
-        let manager = DatabaseManager { conn };
-        manager.create_tables()?;
-        manager.seed_initial_data()?;
-
-        Ok(manager)
+impl ColumnMappingProfile {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        let mappings_json: String = row.get(3)?;
+        Ok(ColumnMappingProfile {
+            id: row.get(0)?,
+            company_id: row.get(1)?,
+            name: row.get(2)?,
+            mappings: serde_json::from_str(&mappings_json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    3,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
     }
+}
 
-    fn create_tables(&self) -> Result<()> {
-        // Companies table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS companies (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                key TEXT NOT NULL UNIQUE,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
+#[derive(Debug, Deserialize)]
+pub struct CreateColumnMappingProfileRequest {
+    pub company_id: i32,
+    pub name: String,
+    pub mappings: std::collections::BTreeMap<String, Vec<String>>,
+}
 
-        // Categories table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS categories (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                name TEXT NOT NULL,
-                description TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
-                UNIQUE(company_id, name)
-            )",
-            [],
-        )?;
+#[derive(Debug, Deserialize)]
+pub struct UpdateColumnMappingProfileRequest {
+    pub name: Option<String>,
+    pub mappings: Option<std::collections::BTreeMap<String, Vec<String>>>,
+}
 
-        // Customers table - create with new schema
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS customers (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                customer_name TEXT NOT NULL,
-                tally_name TEXT NOT NULL,
-                gst_no TEXT,
-                category_id INTEGER,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
-                FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE SET NULL,
-                UNIQUE(company_id, customer_name)
-            )",
-            [],
-        )?;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadedReport {
+    pub id: i32,
+    pub company_id: i32,
+    pub filename: String,
+    pub uploaded_at: String,
+    pub status: String,
+    pub parsed_hash: Option<String>,
+    pub row_count: i32,
+    pub sales_line_count: i32,
+}
 
-        // Handle migration from old schema to new schema
-        self.migrate_database()?;
+// Column list matching `UploadedReport::from_row`, selected from `uploaded_reports u`
+const UPLOADED_REPORT_COLUMNS: &str =
+    "u.id, u.company_id, u.filename, u.uploaded_at, u.status, u.parsed_hash,
+     (SELECT COUNT(*) FROM report_rows r WHERE r.uploaded_report_id = u.id),
+     (SELECT COUNT(*) FROM sales_reports s WHERE s.uploaded_report_id = u.id)";
+
+impl UploadedReport {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(UploadedReport {
+            id: row.get(0)?,
+            company_id: row.get(1)?,
+            filename: row.get(2)?,
+            uploaded_at: row.get(3)?,
+            status: row.get(4)?,
+            parsed_hash: row.get(5)?,
+            row_count: row.get(6)?,
+            sales_line_count: row.get(7)?,
+        })
+    }
+}
 
-        // Sales Reports table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS sales_reports (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                cust_code TEXT NOT NULL,
-                cust_name TEXT NOT NULL,
-                inv_date TEXT NOT NULL,
-                RE TEXT NOT NULL,
-                invno TEXT NOT NULL,
-                part_code TEXT,
-                part_name TEXT,
-                tariff TEXT,
-                qty REAL NOT NULL,
-                bas_price REAL NOT NULL,
-                ass_val REAL NOT NULL,
-                c_gst REAL NOT NULL,
-                s_gst REAL NOT NULL,
-                igst REAL NOT NULL,
-                amot REAL NOT NULL,
-                inv_val REAL NOT NULL,
-                igst_yes_no TEXT NOT NULL,
-                percentage REAL NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
-                UNIQUE(company_id, invno)
-            )",
-            [],
-        )?;
+// Upload lifecycle: uploaded -> parsed (raw rows stored) -> transformed (sales lines
+// created) -> exported. A rolled back upload keeps its raw rows but no sales lines.
+pub const UPLOAD_STATUS_UPLOADED: &str = "uploaded";
+pub const UPLOAD_STATUS_PARSED: &str = "parsed";
+pub const UPLOAD_STATUS_TRANSFORMED: &str = "transformed";
+pub const UPLOAD_STATUS_ROLLED_BACK: &str = "rolled_back";
+pub const UPLOAD_STATUS_EXPORTED: &str = "exported";
 
-        // UploadedReports table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS uploaded_reports (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                filename TEXT NOT NULL,
-                uploaded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                status TEXT NOT NULL DEFAULT 'uploaded',
-                parsed_hash TEXT,
-                FOREIGN KEY (company_id) REFERENCES companies(id)
-            )",
-            [],
-        )?;
+// Default folder for generated Tally files, next to the database
+const TALLY_EXPORT_DIR: &str = "tally_exports";
 
-        // ReportRows table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS report_rows (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                uploaded_report_id INTEGER NOT NULL,
-                invoice_no TEXT NOT NULL,
-                invoice_date DATE NOT NULL,
-                customer_name_raw TEXT NOT NULL,
-                gst_rate DECIMAL(5,2) NOT NULL,
-                cgst_amt DECIMAL(10,2) NOT NULL,
-                sgst_amt DECIMAL(10,2) NOT NULL,
-                igst_amt DECIMAL(10,2) NOT NULL,
-                total_amount DECIMAL(10,2) NOT NULL,
-                extra_json TEXT,
-                FOREIGN KEY (uploaded_report_id) REFERENCES uploaded_reports(id)
-            )",
-            [],
-        )?;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TallyExport {
+    pub id: i32,
+    pub company_id: i32,
+    pub uploaded_report_id: i32,
+    pub generated_at: String,
+    pub file_path: String,
+    pub notes: Option<String>,
+}
 
-        // TallyExports table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS tally_exports (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                uploaded_report_id INTEGER NOT NULL,
-                generated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                file_path TEXT NOT NULL,
-                notes TEXT,
-                FOREIGN KEY (company_id) REFERENCES companies(id),
-                FOREIGN KEY (uploaded_report_id) REFERENCES uploaded_reports(id)
-            )",
-            [],
-        )?;
+impl TallyExport {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        Ok(TallyExport {
+            id: row.get(0)?,
+            company_id: row.get(1)?,
+            uploaded_report_id: row.get(2)?,
+            generated_at: row.get(3)?,
+            file_path: row.get(4)?,
+            notes: row.get(5)?,
+        })
+    }
+}
 
-        // AuditLogs table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS audit_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                user_action TEXT NOT NULL,
-                details_json TEXT NOT NULL,
-                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (company_id) REFERENCES companies(id)
-            )",
-            [],
-        )?;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub id: i32,
+    pub company_id: Option<i32>,
+    pub user_action: String,
+    pub details_json: String,
+    pub timestamp: String,
+}
 
-        // InvoiceMappings table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS invoice_mappings (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                company_id INTEGER NOT NULL,
-                original_invoice_no TEXT NOT NULL,
-                split_invoice_no TEXT NOT NULL,
-                gst_rate DECIMAL(5,2) NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (company_id) REFERENCES companies(id)
-            )",
-            [],
-        )?;
+#[derive(Debug, Deserialize)]
+pub struct AuditLogFilters {
+    pub company_id: Option<i32>,
+    pub user_action: Option<String>,
+    pub search: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+}
 
-        // Create indexes for better performance
-        self.create_indexes()?;
+/// An invoice whose GST type does not fit the company and customer states
+#[derive(Debug, Serialize)]
+pub struct PlaceOfSupplyIssue {
+    pub invno: String,
+    pub inv_date: String,
+    pub cust_name: String,
+    pub customer_state_code: String,
+    pub expected: TaxType,
+    pub message: String,
+}
 
-        Ok(())
-    }
+/// A customer in the sales data with no matching customer record
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnmappedCustomer {
+    pub cust_code: String,
+    pub cust_name: String,
+    pub invoice_count: i32,
+    pub line_count: i32,
+    pub total_inv_val: Money,
+}
 
-    fn create_indexes(&self) -> Result<()> {
-        // Indexes for sales_reports table
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_company_id ON sales_reports(company_id)",
-            [],
-        )?;
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceMapping {
+    pub id: i32,
+    pub company_id: i32,
+    pub original_invoice_no: String,
+    pub split_invoice_no: String,
+    pub gst_rate: f64,
+    pub created_at: String,
+}
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_inv_date ON sales_reports(inv_date)",
-            [],
-        )?;
+/// A file row that an import did not insert, with the original values of the row
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedRow {
+    pub row_number: usize,
+    pub field: String,
+    pub reason: String,
+    pub duplicate: bool,
+    pub values: std::collections::HashMap<String, String>,
+}
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_cust_code ON sales_reports(cust_code)",
-            [],
-        )?;
+/// How an import treats rows that cannot be inserted. Either way the import runs in
+/// one transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Skip rows that fail validation or whose insert fails, keep the rest
+    #[default]
+    BestEffort,
+    /// Import nothing unless every row is valid and inserts cleanly. Rows that are
+    /// duplicates of existing data are still skipped.
+    AllOrNothing,
+}
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_invno ON sales_reports(invno)",
-            [],
-        )?;
+/// Outcome of a CSV import: counts plus every rejected row
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub imported: i32,
+    pub skipped: i32,
+    pub duplicates: i32,
+    pub uploaded_report_id: Option<i32>,
+    /// Column mapping profile the rows were read with, chosen or detected
+    pub mapping_profile_id: Option<i32>,
+    /// Headers of the imported file, in file order
+    pub headers: Vec<String>,
+    pub rejected_rows: Vec<RejectedRow>,
+}
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_company_inv_date ON sales_reports(company_id, inv_date)",
-            [],
-        )?;
+impl ImportReport {
+    /// In all-or-nothing mode, fail on the first row that was rejected as invalid
+    fn check_mode(&self, mode: ImportMode) -> AppResult<()> {
+        if mode != ImportMode::AllOrNothing {
+            return Ok(());
+        }
+        match self.rejected_rows.iter().find(|r| !r.duplicate) {
+            Some(rejected) => Err(AppError::validation(
+                &rejected.field,
+                Some(rejected.row_number),
+                format!(
+                    "Row {}: {}. Nothing was imported",
+                    rejected.row_number, rejected.reason
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_sales_reports_company_cust ON sales_reports(company_id, cust_code)",
-            [],
-        )?;
+    fn reject(
+        &mut self,
+        row_number: usize,
+        field: &str,
+        reason: String,
+        fields: &[(String, String)],
+    ) {
+        println!("Skipping row {row_number}: {reason}");
+        self.skipped += 1;
+        self.push_rejected(row_number, field, reason, false, fields);
+    }
 
-        // Indexes for customers table
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_customers_company_id ON customers(company_id)",
-            [],
-        )?;
+    fn reject_duplicate(
+        &mut self,
+        row_number: usize,
+        field: &str,
+        reason: String,
+        fields: &[(String, String)],
+    ) {
+        println!("Skipping row {row_number}: {reason}");
+        self.duplicates += 1;
+        self.push_rejected(row_number, field, reason, true, fields);
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_customers_name ON customers(customer_name)",
-            [],
-        )?;
+    fn push_rejected(
+        &mut self,
+        row_number: usize,
+        field: &str,
+        reason: String,
+        duplicate: bool,
+        fields: &[(String, String)],
+    ) {
+        for (header, _) in fields {
+            if !self.headers.contains(header) {
+                self.headers.push(header.clone());
+            }
+        }
+        self.rejected_rows.push(RejectedRow {
+            row_number,
+            field: field.to_string(),
+            reason,
+            duplicate,
+            values: fields.iter().cloned().collect(),
+        });
+    }
 
-        // Indexes for categories table
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_categories_company_id ON categories(company_id)",
-            [],
-        )?;
+    /// Rejected rows as CSV with the file's own columns plus the row number, field and
+    /// reason, so the rows can be fixed and imported again. The extra columns are
+    /// ignored on import.
+    pub fn error_csv(&self) -> AppResult<String> {
+        let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new()));
 
-        println!("Database indexes created successfully");
-        Ok(())
-    }
+        let mut header_row = self.headers.clone();
+        header_row.extend(["error_row", "error_field", "error_reason"].map(String::from));
+        wtr.write_record(&header_row)?;
+
+        for rejected in &self.rejected_rows {
+            let mut record: Vec<String> = self
+                .headers
+                .iter()
+                .map(|h| rejected.values.get(h).cloned().unwrap_or_default())
+                .collect();
+            record.push(rejected.row_number.to_string());
+            record.push(rejected.field.clone());
+            record.push(rejected.reason.clone());
+            wtr.write_record(&record)?;
+        }
 
-    fn seed_initial_data(&self) -> Result<()> {
-        // Check if companies already exist
-        let count: i32 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM companies", [], |row| row.get(0))?;
+        wtr.flush()?;
+        Ok(String::from_utf8(wtr.into_inner()?.into_inner())?)
+    }
+}
 
-        if count == 0 {
-            // Insert Company A and Company B
-            self.conn.execute(
-                "INSERT INTO companies (name, key) VALUES (?, ?)",
-                ["Company A", "company_a"],
-            )?;
+/// The file header a field is read from; `header` is `None` when no alias matched
+#[derive(Debug, Serialize)]
+pub struct ColumnMapping {
+    pub field: String,
+    pub header: Option<String>,
+}
 
-            self.conn.execute(
-                "INSERT INTO companies (name, key) VALUES (?, ?)",
-                ["Company B", "company_b"],
-            )?;
+/// Result of a dry-run import: how the columns were resolved, the first normalized
+/// rows and the counts the real import would report. Nothing is written.
+#[derive(Debug, Serialize)]
+pub struct ImportPreview<T> {
+    pub column_mapping: Vec<ColumnMapping>,
+    /// File headers that no field maps from
+    pub unmapped_headers: Vec<String>,
+    pub rows: Vec<T>,
+    pub report: ImportReport,
+    /// Upload with the same content, which the real import would refuse without `force`
+    pub duplicate_upload_id: Option<i32>,
+}
 
-            // Insert sample customer for Company A
-            self.conn.execute(
-                "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, category)
-                 VALUES (?, ?, ?, ?, ?)",
-                rusqlite::params![
-                    1,
-                    "Sample Customer A",
-                    "SAMPLE_CUSTOMER_A",
-                    "22AAAAA0000A1Z5",
-                    "General",
-                ],
-            )?;
+/// A customer row as `import_customers_csv` would store it
+#[derive(Debug, Serialize)]
+pub struct CustomerImportRow {
+    pub customer_name: String,
+    pub tally_name: String,
+    pub gst_no: Option<String>,
+    pub gst_state_code: Option<String>,
+    pub category: Option<String>,
+}
 
-            println!("Initial data seeded successfully");
-        }
+/// A customer whose stored GSTIN does not validate
+#[derive(Debug, Serialize)]
+pub struct InvalidGstin {
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub gst_no: String,
+    pub message: String,
+}
 
-        Ok(())
-    }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportRow {
+    pub id: i32,
+    pub uploaded_report_id: i32,
+    pub row_number: i32,
+    pub invoice_no: String,
+    pub invoice_date: String,
+    pub customer_name_raw: String,
+    pub gst_rate: f64,
+    pub cgst_amt: Money,
+    pub sgst_amt: Money,
+    pub igst_amt: Money,
+    pub total_amount: Money,
+    pub extra_json: Option<String>,
+}
 
-    pub fn get_companies(&self) -> Result<Vec<Company>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, key, created_at FROM companies ORDER BY name")?;
+/// A broken reconciliation rule. The line is `None` when the invoice as a whole does
+/// not add up.
+#[derive(Debug, Serialize)]
+pub struct ReconciliationIssue {
+    pub sales_report_id: Option<i32>,
+    pub invno: String,
+    pub line_no: Option<i32>,
+    pub inv_date: String,
+    pub cust_name: String,
+    pub rule: Rule,
+    pub message: String,
+}
 
-        let companies = stmt.query_map([], |row| {
-            Ok(Company {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                key: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })?;
+// Header aliases accepted for each sales report field, in order of preference.
+// Headers are compared after `normalize_header`.
+const SALES_REPORT_FIELDS: &[(&str, &[&str])] = &[
+    ("cust_code", &["cust_code", "cust_cde", "customer_code"]),
+    ("cust_name", &["cust_name", "customer_name"]),
+    ("inv_date", &["inv_date", "io_date", "invoice_date"]),
+    ("RE", &["re", "region"]),
+    (
+        "invno",
+        &[
+            "invno",
+            "invoice_no",
+            "invoice_number",
+            "invoiceno",
+            "invoice",
+            "inv_no",
+            "inv_number",
+        ],
+    ),
+    ("part_code", &["part_code", "prod_cde", "product_code"]),
+    ("part_name", &["part_name", "prod_name_ko", "product_name"]),
+    ("tariff", &["tariff", "tariff_code"]),
+    ("qty", &["qty", "io_qty", "quantity"]),
+    ("bas_price", &["bas_price", "rate_pre_unit", "base_price"]),
+    ("ass_val", &["ass_val", "assessable_value"]),
+    ("c_gst", &["c_gst", "cgst_amt", "c gst"]),
+    ("s_gst", &["s_gst", "sgst_amt", "s gst"]),
+    ("igst", &["igst", "igst_amt"]),
+    ("amot", &["amot", "amortisation_cost", "total_amorization"]),
+    (
+        "inv_val",
+        &["inv_val", "total_inv_value", "invoice_total", "grand_total"],
+    ),
+    ("igst_yes_no", &["igst_yes_no", "igst_flag"]),
+    ("percentage", &["percentage", "gst_rate", "gst_percentage"]),
+    // Only used to derive igst_yes_no and percentage
+    ("cgst_rate", &["cgst_rate", "cgst_percentage"]),
+    ("sgst_rate", &["sgst_rate", "sgst_percentage"]),
+    ("igst_rate", &["igst_rate", "igst_percentage"]),
+];
+
+// Header aliases accepted for each customer field, in order of preference
+const CUSTOMER_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "customer_name",
+        &[
+            "customer_name",
+            "cust_name",
+            "client_name",
+            "company_name",
+            "name",
+        ],
+    ),
+    (
+        "tally_name",
+        &[
+            "tally_name",
+            "tally_code",
+            "customer_code",
+            "cust_code",
+            "code",
+        ],
+    ),
+    ("gst_no", &["gst_no", "gst_number", "gst", "tax_id", "tin"]),
+    (
+        "category",
+        &["category", "customer_category", "type", "group"],
+    ),
+];
+
+const DEFAULT_PREVIEW_ROWS: usize = 20;
+
+type FieldTable = &'static [(&'static str, &'static [&'static str])];
+
+/// Values per customer record, looked up the way `get_unmapped_customers` matches
+/// sales lines to customers
+#[derive(Default)]
+struct CustomerLookup<T> {
+    by_name: std::collections::HashMap<String, T>,
+    by_tally_name: std::collections::HashMap<String, T>,
+}
 
-        companies.collect()
+impl<T: Clone> CustomerLookup<T> {
+    fn key(name: &str) -> String {
+        name.trim().to_lowercase()
     }
 
-    // Clear all data from database (development utility)
-    pub fn clear_all_data(&self) -> Result<()> {
-        println!("Clearing all data from database...");
+    fn insert(&mut self, customer_name: &str, tally_name: &str, value: T) {
+        self.by_name.insert(Self::key(tally_name), value.clone());
+        self.by_tally_name
+            .insert(Self::key(tally_name), value.clone());
+        self.by_name.insert(Self::key(customer_name), value);
+    }
 
-        // Disable foreign key constraints temporarily
-        self.conn.execute("PRAGMA foreign_keys = OFF", [])?;
+    /// The line's cust_name against customer and tally names, else its cust_code
+    /// against tally names
+    fn get(&self, cust_name: &str, cust_code: &str) -> Option<&T> {
+        let code = Self::key(cust_code);
+        self.by_name.get(&Self::key(cust_name)).or_else(|| {
+            Some(code)
+                .filter(|c| !c.is_empty())
+                .and_then(|c| self.by_tally_name.get(&c))
+        })
+    }
+}
 
-        // Clear all tables in reverse dependency order to avoid foreign key constraints
-        self.conn.execute("DELETE FROM invoice_mappings", [])?;
-        self.conn.execute("DELETE FROM audit_logs", [])?;
-        self.conn.execute("DELETE FROM tally_exports", [])?;
-        self.conn.execute("DELETE FROM report_rows", [])?;
-        self.conn.execute("DELETE FROM uploaded_reports", [])?;
-        self.conn.execute("DELETE FROM sales_reports", [])?;
-        self.conn.execute("DELETE FROM customers", [])?;
-        self.conn.execute("DELETE FROM categories", [])?;
-        self.conn.execute("DELETE FROM companies", [])?;
+fn normalize_header(header: &str) -> String {
+    header.to_lowercase().replace(" ", "_")
+}
 
-        // Reset auto-increment counters
-        self.conn.execute("DELETE FROM sqlite_sequence", [])?;
+/// Header aliases per field, in order of preference. The headers of a column mapping
+/// profile come before the built-in aliases of a field.
+struct FieldAliases {
+    fields: Vec<(&'static str, Vec<String>)>,
+}
 
-        // Re-enable foreign key constraints
-        self.conn.execute("PRAGMA foreign_keys = ON", [])?;
+impl FieldAliases {
+    fn new(table: FieldTable, profile: Option<&ColumnMappingProfile>) -> Self {
+        let fields = table
+            .iter()
+            .map(|(field, aliases)| {
+                let mut headers: Vec<String> = profile
+                    .and_then(|p| p.mappings.get(*field))
+                    .cloned()
+                    .unwrap_or_default();
+                headers.extend(aliases.iter().map(|alias| alias.to_string()));
+                (*field, headers)
+            })
+            .collect();
+        FieldAliases { fields }
+    }
 
-        // Verify that customers table is empty
-        let customer_count: i32 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM customers", [], |row| row.get(0))?;
+    fn get(&self, field: &str) -> &[String] {
+        self.fields
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, aliases)| aliases.as_slice())
+            .unwrap_or_default()
+    }
 
-        println!("Customer records remaining after clear: {customer_count}");
-        println!("All data cleared successfully");
-        Ok(())
+    fn is_mapped(&self, header: &str) -> bool {
+        let header = normalize_header(header);
+        self.fields
+            .iter()
+            .flat_map(|(_, aliases)| aliases.iter())
+            .any(|alias| normalize_header(alias) == header)
     }
+}
 
-    pub fn update_company(&self, id: i32, company: UpdateCompanyRequest) -> Result<bool> {
-        let mut updates = Vec::new();
-        let mut params = Vec::new();
+/// Hash of an import's content that ignores formatting-only differences: header case
+/// and spacing, column order, blank rows and whitespace or quotes around values.
+fn content_hash<'a>(rows: impl IntoIterator<Item = &'a FileRow>) -> String {
+    let mut hasher = Sha256::new();
 
-        if let Some(name) = company.name {
-            updates.push("name = ?");
-            params.push(name);
-        }
-        if let Some(key) = company.key {
-            updates.push("key = ?");
-            params.push(key);
+    for fields in rows {
+        let mut cells: Vec<(String, &str)> = fields
+            .iter()
+            .map(|(header, value)| {
+                (
+                    normalize_header(header.trim()),
+                    value.trim_matches('"').trim(),
+                )
+            })
+            .collect();
+        if cells.iter().all(|(_, value)| value.is_empty()) {
+            continue;
         }
+        cells.sort();
 
-        if updates.is_empty() {
-            return Ok(false);
+        for (header, value) in cells {
+            hasher.update(header.as_bytes());
+            hasher.update([0x1f]);
+            hasher.update(value.as_bytes());
+            hasher.update([0x1f]);
         }
+        hasher.update([0x1e]);
+    }
 
-        params.push(id.to_string());
-        let sql = format!("UPDATE companies SET {} WHERE id = ?", updates.join(", "));
+    format!("{:x}", hasher.finalize())
+}
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows_affected = stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+/// A file row as (header, value) pairs, in file order
+type FileRow = Vec<(String, String)>;
 
-        Ok(rows_affected > 0)
+/// Headers and rows of a CSV file
+fn read_csv_rows(csv_data: &str) -> AppResult<(Vec<String>, Vec<FileRow>)> {
+    let mut reader = csv::Reader::from_reader(csv_data.as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+
+    let mut rows = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let fields: Vec<(String, String)> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(h, v)| (h.clone(), v.to_string()))
+            .collect();
+        rows.push(fields);
     }
+    Ok((headers, rows))
+}
 
-    pub fn get_customers_by_company(&self, company_id: i32) -> Result<Vec<Customer>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT c.id, c.company_id, c.customer_name, c.tally_name, c.gst_no, 
-                    c.category_id, cat.name as category_name, c.created_at 
-             FROM customers c 
-             LEFT JOIN categories cat ON c.category_id = cat.id 
-             WHERE c.company_id = ? 
-             ORDER BY c.customer_name",
-        )?;
+/// Which header each field is read from, plus the headers left unused
+fn resolve_columns(
+    aliases: &FieldAliases,
+    headers: &[String],
+) -> (Vec<ColumnMapping>, Vec<String>) {
+    let mut used = std::collections::HashSet::new();
+    let column_mapping = aliases
+        .fields
+        .iter()
+        .map(|(field, aliases)| {
+            let header = aliases.iter().find_map(|alias| {
+                headers
+                    .iter()
+                    .find(|h| normalize_header(h) == normalize_header(alias))
+            });
+            if let Some(header) = header {
+                used.insert(header.clone());
+            }
+            ColumnMapping {
+                field: field.to_string(),
+                header: header.cloned(),
+            }
+        })
+        .collect();
+    let unmapped_headers = headers
+        .iter()
+        .filter(|h| !used.contains(*h))
+        .cloned()
+        .collect();
+    (column_mapping, unmapped_headers)
+}
 
-        let customers = stmt.query_map([company_id], |row| {
-            Ok(Customer {
-                id: row.get(0)?,
-                company_id: row.get(1)?,
-                customer_name: row.get(2)?,
-                tally_name: row.get(3)?,
-                gst_no: row.get(4)?,
-                category_id: row.get(5)?,
-                category_name: row.get(6)?,
-                created_at: row.get(7)?,
-            })
-        })?;
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
 
-        customers.collect()
+/// Trimmed company name or key; neither may be empty
+fn company_name_field(field: &str, value: &str) -> AppResult<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::validation(
+            field,
+            None,
+            format!("Company {field} is required"),
+        ));
     }
+    Ok(value.to_string())
+}
 
-    pub fn search_customers(&self, company_id: i32, search_term: String) -> Result<Vec<Customer>> {
-        let search_pattern = format!("%{search_term}%");
-        let mut stmt = self.conn.prepare(
-            "SELECT c.id, c.company_id, c.customer_name, c.tally_name, c.gst_no, 
-                    c.category_id, cat.name as category_name, c.created_at
-             FROM customers c 
-             LEFT JOIN categories cat ON c.category_id = cat.id 
-             WHERE c.company_id = ?
-             AND (c.customer_name LIKE ? OR c.tally_name LIKE ? OR c.gst_no LIKE ? OR cat.name LIKE ?)
-             ORDER BY c.customer_name",
-        )?;
+fn check_daily_backups_kept(keep: usize) -> AppResult<()> {
+    if keep == 0 {
+        return Err(AppError::validation(
+            "daily_backups_kept",
+            None,
+            "Keep at least one daily backup",
+        ));
+    }
+    Ok(())
+}
 
-        let customers = stmt.query_map(
-            rusqlite::params![
-                company_id,
-                search_pattern,
-                search_pattern,
-                search_pattern,
-                search_pattern
-            ],
-            |row| {
-                Ok(Customer {
-                    id: row.get(0)?,
-                    company_id: row.get(1)?,
-                    customer_name: row.get(2)?,
-                    tally_name: row.get(3)?,
-                    gst_no: row.get(4)?,
-                    category_id: row.get(5)?,
-                    category_name: row.get(6)?,
-                    created_at: row.get(7)?,
-                })
-            },
-        )?;
+fn check_tolerance(tolerance: f64) -> AppResult<()> {
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err(AppError::validation(
+            "reconcile_tolerance",
+            None,
+            "Tolerance must be zero or a positive amount",
+        ));
+    }
+    Ok(())
+}
 
-        customers.collect()
+fn check_fiscal_year_start(month: u32) -> AppResult<()> {
+    if !(1..=12).contains(&month) {
+        return Err(AppError::validation(
+            "fiscal_year_start",
+            None,
+            format!("Fiscal year start must be a month from 1 to 12, got {month}"),
+        ));
     }
+    Ok(())
+}
 
-    pub fn create_customer(&self, customer: CreateCustomerRequest) -> Result<i32> {
-        let mut stmt = self.conn.prepare(
-            "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, category_id) 
-             VALUES (?, ?, ?, ?, ?)",
-        )?;
+/// New value of a nullable column: `None` leaves it, `Some(None)` clears it
+type ColumnChange = Option<Option<String>>;
+
+/// New `gstin` and `state_code` column values for a company. The state follows the
+/// GSTIN and can only be set on its own when it agrees with the new or `stored` GSTIN.
+fn company_gst_fields(
+    stored: Option<&str>,
+    gstin: Option<String>,
+    state_code: Option<String>,
+) -> AppResult<(ColumnChange, ColumnChange)> {
+    let new_gstin = gstin
+        .map(|gstin| parse_gst_no(Some(&gstin)))
+        .transpose()
+        .map_err(|message| AppError::validation("gstin", None, message))?;
+    let gstin_state = match &new_gstin {
+        Some(gstin) => gstin.as_ref().map(|g| g.state_code.clone()),
+        None => stored
+            .and_then(|gstin| gstin::parse(gstin).ok())
+            .map(|g| g.state_code),
+    };
+    let state_code = match state_code.as_deref().map(str::trim) {
+        Some("") => Some(gstin_state),
+        Some(code) => {
+            if gstin::state_name(code).is_none() {
+                return Err(AppError::validation(
+                    "state_code",
+                    None,
+                    format!("Unknown state code '{code}', expected 01 to 38"),
+                ));
+            }
+            if let Some(gstin_state) = gstin_state.filter(|state| state != code) {
+                return Err(AppError::validation(
+                    "state_code",
+                    None,
+                    format!("State code {code} does not match the GSTIN state {gstin_state}"),
+                ));
+            }
+            Some(Some(code.to_string()))
+        }
+        None if matches!(new_gstin, Some(Some(_))) => Some(gstin_state),
+        None => None,
+    };
+    Ok((new_gstin.map(|gstin| gstin.map(|g| g.gstin)), state_code))
+}
 
-        let id = stmt.insert(rusqlite::params![
-            customer.company_id,
-            customer.customer_name,
-            customer.tally_name,
-            customer.gst_no,
-            customer.category_id,
-        ])?;
+/// Validate an optional GSTIN. A blank value means the customer has none.
+fn parse_gst_no(gst_no: Option<&str>) -> std::result::Result<Option<gstin::Gstin>, String> {
+    match gst_no.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(gst_no) => gstin::parse(gst_no).map(Some),
+    }
+}
 
-        Ok(id as i32)
+/// Map and validate one sales file row. Errors carry the field and reason.
+fn normalize_sales_row(
+    company_id: i32,
+    aliases: &FieldAliases,
+    fields: &[(String, String)],
+) -> std::result::Result<CreateSalesReportRequest, (&'static str, String)> {
+    let row = SourceRow::new(aliases, fields);
+
+    let cust_code = row.get("cust_code");
+    let cust_name = row.get("cust_name");
+    let raw_inv_date = row.get("inv_date");
+    let inv_date = parse_date(&raw_inv_date).unwrap_or_default();
+    let invno = row.get("invno");
+
+    if invno.is_empty() {
+        return Err(("invno", "Missing invoice number".to_string()));
+    }
+    if cust_code.is_empty() && cust_name.is_empty() {
+        return Err(("cust_name", "Missing customer".to_string()));
+    }
+    if inv_date.is_empty() {
+        let reason = if raw_inv_date.is_empty() {
+            "Missing invoice date".to_string()
+        } else {
+            format!("Invalid date '{raw_inv_date}'")
+        };
+        return Err(("inv_date", reason));
     }
 
-    pub fn update_customer(&self, id: i32, customer: UpdateCustomerRequest) -> Result<bool> {
-        let mut updates = Vec::new();
-        let mut params = Vec::new();
+    let rates = GstRates {
+        cgst: parse_number(&row.get("cgst_rate")),
+        sgst: parse_number(&row.get("sgst_rate")),
+        igst: parse_number(&row.get("igst_rate")),
+    };
+    let ass_val = parse_money(&row.get("ass_val"));
+    let c_gst = parse_money(&row.get("c_gst"));
+    let s_gst = parse_money(&row.get("s_gst"));
+    let igst = parse_money(&row.get("igst"));
+    let (igst_yes_no, percentage) = gst_split::igst_flags(
+        rates,
+        parse_number(&row.get("percentage")),
+        ass_val,
+        c_gst,
+        s_gst,
+        igst,
+    );
+
+    Ok(CreateSalesReportRequest {
+        company_id,
+        cust_code: if cust_code.is_empty() {
+            cust_name.clone()
+        } else {
+            cust_code.clone()
+        },
+        cust_name: if cust_name.is_empty() {
+            cust_code
+        } else {
+            cust_name
+        },
+        inv_date,
+        RE: row.get("RE"),
+        invno,
+        line_no: None,
+        part_code: non_empty(row.get("part_code")),
+        part_name: non_empty(row.get("part_name")),
+        tariff: non_empty(row.get("tariff")),
+        // Numeric fields with currency symbol handling
+        qty: parse_number(&row.get("qty")),
+        bas_price: parse_number(&row.get("bas_price")),
+        ass_val,
+        c_gst,
+        s_gst,
+        igst,
+        amot: parse_money(&row.get("amot")),
+        inv_val: parse_money(&row.get("inv_val")),
+        igst_yes_no: igst_yes_no.to_string(),
+        percentage,
+    })
+}
 
-        if let Some(customer_name) = customer.customer_name {
-            updates.push("customer_name = ?");
-            params.push(customer_name);
-        }
-        if let Some(tally_name) = customer.tally_name {
-            updates.push("tally_name = ?");
-            params.push(tally_name);
-        }
-        if let Some(gst_no) = customer.gst_no {
-            updates.push("gst_no = ?");
-            params.push(gst_no);
-        }
-        if let Some(category_id) = customer.category_id {
-            updates.push("category_id = ?");
-            params.push(category_id.to_string());
-        }
+/// Map and validate one customer file row. Errors carry the field and reason.
+fn normalize_customer_row(
+    aliases: &FieldAliases,
+    fields: &[(String, String)],
+) -> std::result::Result<CustomerImportRow, (&'static str, String)> {
+    let row = SourceRow::new(aliases, fields);
+
+    let customer_name = row.get("customer_name");
+    let tally_name = row.get("tally_name");
+    if customer_name.is_empty() {
+        return Err(("customer_name", "Missing customer name".to_string()));
+    }
+    if tally_name.is_empty() {
+        return Err(("tally_name", "Missing tally name".to_string()));
+    }
 
-        if updates.is_empty() {
-            return Ok(false);
-        }
+    let gstin = parse_gst_no(Some(&row.get("gst_no"))).map_err(|message| ("gst_no", message))?;
 
-        params.push(id.to_string());
-        let sql = format!("UPDATE customers SET {} WHERE id = ?", updates.join(", "));
+    Ok(CustomerImportRow {
+        customer_name,
+        tally_name,
+        gst_state_code: gstin.as_ref().map(|g| g.state_code.clone()),
+        gst_no: gstin.map(|g| g.gstin),
+        category: non_empty(row.get("category")),
+    })
+}
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows_affected = stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+/// One row of an imported file, keyed by normalized header
+struct SourceRow<'a> {
+    aliases: &'a FieldAliases,
+    values: std::collections::HashMap<String, String>,
+}
 
-        Ok(rows_affected > 0)
+impl<'a> SourceRow<'a> {
+    fn new(aliases: &'a FieldAliases, row: &[(String, String)]) -> Self {
+        SourceRow {
+            aliases,
+            values: row
+                .iter()
+                .map(|(header, value)| (normalize_header(header), value.clone()))
+                .collect(),
+        }
     }
 
-    pub fn delete_customer(&self, id: i32) -> Result<bool> {
-        let mut stmt = self.conn.prepare("DELETE FROM customers WHERE id = ?")?;
-        let rows_affected = stmt.execute([id])?;
-        Ok(rows_affected > 0)
+    /// Value of a field via its header aliases, with surrounding quotes and
+    /// whitespace removed. Empty when no alias is present.
+    fn get(&self, field: &str) -> String {
+        for alias in self.aliases.get(field) {
+            if let Some(value) = self.values.get(&normalize_header(alias)) {
+                return value.trim_matches('"').trim().to_string();
+            }
+        }
+        String::new()
     }
+}
 
-    // Category management methods
-    pub fn get_categories_by_company(&self, company_id: i32) -> Result<Vec<Category>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, company_id, name, description, created_at 
-             FROM categories WHERE company_id = ? ORDER BY name",
-        )?;
-
-        let categories = stmt.query_map([company_id], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                company_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })?;
+fn validate_profile_name(name: &str) -> AppResult<()> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation(
+            "name",
+            None,
+            "Profile name is required",
+        ));
+    }
+    Ok(())
+}
 
-        categories.collect()
+// Mappings must name known sales report fields and non-empty headers
+fn validate_profile_mappings(
+    mappings: &std::collections::BTreeMap<String, Vec<String>>,
+) -> AppResult<()> {
+    for (field, headers) in mappings {
+        if !SALES_REPORT_FIELDS.iter().any(|(name, _)| name == field) {
+            return Err(AppError::validation(
+                "mappings",
+                None,
+                format!("Unknown sales report field '{field}'"),
+            ));
+        }
+        if headers.iter().any(|header| header.trim().is_empty()) {
+            return Err(AppError::validation(
+                "mappings",
+                None,
+                format!("Empty header name for field '{field}'"),
+            ));
+        }
     }
+    Ok(())
+}
 
-    pub fn create_category(&self, category: CreateCategoryRequest) -> Result<i32> {
-        let mut stmt = self.conn.prepare(
-            "INSERT INTO categories (company_id, name, description) 
-             VALUES (?, ?, ?)",
-        )?;
+/// Field to blame for a row whose insert failed: `key` when the row duplicates a
+/// unique key, the column SQLite names for any other constraint, else the whole row
+fn insert_error_field<'a>(error: &'a rusqlite::Error, key: &'a str) -> &'a str {
+    let rusqlite::Error::SqliteFailure(failure, Some(message)) = error else {
+        return "row";
+    };
+    if failure.code != rusqlite::ErrorCode::ConstraintViolation {
+        return "row";
+    }
+    match failure.extended_code {
+        rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE | rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+            key
+        }
+        // e.g. "NOT NULL constraint failed: customers.tally_name"
+        _ => message
+            .rsplit_once(": ")
+            .and_then(|(_, columns)| columns.rsplit(", ").next())
+            .and_then(|column| column.split_once('.'))
+            .map_or("row", |(_, column)| column),
+    }
+}
 
-        let id = stmt.insert(rusqlite::params![
-            category.company_id,
-            category.name,
-            category.description,
-        ])?;
+/// Reject a line that breaks a line rule, naming every broken rule. Invoice values are
+/// left to imports and the reconciliation scan, as an invoice entered line by line
+/// does not add up until its last line.
+fn check_amounts(amounts: reconcile::LineAmounts, tolerance: Money) -> AppResult<()> {
+    let issues = reconcile::check_line(amounts, tolerance);
+    match issues.first() {
+        None => Ok(()),
+        Some(first) => Err(AppError::validation(
+            first.rule.field(),
+            None,
+            issues
+                .iter()
+                .map(|issue| issue.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+        )),
+    }
+}
 
-        Ok(id as i32)
+/// Normalize YYYY-MM-DD, DD/MM/YYYY, DD-MM-YYYY or an Excel date serial to YYYY-MM-DD
+fn parse_date(date_str: &str) -> Option<String> {
+    if date_str.is_empty() {
+        return None;
     }
 
-    pub fn update_category(&self, id: i32, category: UpdateCategoryRequest) -> Result<bool> {
-        let mut updates = Vec::new();
-        let mut params = Vec::new();
+    // Try different date formats
+    let formats = [
+        ("YYYY-MM-DD", r"^(\d{4})-(\d{1,2})-(\d{1,2})$"),
+        ("DD/MM/YYYY", r"^(\d{1,2})/(\d{1,2})/(\d{4})$"),
+        ("DD-MM-YYYY", r"^(\d{1,2})-(\d{1,2})-(\d{4})$"),
+    ];
+
+    for (format_name, pattern) in &formats {
+        if let Some(captures) = regex::Regex::new(pattern).unwrap().captures(date_str) {
+            let (year, month, day) = if *format_name == "YYYY-MM-DD" {
+                (&captures[1], &captures[2], &captures[3])
+            } else {
+                (&captures[3], &captures[2], &captures[1])
+            };
 
-        if let Some(name) = category.name {
-            updates.push("name = ?");
-            params.push(name);
-        }
-        if let Some(description) = category.description {
-            updates.push("description = ?");
-            params.push(description);
+            // Validate date components
+            if let (Ok(y), Ok(m), Ok(d)) = (
+                year.parse::<i32>(),
+                month.parse::<i32>(),
+                day.parse::<i32>(),
+            ) {
+                if (1900..=2100).contains(&y) && (1..=12).contains(&m) && (1..=31).contains(&d) {
+                    return Some(format!("{y:04}-{m:02}-{d:02}"));
+                }
+            }
         }
+    }
 
-        if updates.is_empty() {
-            return Ok(false);
-        }
+    // Serials of unformatted Excel date cells. Only 1950-01-01 to 2100-12-31 is
+    // accepted, so that a bare year or a stray number is not taken for a date.
+    match date_str.parse::<f64>() {
+        Ok(serial) if (18_264.0..73_051.0).contains(&serial) => excel::serial_to_date(serial),
+        _ => None,
+    }
+}
 
-        params.push(id.to_string());
+// How far down a sheet the header row is looked for
+const HEADER_SCAN_ROWS: usize = 20;
+
+/// Index of the header row of a sheet: the row among the first `HEADER_SCAN_ROWS`
+/// with the most recognized sales headers, so title rows above the table are
+/// skipped. Falls back to the first non-empty row.
+fn detect_header_row(cells: &[Vec<String>], aliases: &FieldAliases) -> Option<usize> {
+    let recognized = |row: &Vec<String>| {
+        row.iter()
+            .filter(|cell| aliases.is_mapped(cell.trim()))
+            .count()
+    };
+
+    let best = cells
+        .iter()
+        .take(HEADER_SCAN_ROWS)
+        .enumerate()
+        .map(|(index, row)| (recognized(row), index))
+        .filter(|(count, _)| *count > 0)
+        // Earliest row wins a tie
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+        .map(|(_, index)| index);
+
+    best.or_else(|| {
+        cells
+            .iter()
+            .position(|row| row.iter().any(|cell| !cell.trim().is_empty()))
+    })
+}
 
-        let query = format!("UPDATE categories SET {} WHERE id = ?", updates.join(", "));
-        let mut stmt = self.conn.prepare(&query)?;
-        let rows_affected = stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+/// Parse an amount like `parse_number`, straight from its digits to paise. Only
+/// exponent notation goes through a float.
+fn parse_money(value: &str) -> Money {
+    let cleaned = clean_number(value);
+    Money::parse(&cleaned)
+        .or_else(|| {
+            cleaned
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(Money::from_rupees)
+        })
+        .unwrap_or(Money::ZERO)
+}
 
-        Ok(rows_affected > 0)
+/// Parse a number, ignoring currency symbols and thousands separators.
+/// Accounting-style negatives like `(1,200.00)` are supported; anything else is 0.
+fn parse_number(value: &str) -> f64 {
+    clean_number(value).parse().unwrap_or(0.0)
+}
+
+/// A number without currency symbols, thousands separators and spaces, with
+/// accounting-style parentheses turned into a minus sign
+fn clean_number(value: &str) -> String {
+    if value.is_empty() {
+        return String::new();
     }
 
-    pub fn delete_category(&self, id: i32) -> Result<bool> {
-        let mut stmt = self.conn.prepare("DELETE FROM categories WHERE id = ?")?;
-        let rows_affected = stmt.execute([id])?;
-        Ok(rows_affected > 0)
+    // Remove currency symbols, commas, and spaces
+    let cleaned = value
+        .replace("₹", "")
+        .replace("$", "")
+        .replace("€", "")
+        .replace("£", "")
+        .replace("¥", "")
+        .replace(",", "")
+        .replace(" ", "");
+
+    // Handle negative numbers in parentheses (accounting format)
+    if value.contains('(') && value.contains(')') {
+        format!("-{}", cleaned.replace("(", "").replace(")", ""))
+    } else {
+        cleaned
     }
+}
 
-    pub fn get_category_by_name(&self, company_id: i32, name: &str) -> Result<Option<Category>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, company_id, name, description, created_at 
-             FROM categories WHERE company_id = ? AND name = ?",
-        )?;
+pub struct DatabaseManager {
+    conn: Connection,
+    pending_clear: Option<PendingClear>,
+}
 
-        let mut rows = stmt.query_map(rusqlite::params![company_id, name], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                company_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })?;
+/// Environment variable that overrides the database location.
+pub const DB_PATH_ENV: &str = "SALES_REPORT_DB_PATH";
+const DB_FILE_NAME: &str = "sales_report.db";
+// Optional settings file in the app config directory, e.g.
+// {"database_path": "D:/data/sales.db", "daily_backups_kept": 14}
+const CONFIG_FILE_NAME: &str = "config.json";
+
+fn config_path(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    Ok(app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| AppError::Io {
+            message: format!("App config directory unavailable: {e}"),
+        })?
+        .join(CONFIG_FILE_NAME))
+}
 
-        rows.next().transpose()
+/// Settings from the app's config.json, or an empty object when there is none
+fn read_config(app_handle: &AppHandle) -> AppResult<serde_json::Value> {
+    let path = config_path(app_handle)?;
+    if !path.exists() {
+        return Ok(serde_json::json!({}));
     }
+    Ok(serde_json::from_str(&std::fs::read_to_string(&path)?)?)
+}
 
-    // Database migration method
-    fn migrate_database(&self) -> Result<()> {
-        // Check if the old 'category' column exists
-        let mut stmt = self
-            .conn
-            .prepare("SELECT COUNT(*) FROM pragma_table_info('customers') WHERE name='category'")?;
-        let old_column_exists: i32 = stmt.query_row([], |row| row.get(0))?;
+/// `daily_backups_kept` from the settings, else the default
+fn config_daily_backups_kept(config: &serde_json::Value) -> AppResult<usize> {
+    let Some(value) = config.get("daily_backups_kept") else {
+        return Ok(backup::DEFAULT_DAILY_BACKUPS_KEPT);
+    };
+    let keep = value.as_u64().ok_or_else(|| {
+        AppError::validation(
+            "daily_backups_kept",
+            None,
+            format!("Daily backups kept must be a whole number, not {value}"),
+        )
+    })?;
+    let keep = usize::try_from(keep).unwrap_or(usize::MAX);
+    check_daily_backups_kept(keep)?;
+    Ok(keep)
+}
 
-        if old_column_exists > 0 {
-            // Migration needed: old schema exists
-            println!("Migrating database from old schema to new schema...");
+/// How many daily backups to keep, as set in the app's config.json
+pub fn daily_backups_kept(app_handle: &AppHandle) -> AppResult<usize> {
+    config_daily_backups_kept(&read_config(app_handle)?)
+}
 
-            // Add category_id column if it doesn't exist
-            self.conn
-                .execute("ALTER TABLE customers ADD COLUMN category_id INTEGER", [])
-                .ok(); // Ignore error if column already exists
+/// Save how many daily backups to keep in the app's config.json, leaving its other
+/// settings as they are
+pub fn set_daily_backups_kept(app_handle: &AppHandle, keep: usize) -> AppResult<()> {
+    check_daily_backups_kept(keep)?;
+    let mut config = read_config(app_handle)?;
+    let Some(settings) = config.as_object_mut() else {
+        return Err(AppError::validation(
+            "daily_backups_kept",
+            None,
+            format!("{CONFIG_FILE_NAME} does not hold a settings object"),
+        ));
+    };
+    settings.insert("daily_backups_kept".to_string(), keep.into());
+
+    let path = config_path(app_handle)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&config)?)?;
+    Ok(())
+}
 
-            // Migrate existing category data to categories table
-            let mut stmt = self.conn.prepare(
-                "SELECT DISTINCT company_id, category FROM customers WHERE category IS NOT NULL AND category != ''"
-            )?;
+/// Resolve where the database lives: the `SALES_REPORT_DB_PATH` environment variable,
+/// then `database_path` in the app's `config.json`, then the app data directory.
+/// Relative overrides are taken relative to the app data directory.
+fn resolve_db_path(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    let app_data_dir = app_handle.path().app_data_dir().map_err(|e| AppError::Io {
+        message: format!("App data directory unavailable: {e}"),
+    })?;
+
+    if let Ok(path) = std::env::var(DB_PATH_ENV) {
+        if !path.trim().is_empty() {
+            return Ok(app_data_dir.join(path.trim()));
+        }
+    }
 
-            let category_rows = stmt.query_map([], |row| {
-                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
-            })?;
+    let config = read_config(app_handle)?;
+    if let Some(path) = config.get("database_path").and_then(|v| v.as_str()) {
+        if !path.trim().is_empty() {
+            return Ok(app_data_dir.join(path.trim()));
+        }
+    }
 
-            for row_result in category_rows {
-                let (company_id, category_name) = row_result?;
+    Ok(app_data_dir.join(DB_FILE_NAME))
+}
 
-                // Check if category already exists
-                let mut check_stmt = self
-                    .conn
-                    .prepare("SELECT id FROM categories WHERE company_id = ? AND name = ?")?;
-                let existing_category: Result<i32, _> = check_stmt
-                    .query_row(rusqlite::params![company_id, category_name], |row| {
-                        row.get(0)
-                    });
+/// Move a database left at `legacy` to `target` unless `target` already exists.
+/// SQLite side files (journal, WAL) travel with it. Returns whether anything moved.
+fn move_legacy_database(legacy: &Path, target: &Path) -> std::io::Result<bool> {
+    if target.exists() || !legacy.is_file() {
+        return Ok(false);
+    }
 
-                let category_id = match existing_category {
-                    Ok(id) => id,
-                    Err(_) => {
-                        // Create new category
-                        let mut insert_stmt = self.conn.prepare(
-                            "INSERT INTO categories (company_id, name, description) VALUES (?, ?, ?)"
-                        )?;
-                        insert_stmt.insert(rusqlite::params![
-                            company_id,
-                            category_name,
-                            None::<String>
-                        ])? as i32
-                    }
-                };
+    for suffix in ["", "-journal", "-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{suffix}", legacy.display()));
+        let to = PathBuf::from(format!("{}{suffix}", target.display()));
+        if !from.exists() {
+            continue;
+        }
 
-                // Update customers with the new category_id
-                let mut update_stmt = self.conn.prepare(
-                    "UPDATE customers SET category_id = ? WHERE company_id = ? AND category = ?",
-                )?;
-                update_stmt.execute(rusqlite::params![category_id, company_id, category_name])?;
-            }
+        // rename fails across drives, fall back to copy + delete
+        if std::fs::rename(&from, &to).is_err() {
+            std::fs::copy(&from, &to)?;
+            std::fs::remove_file(&from)?;
+        }
+    }
+
+    Ok(true)
+}
+
+impl DatabaseManager {
+    pub fn new(app_handle: &AppHandle) -> AppResult<Self> {
+        let db_path = resolve_db_path(app_handle)?;
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
 
-            // Drop the old category column
-            // Note: SQLite doesn't support DROP COLUMN directly, so we'll leave it for now
-            // The new code will use category_id instead
-            println!("Database migration completed successfully!");
+        // Earlier versions kept the database in the working directory
+        let legacy_path = std::env::current_dir()?.join(DB_FILE_NAME);
+        if move_legacy_database(&legacy_path, &db_path)? {
+            println!(
+                "Moved database from {} to {}",
+                legacy_path.display(),
+                db_path.display()
+            );
         }
 
-        Ok(())
+        println!("Opening database at {}", db_path.display());
+        let conn = Connection::open(&db_path)?;
+
+        let db = Self::from_connection(conn)?;
+        let keep = daily_backups_kept(app_handle).unwrap_or_else(|e| {
+            println!("Ignoring daily backup setting: {e}");
+            backup::DEFAULT_DAILY_BACKUPS_KEPT
+        });
+        match db.daily_backup(keep) {
+            Ok(Some(path)) => println!("Saved daily backup to {}", path.display()),
+            Ok(None) => {}
+            // The app stays usable without its daily backup
+            Err(e) => println!("Daily backup failed: {e}"),
+        }
+        Ok(db)
     }
 
-    pub fn export_customers_csv(&self, company_id: i32) -> Result<String> {
-        let customers = self.get_customers_by_company(company_id)?;
+    fn from_connection(conn: Connection) -> AppResult<Self> {
+        migrations::run(&conn)?;
 
-        let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new()));
+        Ok(DatabaseManager {
+            conn,
+            pending_clear: None,
+        })
+    }
 
-        // Write header
-        wtr.write_record([
-            "Customer Name",
-            "Tally Name",
-            "GST No",
-            "Category",
-            "Created At",
-        ])
-        .map_err(|e| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some(format!("CSV write error: {e}")),
-            )
-        })?;
+    /// File the database was opened from (`None` for in-memory databases)
+    pub fn database_path(&self) -> Option<String> {
+        self.conn.path().map(|p| p.to_string())
+    }
 
-        // Write data
-        for customer in customers {
-            wtr.write_record([
-                &customer.customer_name,
-                &customer.tally_name,
-                customer.gst_no.as_deref().unwrap_or(""),
-                customer.category_name.as_deref().unwrap_or(""),
-                &customer.created_at,
-            ])
-            .map_err(|e| {
-                rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("CSV write error: {e}")),
-                )
-            })?;
+    /// Add two sample companies and a sample customer to a database without companies
+    /// (development utility). Returns whether anything was added.
+    pub fn seed_sample_data(&self) -> Result<bool> {
+        let count: i32 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM companies", [], |row| row.get(0))?;
+
+        if count == 0 {
+            // Insert Company A and Company B
+            for (name, key) in [("Company A", "company_a"), ("Company B", "company_b")] {
+                self.conn.execute(
+                    "INSERT INTO companies (name, key) VALUES (?, ?)",
+                    [name, key],
+                )?;
+                self.insert_default_year_codes(self.conn.last_insert_rowid() as i32)?;
+            }
+
+            // Insert sample customer for Company A
+            self.conn.execute(
+                "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, gst_state_code)
+                 VALUES (?, ?, ?, ?, ?)",
+                rusqlite::params![
+                    1,
+                    "Sample Customer A",
+                    "SAMPLE_CUSTOMER_A",
+                    "22AAAAA0000A1ZC",
+                    "22",
+                ],
+            )?;
+
+            println!("Initial data seeded successfully");
         }
 
-        wtr.flush().map_err(|e| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some(format!("CSV flush error: {e}")),
-            )
-        })?;
+        Ok(count == 0)
+    }
 
-        let data = wtr.into_inner().map_err(|e| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some(format!("CSV inner error: {e}")),
-            )
-        })?;
+    pub fn get_companies(&self, include_archived: bool) -> Result<Vec<Company>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {COMPANY_COLUMNS} FROM companies
+             WHERE ?1 OR archived_at IS NULL
+             ORDER BY name"
+        ))?;
 
-        let csv_string = String::from_utf8(data.into_inner()).map_err(|e| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some(format!("UTF-8 error: {e}")),
-            )
-        })?;
+        let companies = stmt.query_map([include_archived], Company::from_row)?;
+        companies.collect()
+    }
 
-        Ok(csv_string)
+    pub fn create_company(&self, company: CreateCompanyRequest) -> AppResult<i32> {
+        let name = company_name_field("name", &company.name)?;
+        let key = company_name_field("key", &company.key)?;
+        if let Some(tolerance) = company.reconcile_tolerance {
+            check_tolerance(tolerance)?;
+        }
+        let fiscal_year_start = company.fiscal_year_start.unwrap_or(4);
+        check_fiscal_year_start(fiscal_year_start)?;
+        let (gstin, state_code) = company_gst_fields(None, company.gstin, company.state_code)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "INSERT INTO companies
+                 (name, key, gstin, state_code, address, fiscal_year_start, reconcile_tolerance)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                name,
+                key,
+                gstin.flatten(),
+                state_code.flatten(),
+                company
+                    .address
+                    .and_then(|a| non_empty(a.trim().to_string())),
+                fiscal_year_start,
+                company
+                    .reconcile_tolerance
+                    .unwrap_or(reconcile::DEFAULT_TOLERANCE),
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid() as i32;
+        self.insert_default_year_codes(id)?;
+
+        let after = self.get_company(id)?;
+        self.log_change(Some(id), "create_company", id, None, after.as_ref())?;
+        tx.commit()?;
+        Ok(id)
     }
 
-    pub fn import_customers_csv(&self, company_id: i32, csv_data: String) -> Result<i32> {
-        let mut rdr = csv::Reader::from_reader(csv_data.as_bytes());
-        let mut imported_count = 0;
-        let mut skipped_count = 0;
-        let mut duplicate_count = 0;
+    /// Hide a company from the company list without deleting anything
+    pub fn archive_company(&self, id: i32) -> AppResult<bool> {
+        self.set_company_archived(id, true)
+    }
 
-        // Read headers first
-        let headers: Vec<String> = rdr
-            .headers()
-            .map_err(|e| {
-                rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("CSV header error: {e}")),
-                )
-            })?
-            .iter()
-            .map(|h| h.to_string())
-            .collect();
-        println!("Customer CSV Headers: {headers:?}");
+    pub fn unarchive_company(&self, id: i32) -> AppResult<bool> {
+        self.set_company_archived(id, false)
+    }
 
-        // Create header mapping for flexible column access
-        let header_map: std::collections::HashMap<String, usize> = headers
-            .iter()
-            .enumerate()
-            .map(|(i, h)| (h.to_lowercase().replace(" ", "_"), i))
-            .collect();
+    fn set_company_archived(&self, id: i32, archived: bool) -> AppResult<bool> {
+        let before = self.require_company(id)?;
+        if before.archived_at.is_some() == archived {
+            return Ok(false);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "UPDATE companies
+             SET archived_at = CASE WHEN ?1 THEN CURRENT_TIMESTAMP END
+             WHERE id = ?2",
+            rusqlite::params![archived, id],
+        )?;
 
-        println!("Customer Header mapping: {header_map:?}");
+        let after = self.get_company(id)?;
+        let action = if archived {
+            "archive_company"
+        } else {
+            "unarchive_company"
+        };
+        self.log_change(Some(id), action, id, Some(&before), after.as_ref())?;
+        tx.commit()?;
+        Ok(true)
+    }
 
-        for (row_num, result) in rdr.records().enumerate() {
-            let record = result.map_err(|e| {
-                rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("CSV read error: {e}")),
-                )
-            })?;
-            let row_index = row_num + 2; // +2 because we're 0-indexed and skipped header row
+    fn company_row_counts(&self, id: i32) -> Result<Vec<TableRowCount>> {
+        COMPANY_DATA_TABLES
+            .iter()
+            .map(|(table, condition)| self.count_rows(table, condition, &[&id]))
+            .collect()
+    }
 
-            println!(
-                "Processing customer row {} with {} columns",
-                row_index,
-                record.len()
-            );
+    fn count_rows(
+        &self,
+        table: &str,
+        condition: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<TableRowCount> {
+        let rows = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM {table} WHERE {condition}"),
+            params,
+            |row| row.get(0),
+        )?;
+        Ok(TableRowCount {
+            table: table.to_string(),
+            rows,
+        })
+    }
 
-            // Helper function to get field value by header name and clean it
-            let get_field = |field_names: &[&str]| -> String {
-                for field_name in field_names {
-                    if let Some(&col_index) =
-                        header_map.get(&field_name.to_lowercase().replace(" ", "_"))
-                    {
-                        if col_index < record.len() {
-                            let value = record.get(col_index).unwrap_or("").to_string();
-                            // Remove surrounding quotes and trim whitespace
-                            return value.trim_matches('"').trim().to_string();
-                        }
-                    }
-                }
-                String::new()
-            };
+    /// Row counts per table that `delete_company` would remove
+    pub fn company_deletion_preview(&self, id: i32) -> AppResult<CompanyDeletionPreview> {
+        let company = self.require_company(id)?;
+        let tables = self.company_row_counts(id)?;
+        Ok(CompanyDeletionPreview {
+            total_rows: tables.iter().map(|t| t.rows).sum(),
+            company,
+            tables,
+        })
+    }
 
-            // Extract fields using flexible header mapping
-            let customer_name = get_field(&[
-                "customer_name",
-                "cust_name",
-                "client_name",
-                "company_name",
-                "name",
-            ]);
-            let tally_name = get_field(&[
-                "tally_name",
-                "tally_code",
-                "customer_code",
-                "cust_code",
-                "code",
-            ]);
-            let gst_no = get_field(&["gst_no", "gst_number", "gst", "tax_id", "tin"]);
-            let category_name = get_field(&["category", "customer_category", "type", "group"]);
-
-            // Validate required fields
-            if customer_name.is_empty() {
-                println!("Skipping row {row_index}: Missing customer name");
-                skipped_count += 1;
-                continue;
-            }
+    /// Delete a company and all of its data. The company must be archived first and
+    /// `confirm_key` must repeat its key. The audit trail is kept, detached from the
+    /// company. Returns the rows deleted per table.
+    pub fn delete_company(&self, id: i32, confirm_key: &str) -> AppResult<Vec<TableRowCount>> {
+        let company = self.require_company(id)?;
+        if company.archived_at.is_none() {
+            return Err(AppError::validation(
+                "archived_at",
+                None,
+                format!("Archive {} before deleting it", company.name),
+            ));
+        }
+        if confirm_key != company.key {
+            return Err(AppError::validation(
+                "confirm_key",
+                None,
+                format!("Type the company key '{}' to confirm deletion", company.key),
+            ));
+        }
 
-            if tally_name.is_empty() {
-                println!("Skipping row {row_index}: Missing tally name");
-                skipped_count += 1;
-                continue;
-            }
+        let tx = self.conn.unchecked_transaction()?;
+        let tables = self.company_row_counts(id)?;
+        for (table, condition) in COMPANY_DATA_TABLES {
+            self.conn
+                .execute(&format!("DELETE FROM {table} WHERE {condition}"), [id])?;
+        }
+        self.conn.execute(
+            "UPDATE audit_logs SET company_id = NULL WHERE company_id = ?",
+            [id],
+        )?;
+        self.conn
+            .execute("DELETE FROM companies WHERE id = ?", [id])?;
 
-            // Check if customer already exists
-            let existing_count: i32 = self.conn.query_row(
-                "SELECT COUNT(*) FROM customers WHERE company_id = ? AND customer_name = ?",
-                rusqlite::params![company_id, customer_name],
-                |row| row.get(0),
-            )?;
+        let deleted: serde_json::Map<String, serde_json::Value> = tables
+            .iter()
+            .map(|t| (t.table.clone(), t.rows.into()))
+            .collect();
+        self.log_action(
+            None,
+            "delete_company",
+            serde_json::json!({ "company": company, "deleted": deleted }),
+        )?;
+        tx.commit()?;
+        Ok(tables)
+    }
 
-            if existing_count > 0 {
-                println!("Skipping row {row_index}: Customer '{customer_name}' already exists");
-                duplicate_count += 1;
-                continue;
+    /// Check a clear scope, normalizing its dates
+    fn check_clear_scope(&self, scope: ClearScope) -> AppResult<ClearScope> {
+        match scope {
+            ClearScope::Everything => Ok(scope),
+            ClearScope::Company { company_id } => {
+                self.require_company(company_id)?;
+                Ok(scope)
             }
-
-            // Handle category - create if it doesn't exist
-            let mut category_id: Option<i32> = None;
-            if !category_name.is_empty() {
-                // Check if category exists
-                if let Some(existing_category) =
-                    self.get_category_by_name(company_id, &category_name)?
-                {
-                    category_id = Some(existing_category.id);
-                } else {
-                    // Create new category
-                    let new_category = CreateCategoryRequest {
-                        company_id,
-                        name: category_name.clone(),
-                        description: None,
-                    };
-                    category_id = Some(self.create_category(new_category)?);
+            ClearScope::Sales {
+                company_id,
+                date_from,
+                date_to,
+            } => {
+                if let Some(id) = company_id {
+                    self.require_company(id)?;
+                }
+                let date = |field: &str, value: Option<String>| match value {
+                    Some(value) if !value.trim().is_empty() => {
+                        parse_date(value.trim()).map(Some).ok_or_else(|| {
+                            AppError::validation(field, None, format!("Invalid date '{value}'"))
+                        })
+                    }
+                    _ => Ok(None),
+                };
+                let date_from = date("date_from", date_from)?;
+                let date_to = date("date_to", date_to)?;
+                if let (Some(from), Some(to)) = (&date_from, &date_to) {
+                    if from > to {
+                        return Err(AppError::validation(
+                            "date_to",
+                            None,
+                            format!("Date range ends ({to}) before it starts ({from})"),
+                        ));
+                    }
                 }
+                Ok(ClearScope::Sales {
+                    company_id,
+                    date_from,
+                    date_to,
+                })
             }
+        }
+    }
 
-            // Insert new customer
-            let mut stmt = self.conn.prepare(
-                "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, category_id) 
-                 VALUES (?, ?, ?, ?, ?)",
-            )?;
+    fn clear_row_counts(&self, scope: &ClearScope) -> Result<Vec<TableRowCount>> {
+        let (tables, params) = clear::plan(scope);
+        let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        tables
+            .iter()
+            .map(|(table, condition)| self.count_rows(table, condition, &params))
+            .collect()
+    }
 
-            stmt.execute(rusqlite::params![
-                company_id,
-                customer_name,
-                tally_name,
-                if gst_no.is_empty() {
-                    None
-                } else {
-                    Some(gst_no)
-                },
-                category_id,
-            ])?;
+    /// Row counts per table that clearing `scope` would remove, and the token
+    /// `clear_data` needs to go ahead. Only the latest token is valid.
+    pub fn prepare_clear_data(&mut self, scope: ClearScope) -> AppResult<ClearDataPreview> {
+        let scope = self.check_clear_scope(scope)?;
+        let tables = self.clear_row_counts(&scope)?;
+        let token = clear::token(&scope);
+        self.pending_clear = Some(PendingClear {
+            token: token.clone(),
+            scope: scope.clone(),
+            issued_at: std::time::Instant::now(),
+        });
+        Ok(ClearDataPreview {
+            total_rows: tables.iter().map(|t| t.rows).sum(),
+            scope,
+            tables,
+            token,
+        })
+    }
 
-            imported_count += 1;
-            println!("Successfully imported customer '{customer_name}' from row {row_index}");
+    /// Delete the rows of `scope`, confirmed by the token `prepare_clear_data` issued
+    /// for the same scope. A snapshot of the database is taken first; restoring it with
+    /// `restore_backup` undoes the clear. The audit trail is kept.
+    pub fn clear_data(&mut self, scope: ClearScope, token: &str) -> AppResult<ClearDataResult> {
+        let scope = self.check_clear_scope(scope)?;
+        match self.pending_clear.take() {
+            Some(pending)
+                if pending.token == token
+                    && pending.scope == scope
+                    && pending.issued_at.elapsed() <= clear::TOKEN_TTL => {}
+            _ => {
+                return Err(AppError::validation(
+                    "token",
+                    None,
+                    "The confirmation is invalid or has expired, preview the clear again",
+                ))
+            }
         }
 
+        let backup_path = self.snapshot(BackupKind::BeforeClear)?;
         println!(
-            "Customer import completed: {imported_count} imported, {skipped_count} skipped, {duplicate_count} duplicates"
+            "Saved a backup to {} before clearing",
+            backup_path.display()
         );
 
-        Ok(imported_count)
-    }
+        let tx = self.conn.unchecked_transaction()?;
+        let deleted = self.clear_row_counts(&scope)?;
+        let (tables, params) = clear::plan(&scope);
+        for (table, condition) in &tables {
+            self.conn.execute(
+                &format!("DELETE FROM {table} WHERE {condition}"),
+                rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            )?;
+        }
+        let company_id = match scope {
+            ClearScope::Everything => {
+                // The audit trail survives, detached from the deleted companies
+                self.conn
+                    .execute("UPDATE audit_logs SET company_id = NULL", [])?;
+                None
+            }
+            ClearScope::Company { company_id } => Some(company_id),
+            ClearScope::Sales { company_id, .. } => company_id,
+        };
 
-    // Sales Report methods
-    pub fn get_sales_reports_by_company(&self, company_id: i32) -> Result<Vec<SalesReport>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, company_id, cust_code, cust_name, inv_date, RE, invno, 
-                    part_code, part_name, tariff, qty, bas_price, ass_val, 
-                    c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at 
-             FROM sales_reports 
-             WHERE company_id = ? 
-             ORDER BY inv_date DESC, invno",
+        let counts: serde_json::Map<String, serde_json::Value> = deleted
+            .iter()
+            .map(|t| (t.table.clone(), t.rows.into()))
+            .collect();
+        self.log_action(
+            company_id,
+            "clear_data",
+            serde_json::json!({
+                "scope": scope,
+                "deleted": counts,
+                "backup_path": backup_path,
+            }),
         )?;
+        tx.commit()?;
 
-        let rows = stmt.query_map([company_id], |row| {
-            Ok(SalesReport {
-                id: row.get(0)?,
-                company_id: row.get(1)?,
-                cust_code: row.get(2)?,
-                cust_name: row.get(3)?,
-                inv_date: row.get(4)?,
-                RE: row.get(5)?,
-                invno: row.get(6)?,
-                part_code: row.get(7)?,
-                part_name: row.get(8)?,
-                tariff: row.get(9)?,
-                qty: row.get(10)?,
-                bas_price: row.get(11)?,
-                ass_val: row.get(12)?,
-                c_gst: row.get(13)?,
-                s_gst: row.get(14)?,
-                igst: row.get(15)?,
-                amot: row.get(16)?,
-                inv_val: row.get(17)?,
-                igst_yes_no: row.get(18)?,
-                percentage: row.get(19)?,
-                created_at: row.get(20)?,
+        Ok(ClearDataResult {
+            deleted,
+            backup_path: backup_path.display().to_string(),
+        })
+    }
+
+    fn backup_dir(&self) -> AppResult<PathBuf> {
+        self.database_path()
+            .filter(|path| !path.is_empty())
+            .and_then(|path| Path::new(&path).parent().map(|dir| dir.join(BACKUP_DIR)))
+            .ok_or_else(|| {
+                AppError::validation("backup", None, "An in-memory database cannot be backed up")
             })
-        })?;
+    }
 
-        let mut reports = Vec::new();
-        for row in rows {
-            reports.push(row?);
-        }
+    /// Copy the database into the backups folder with SQLite's online backup, named
+    /// after `kind` and the current time
+    fn snapshot(&self, kind: BackupKind) -> AppResult<PathBuf> {
+        let label = kind.label();
+        let dir = self.backup_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        let stamp: String = self.conn.query_row(
+            "SELECT strftime('%Y%m%d-%H%M%S', 'now', 'localtime')",
+            [],
+            |row| row.get(0),
+        )?;
 
-        Ok(reports)
+        let mut path = dir.join(format!("{label}-{stamp}.db"));
+        let mut n = 1;
+        while path.exists() {
+            n += 1;
+            path = dir.join(format!("{label}-{stamp}-{n}.db"));
+        }
+        self.conn.backup(rusqlite::MAIN_DB, &path, None)?;
+        Ok(path)
     }
 
-    // Paginated sales reports with filtering
-    pub fn get_sales_reports_paginated(
-        &self,
-        company_id: i32,
-        page: i32,
-        page_size: i32,
-        filters: Option<SalesReportFilters>,
-    ) -> Result<(Vec<SalesReport>, i32)> {
-        let offset = (page - 1) * page_size;
-
-        // Build WHERE clause based on filters
-        let mut where_clauses = vec!["company_id = ?".to_string()];
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(company_id)];
+    fn backup_info(&self, path: &Path) -> AppResult<BackupInfo> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let modified_at =
+            self.conn
+                .query_row("SELECT datetime(?, 'unixepoch')", [modified], |row| {
+                    row.get(0)
+                })?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(BackupInfo {
+            kind: backup::kind(&file_name),
+            file_name,
+            path: path.display().to_string(),
+            size_bytes: metadata.len(),
+            modified_at,
+        })
+    }
 
-        if let Some(f) = filters {
-            if let Some(date_from) = f.date_from {
-                where_clauses.push("inv_date >= ?".to_string());
-                params.push(Box::new(date_from));
-            }
-            if let Some(date_to) = f.date_to {
-                where_clauses.push("inv_date <= ?".to_string());
-                params.push(Box::new(date_to));
-            }
-            // Universal search - if both customer and invoice are the same, search across all fields
-            if let (Some(customer), Some(invoice)) = (&f.customer, &f.invoice) {
-                if customer == invoice && !customer.is_empty() {
-                    where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ? OR invno LIKE ? OR part_name LIKE ? OR part_code LIKE ?)".to_string());
-                    let search_pattern = format!("%{customer}%");
-                    params.push(Box::new(search_pattern.clone()));
-                    params.push(Box::new(search_pattern.clone()));
-                    params.push(Box::new(search_pattern.clone()));
-                    params.push(Box::new(search_pattern.clone()));
-                    params.push(Box::new(search_pattern));
-                } else {
-                    // Individual field searches
-                    if !customer.is_empty() {
-                        where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ?)".to_string());
-                        let customer_pattern = format!("%{customer}%");
-                        params.push(Box::new(customer_pattern.clone()));
-                        params.push(Box::new(customer_pattern));
-                    }
-                    if !invoice.is_empty() {
-                        where_clauses.push("invno LIKE ?".to_string());
-                        params.push(Box::new(format!("%{invoice}%")));
-                    }
-                }
-            } else {
-                // Individual field searches when only one is provided
-                if let Some(customer) = &f.customer {
-                    if !customer.is_empty() {
-                        where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ?)".to_string());
-                        let customer_pattern = format!("%{customer}%");
-                        params.push(Box::new(customer_pattern.clone()));
-                        params.push(Box::new(customer_pattern));
-                    }
-                }
-                if let Some(invoice) = &f.invoice {
-                    if !invoice.is_empty() {
-                        where_clauses.push("invno LIKE ?".to_string());
-                        params.push(Box::new(format!("%{invoice}%")));
-                    }
-                }
-            }
-            if let Some(min_amount) = f.min_amount {
-                where_clauses.push("inv_val >= ?".to_string());
-                params.push(Box::new(min_amount));
-            }
-            if let Some(max_amount) = f.max_amount {
-                where_clauses.push("inv_val <= ?".to_string());
-                params.push(Box::new(max_amount));
+    /// Write a consistent copy of the open database to `path` with SQLite's online
+    /// backup, replacing any database already there
+    pub fn backup_database(&self, path: &Path) -> AppResult<BackupInfo> {
+        if !path.is_absolute() {
+            return Err(AppError::validation(
+                "path",
+                None,
+                format!("Choose a full path for the backup, not {}", path.display()),
+            ));
+        }
+        if let Some(current) = self.database_path().filter(|p| !p.is_empty()) {
+            let same_file = std::fs::canonicalize(path)
+                .ok()
+                .zip(std::fs::canonicalize(current).ok())
+                .is_some_and(|(a, b)| a == b);
+            if same_file {
+                return Err(AppError::validation(
+                    "path",
+                    None,
+                    "A backup cannot overwrite the open database",
+                ));
             }
         }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
 
-        let where_clause = where_clauses.join(" AND ");
-
-        // Get total count
-        let count_sql = format!("SELECT COUNT(*) FROM sales_reports WHERE {where_clause}");
-        let mut count_stmt = self.conn.prepare(&count_sql)?;
-        let total_count: i32 = count_stmt.query_row(
-            rusqlite::params_from_iter(params.iter().map(|v| v.as_ref())),
-            |row| row.get(0),
+        self.conn.backup(rusqlite::MAIN_DB, path, None)?;
+        let info = self.backup_info(path)?;
+        self.log_action(
+            None,
+            "backup_database",
+            serde_json::json!({ "path": info.path, "size_bytes": info.size_bytes }),
         )?;
+        Ok(info)
+    }
 
-        // Get paginated data
-        let data_sql = format!(
-            "SELECT id, company_id, cust_code, cust_name, inv_date, RE, invno, 
-                    part_code, part_name, tariff, qty, bas_price, ass_val, 
-                    c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at 
-             FROM sales_reports 
-             WHERE {where_clause} 
-             ORDER BY inv_date DESC, invno 
-             LIMIT ? OFFSET ?"
-        );
+    /// Take today's backup in the backups folder unless it exists, then delete daily
+    /// backups beyond the newest `keep`, which must be at least 1. Returns the new
+    /// backup's path.
+    pub fn daily_backup(&self, keep: usize) -> AppResult<Option<PathBuf>> {
+        check_daily_backups_kept(keep)?;
+        let dir = self.backup_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        let today: String = self
+            .conn
+            .query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))?;
 
-        let mut stmt = self.conn.prepare(&data_sql)?;
-        let mut final_params = params;
-        final_params.push(Box::new(page_size));
-        final_params.push(Box::new(offset));
+        let path = dir.join(backup::daily_file_name(&today));
+        let taken = !path.exists();
+        if taken {
+            self.conn.backup(rusqlite::MAIN_DB, &path, None)?;
+        }
 
-        let rows = stmt.query_map(
-            rusqlite::params_from_iter(final_params.iter().map(|v| v.as_ref())),
-            |row| {
-                Ok(SalesReport {
-                    id: row.get(0)?,
-                    company_id: row.get(1)?,
-                    cust_code: row.get(2)?,
-                    cust_name: row.get(3)?,
-                    inv_date: row.get(4)?,
-                    RE: row.get(5)?,
-                    invno: row.get(6)?,
-                    part_code: row.get(7)?,
-                    part_name: row.get(8)?,
-                    tariff: row.get(9)?,
-                    qty: row.get(10)?,
-                    bas_price: row.get(11)?,
-                    ass_val: row.get(12)?,
-                    c_gst: row.get(13)?,
-                    s_gst: row.get(14)?,
-                    igst: row.get(15)?,
-                    amot: row.get(16)?,
-                    inv_val: row.get(17)?,
-                    igst_yes_no: row.get(18)?,
-                    percentage: row.get(19)?,
-                    created_at: row.get(20)?,
-                })
-            },
-        )?;
+        // Rotate even without a new backup, so a lower setting applies right away
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            names.push(entry?.file_name().to_string_lossy().to_string());
+        }
+        for name in backup::expired_daily(&names, keep) {
+            std::fs::remove_file(dir.join(&name))?;
+            println!("Removed old daily backup {name}");
+        }
+        Ok(taken.then_some(path))
+    }
 
-        let mut reports = Vec::new();
-        for row in rows {
-            reports.push(row?);
+    /// Backups in the backups folder next to the database, newest first
+    pub fn list_backups(&self) -> AppResult<Vec<BackupInfo>> {
+        let dir = self.backup_dir()?;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
         }
 
-        Ok((reports, total_count))
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "db") {
+                backups.push(self.backup_info(&path)?);
+            }
+        }
+        backups.sort_by(|a, b| {
+            b.modified_at
+                .cmp(&a.modified_at)
+                .then_with(|| b.file_name.cmp(&a.file_name))
+        });
+        Ok(backups)
     }
 
-    pub fn search_sales_reports(
-        &self,
-        company_id: i32,
-        search_term: String,
-    ) -> Result<Vec<SalesReport>> {
-        let search_pattern = format!("%{search_term}%");
-        let mut stmt = self.conn.prepare(
-            "SELECT id, company_id, cust_code, cust_name, inv_date, RE, invno, 
-                    part_code, part_name, tariff, qty, bas_price, ass_val, 
-                    c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at 
-             FROM sales_reports 
-             WHERE company_id = ? AND (
-                 cust_code LIKE ? OR 
-                 cust_name LIKE ? OR 
-                 invno LIKE ? OR 
-                 part_code LIKE ? OR 
-                 part_name LIKE ?
-             )
-             ORDER BY inv_date DESC, invno",
+    /// Replace the contents of the database with a backup, in place. The backup must
+    /// be a database of this app no newer than this build; older ones are migrated.
+    /// The current contents are saved as a backup first.
+    pub fn restore_backup(&mut self, path: &Path) -> AppResult<()> {
+        if !path.is_file() {
+            return Err(AppError::not_found(format!(
+                "Backup {} not found",
+                path.display()
+            )));
+        }
+
+        let version = {
+            let backup =
+                Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            let is_app_database = backup
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM sqlite_master
+                     WHERE type = 'table' AND name = 'companies'",
+                    [],
+                    |row| row.get::<_, bool>(0),
+                )
+                .unwrap_or(false);
+            if !is_app_database {
+                return Err(AppError::validation(
+                    "path",
+                    None,
+                    format!("{} is not a sales report database", path.display()),
+                ));
+            }
+            migrations::current_version(&backup)?
+        };
+        let latest = migrations::latest_version();
+        if version > latest {
+            return Err(AppError::SchemaTooNew {
+                message: format!(
+                    "The backup has schema version {version}, newer than this app supports \
+                     ({latest}); update the app to restore it"
+                ),
+                version,
+                supported: latest,
+            });
+        }
+
+        let previous = self.snapshot(BackupKind::BeforeRestore)?;
+        println!("Saved a backup to {} before restoring", previous.display());
+        self.conn.restore(
+            rusqlite::MAIN_DB,
+            path,
+            None::<fn(rusqlite::backup::Progress)>,
         )?;
+        migrations::run(&self.conn)?;
+        self.pending_clear = None;
 
-        let rows = stmt.query_map(
+        self.log_action(
+            None,
+            "restore_backup",
+            serde_json::json!({ "path": path, "user_version": version }),
+        )?;
+        println!("Restored database from {}", path.display());
+        Ok(())
+    }
+
+    pub fn update_company(&self, id: i32, company: UpdateCompanyRequest) -> AppResult<bool> {
+        let Some(before) = self.get_company(id)? else {
+            return Ok(false);
+        };
+        let mut updates = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(name) = company.name {
+            updates.push("name = ?");
+            params.push(Box::new(company_name_field("name", &name)?));
+        }
+        if let Some(key) = company.key {
+            updates.push("key = ?");
+            params.push(Box::new(company_name_field("key", &key)?));
+        }
+        if let Some(tolerance) = company.reconcile_tolerance {
+            check_tolerance(tolerance)?;
+            updates.push("reconcile_tolerance = ?");
+            params.push(Box::new(tolerance));
+        }
+
+        let (gstin, state_code) =
+            company_gst_fields(before.gstin.as_deref(), company.gstin, company.state_code)?;
+        if let Some(gstin) = gstin {
+            updates.push("gstin = ?");
+            params.push(Box::new(gstin));
+        }
+        if let Some(state_code) = state_code {
+            updates.push("state_code = ?");
+            params.push(Box::new(state_code));
+        }
+        if let Some(address) = company.address {
+            updates.push("address = ?");
+            params.push(Box::new(non_empty(address.trim().to_string())));
+        }
+        if let Some(month) = company.fiscal_year_start {
+            check_fiscal_year_start(month)?;
+            updates.push("fiscal_year_start = ?");
+            params.push(Box::new(month));
+        }
+
+        if updates.is_empty() {
+            return Ok(false);
+        }
+
+        params.push(Box::new(id));
+        let sql = format!("UPDATE companies SET {} WHERE id = ?", updates.join(", "));
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows_affected = stmt.execute(rusqlite::params_from_iter(
+            params.iter().map(|p| p.as_ref()),
+        ))?;
+
+        let after = self.get_company(id)?;
+        self.log_change(
+            Some(id),
+            "update_company",
+            id,
+            Some(&before),
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    pub fn get_customers_by_company(&self, company_id: i32) -> Result<Vec<Customer>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {CUSTOMER_COLUMNS}
+             FROM customers c 
+             LEFT JOIN categories cat ON c.category_id = cat.id 
+             WHERE c.company_id = ? 
+             ORDER BY c.customer_name"
+        ))?;
+
+        let customers = stmt.query_map([company_id], Customer::from_row)?;
+
+        customers.collect()
+    }
+
+    pub fn search_customers(&self, company_id: i32, search_term: String) -> Result<Vec<Customer>> {
+        let search_pattern = format!("%{search_term}%");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {CUSTOMER_COLUMNS}
+             FROM customers c 
+             LEFT JOIN categories cat ON c.category_id = cat.id 
+             WHERE c.company_id = ?
+             AND (c.customer_name LIKE ? OR c.tally_name LIKE ? OR c.gst_no LIKE ? OR cat.name LIKE ?)
+             ORDER BY c.customer_name"
+        ))?;
+
+        let customers = stmt.query_map(
             rusqlite::params![
                 company_id,
                 search_pattern,
                 search_pattern,
                 search_pattern,
-                search_pattern,
                 search_pattern
             ],
-            |row| {
-                Ok(SalesReport {
-                    id: row.get(0)?,
-                    company_id: row.get(1)?,
-                    cust_code: row.get(2)?,
-                    cust_name: row.get(3)?,
-                    inv_date: row.get(4)?,
-                    RE: row.get(5)?,
-                    invno: row.get(6)?,
-                    part_code: row.get(7)?,
-                    part_name: row.get(8)?,
-                    tariff: row.get(9)?,
-                    qty: row.get(10)?,
-                    bas_price: row.get(11)?,
-                    ass_val: row.get(12)?,
-                    c_gst: row.get(13)?,
-                    s_gst: row.get(14)?,
-                    igst: row.get(15)?,
-                    amot: row.get(16)?,
-                    inv_val: row.get(17)?,
-                    igst_yes_no: row.get(18)?,
-                    percentage: row.get(19)?,
-                    created_at: row.get(20)?,
-                })
-            },
+            Customer::from_row,
         )?;
 
-        let mut reports = Vec::new();
-        for row in rows {
-            reports.push(row?);
-        }
-
-        Ok(reports)
+        customers.collect()
     }
 
-    pub fn create_sales_report(&self, report: CreateSalesReportRequest) -> Result<i32> {
+    pub fn create_customer(&self, customer: CreateCustomerRequest) -> AppResult<i32> {
+        let gstin = parse_gst_no(customer.gst_no.as_deref())
+            .map_err(|message| AppError::validation("gst_no", None, message))?;
+
+        let tx = self.conn.unchecked_transaction()?;
         let mut stmt = self.conn.prepare(
-            "INSERT INTO sales_reports (
-                company_id, cust_code, cust_name, inv_date, RE, invno, 
-                part_code, part_name, tariff, qty, bas_price, ass_val, 
-                c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, gst_state_code, category_id) 
+             VALUES (?, ?, ?, ?, ?, ?)",
         )?;
 
-        stmt.execute(rusqlite::params![
-            report.company_id,
-            report.cust_code,
-            report.cust_name,
-            report.inv_date,
-            report.RE,
-            report.invno,
-            report.part_code,
-            report.part_name,
-            report.tariff,
-            report.qty,
-            report.bas_price,
-            report.ass_val,
-            report.c_gst,
-            report.s_gst,
-            report.igst,
-            report.amot,
-            report.inv_val,
-            report.igst_yes_no,
-            report.percentage,
+        let id = stmt.insert(rusqlite::params![
+            customer.company_id,
+            customer.customer_name,
+            customer.tally_name,
+            gstin.as_ref().map(|g| &g.gstin),
+            gstin.as_ref().map(|g| &g.state_code),
+            customer.category_id,
         ])?;
 
-        Ok(self.conn.last_insert_rowid() as i32)
+        let id = id as i32;
+        let after = self.get_customer(id)?;
+        self.log_change(
+            Some(customer.company_id),
+            "create_customer",
+            id,
+            None,
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(id)
     }
 
-    pub fn update_sales_report(&self, id: i32, report: UpdateSalesReportRequest) -> Result<bool> {
-        let mut fields = Vec::new();
-        let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    pub fn update_customer(&self, id: i32, customer: UpdateCustomerRequest) -> AppResult<bool> {
+        let mut updates = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
-        if let Some(cust_code) = report.cust_code {
-            fields.push("cust_code = ?");
-            values.push(Box::new(cust_code));
-        }
-        if let Some(cust_name) = report.cust_name {
-            fields.push("cust_name = ?");
-            values.push(Box::new(cust_name));
-        }
-        if let Some(inv_date) = report.inv_date {
-            fields.push("inv_date = ?");
-            values.push(Box::new(inv_date));
-        }
-        if let Some(re) = report.RE {
-            fields.push("RE = ?");
-            values.push(Box::new(re));
-        }
-        if let Some(invno) = report.invno {
-            fields.push("invno = ?");
-            values.push(Box::new(invno));
-        }
-        if let Some(part_code) = report.part_code {
-            fields.push("part_code = ?");
-            values.push(Box::new(part_code));
-        }
-        if let Some(part_name) = report.part_name {
-            fields.push("part_name = ?");
-            values.push(Box::new(part_name));
-        }
-        if let Some(tariff) = report.tariff {
-            fields.push("tariff = ?");
-            values.push(Box::new(tariff));
+        if let Some(customer_name) = customer.customer_name {
+            updates.push("customer_name = ?");
+            params.push(Box::new(customer_name));
         }
-        if let Some(qty) = report.qty {
-            fields.push("qty = ?");
-            values.push(Box::new(qty));
+        if let Some(tally_name) = customer.tally_name {
+            updates.push("tally_name = ?");
+            params.push(Box::new(tally_name));
         }
-        if let Some(bas_price) = report.bas_price {
-            fields.push("bas_price = ?");
-            values.push(Box::new(bas_price));
+        if let Some(gst_no) = customer.gst_no {
+            // An empty GSTIN clears it
+            let gstin = parse_gst_no(Some(&gst_no))
+                .map_err(|message| AppError::validation("gst_no", None, message))?;
+            updates.push("gst_no = ?");
+            updates.push("gst_state_code = ?");
+            params.push(Box::new(gstin.as_ref().map(|g| g.gstin.clone())));
+            params.push(Box::new(gstin.map(|g| g.state_code)));
         }
-        if let Some(ass_val) = report.ass_val {
-            fields.push("ass_val = ?");
-            values.push(Box::new(ass_val));
+        if let Some(category_id) = customer.category_id {
+            updates.push("category_id = ?");
+            params.push(Box::new(category_id));
         }
-        if let Some(c_gst) = report.c_gst {
-            fields.push("c_gst = ?");
-            values.push(Box::new(c_gst));
+
+        if updates.is_empty() {
+            return Ok(false);
         }
-        if let Some(s_gst) = report.s_gst {
-            fields.push("s_gst = ?");
-            values.push(Box::new(s_gst));
+        let Some(before) = self.get_customer(id)? else {
+            return Ok(false);
+        };
+
+        params.push(Box::new(id));
+        let sql = format!("UPDATE customers SET {} WHERE id = ?", updates.join(", "));
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows_affected = stmt.execute(rusqlite::params_from_iter(
+            params.iter().map(|p| p.as_ref()),
+        ))?;
+
+        let after = self.get_customer(id)?;
+        self.log_change(
+            Some(before.company_id),
+            "update_customer",
+            id,
+            Some(&before),
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Customers of a company whose GSTIN does not validate, e.g. entered before
+    /// validation existed. Customers without a GSTIN are not listed.
+    pub fn find_invalid_gstins(&self, company_id: i32) -> AppResult<Vec<InvalidGstin>> {
+        self.require_company(company_id)?;
+        let mut invalid = Vec::new();
+        for customer in self.get_customers_by_company(company_id)? {
+            let Some(gst_no) = customer.gst_no else {
+                continue;
+            };
+            if let Err(message) = parse_gst_no(Some(&gst_no)) {
+                invalid.push(InvalidGstin {
+                    customer_id: customer.id,
+                    customer_name: customer.customer_name,
+                    gst_no,
+                    message,
+                });
+            }
         }
-        if let Some(igst) = report.igst {
-            fields.push("igst = ?");
-            values.push(Box::new(igst));
+        Ok(invalid)
+    }
+
+    pub fn delete_customer(&self, id: i32) -> Result<bool> {
+        let Some(before) = self.get_customer(id)? else {
+            return Ok(false);
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare("DELETE FROM customers WHERE id = ?")?;
+        let rows_affected = stmt.execute([id])?;
+
+        self.log_change::<Customer>(
+            Some(before.company_id),
+            "delete_customer",
+            id,
+            Some(&before),
+            None,
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    // Category management methods
+    pub fn get_categories_by_company(&self, company_id: i32) -> Result<Vec<Category>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, name, description, created_at 
+             FROM categories WHERE company_id = ? ORDER BY name",
+        )?;
+
+        let categories = stmt.query_map([company_id], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                company_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+
+        categories.collect()
+    }
+
+    pub fn create_category(&self, category: CreateCategoryRequest) -> Result<i32> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO categories (company_id, name, description) 
+             VALUES (?, ?, ?)",
+        )?;
+
+        let id = stmt.insert(rusqlite::params![
+            category.company_id,
+            category.name,
+            category.description,
+        ])?;
+
+        let id = id as i32;
+        let after = self.get_category(id)?;
+        self.log_change(
+            Some(category.company_id),
+            "create_category",
+            id,
+            None,
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn update_category(&self, id: i32, category: UpdateCategoryRequest) -> Result<bool> {
+        let mut updates = Vec::new();
+        let mut params = Vec::new();
+
+        if let Some(name) = category.name {
+            updates.push("name = ?");
+            params.push(name);
         }
-        if let Some(amot) = report.amot {
-            fields.push("amot = ?");
-            values.push(Box::new(amot));
+        if let Some(description) = category.description {
+            updates.push("description = ?");
+            params.push(description);
         }
-        if let Some(inv_val) = report.inv_val {
-            fields.push("inv_val = ?");
-            values.push(Box::new(inv_val));
+
+        if updates.is_empty() {
+            return Ok(false);
+        }
+        let Some(before) = self.get_category(id)? else {
+            return Ok(false);
+        };
+
+        params.push(id.to_string());
+
+        let query = format!("UPDATE categories SET {} WHERE id = ?", updates.join(", "));
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows_affected = stmt.execute(rusqlite::params_from_iter(params.iter()))?;
+
+        let after = self.get_category(id)?;
+        self.log_change(
+            Some(before.company_id),
+            "update_category",
+            id,
+            Some(&before),
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    pub fn delete_category(&self, id: i32) -> Result<bool> {
+        let Some(before) = self.get_category(id)? else {
+            return Ok(false);
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare("DELETE FROM categories WHERE id = ?")?;
+        let rows_affected = stmt.execute([id])?;
+
+        self.log_change::<Category>(
+            Some(before.company_id),
+            "delete_category",
+            id,
+            Some(&before),
+            None,
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    pub fn get_category_by_name(&self, company_id: i32, name: &str) -> Result<Option<Category>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, name, description, created_at 
+             FROM categories WHERE company_id = ? AND name = ?",
+        )?;
+
+        let mut rows = stmt.query_map(rusqlite::params![company_id, name], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                company_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+
+        rows.next().transpose()
+    }
+
+    pub fn export_customers_csv(&self, company_id: i32) -> AppResult<String> {
+        let customers = self.get_customers_by_company(company_id)?;
+
+        let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new()));
+
+        // Write header
+        wtr.write_record([
+            "Customer Name",
+            "Tally Name",
+            "GST No",
+            "Category",
+            "Created At",
+        ])?;
+
+        // Write data
+        for customer in customers {
+            wtr.write_record([
+                &customer.customer_name,
+                &customer.tally_name,
+                customer.gst_no.as_deref().unwrap_or(""),
+                customer.category_name.as_deref().unwrap_or(""),
+                &customer.created_at,
+            ])?;
+        }
+
+        wtr.flush()?;
+
+        let data = wtr.into_inner()?;
+
+        let csv_string = String::from_utf8(data.into_inner())?;
+
+        Ok(csv_string)
+    }
+
+    /// Write the customers of a company to an .xlsx file, returning the number written
+    pub fn export_customers_xlsx(&self, company_id: i32, path: &Path) -> AppResult<usize> {
+        let customers = self.get_customers_by_company(company_id)?;
+        xlsx::customers_workbook(&customers)?.save(path)?;
+        Ok(customers.len())
+    }
+
+    pub fn import_customers_csv(
+        &self,
+        company_id: i32,
+        csv_data: String,
+        mode: ImportMode,
+    ) -> AppResult<ImportReport> {
+        let (headers, rows) = read_csv_rows(&csv_data)?;
+        println!("Customer CSV Headers: {headers:?}");
+        let mut report = ImportReport {
+            headers,
+            ..Default::default()
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.process_customer_rows(company_id, &rows, mode, &mut report, |customer| {
+            self.with_savepoint(|| self.insert_imported_customer(company_id, customer))
+        })?;
+        report.check_mode(mode)?;
+
+        println!(
+            "Customer import completed: {} imported, {} skipped, {} duplicates",
+            report.imported, report.skipped, report.duplicates
+        );
+        self.log_action(
+            Some(company_id),
+            "import_customers_csv",
+            serde_json::json!({
+                "imported": report.imported,
+                "skipped": report.skipped,
+                "duplicates": report.duplicates,
+                "mode": mode,
+            }),
+        )?;
+        tx.commit()?;
+
+        Ok(report)
+    }
+
+    fn insert_imported_customer(&self, company_id: i32, customer: CustomerImportRow) -> Result<()> {
+        // Handle category - create if it doesn't exist
+        let mut category_id: Option<i32> = None;
+        if let Some(category_name) = &customer.category {
+            if let Some(existing_category) = self.get_category_by_name(company_id, category_name)? {
+                category_id = Some(existing_category.id);
+            } else {
+                let new_category = CreateCategoryRequest {
+                    company_id,
+                    name: category_name.clone(),
+                    description: None,
+                };
+                category_id = Some(self.create_category(new_category)?);
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO customers (company_id, customer_name, tally_name, gst_no, gst_state_code, category_id) 
+                 VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        stmt.execute(rusqlite::params![
+            company_id,
+            customer.customer_name,
+            customer.tally_name,
+            customer.gst_no,
+            customer.gst_state_code,
+            category_id,
+        ])?;
+        Ok(())
+    }
+
+    /// Dry run of `import_customers_csv`: the resolved columns, the first rows as they
+    /// would be stored and the predicted counts. Nothing is written.
+    pub fn preview_customers_csv(
+        &self,
+        company_id: i32,
+        csv_data: String,
+        preview_rows: Option<usize>,
+    ) -> AppResult<ImportPreview<CustomerImportRow>> {
+        let (headers, rows) = read_csv_rows(&csv_data)?;
+        let limit = preview_rows.unwrap_or(DEFAULT_PREVIEW_ROWS);
+        let (column_mapping, unmapped_headers) =
+            resolve_columns(&FieldAliases::new(CUSTOMER_FIELDS, None), &headers);
+        let mut report = ImportReport {
+            headers,
+            ..Default::default()
+        };
+
+        let mut preview = Vec::new();
+        self.process_customer_rows(
+            company_id,
+            &rows,
+            ImportMode::BestEffort,
+            &mut report,
+            |customer| {
+                if preview.len() < limit {
+                    preview.push(customer);
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(ImportPreview {
+            column_mapping,
+            unmapped_headers,
+            rows: preview,
+            report,
+            duplicate_upload_id: None,
+        })
+    }
+
+    /// Validate customer rows and skip names that already exist, in the database or
+    /// earlier in the file. Each accepted row is passed to `accept` and counted; when
+    /// `accept` fails, best-effort mode rejects the row and all-or-nothing returns the error.
+    fn process_customer_rows(
+        &self,
+        company_id: i32,
+        rows: &[FileRow],
+        mode: ImportMode,
+        report: &mut ImportReport,
+        mut accept: impl FnMut(CustomerImportRow) -> Result<()>,
+    ) -> Result<()> {
+        let aliases = FieldAliases::new(CUSTOMER_FIELDS, None);
+        let mut seen_names = std::collections::HashSet::new();
+
+        for (row_num, fields) in rows.iter().enumerate() {
+            let row_index = row_num + 2; // +2 because we're 0-indexed and skipped header row
+            println!(
+                "Processing customer row {} with {} columns",
+                row_index,
+                fields.len()
+            );
+
+            let customer = match normalize_customer_row(&aliases, fields) {
+                Ok(customer) => customer,
+                Err((field, reason)) => {
+                    report.reject(row_index, field, reason, fields);
+                    continue;
+                }
+            };
+
+            // Check if customer already exists
+            let existing_count: i32 = self.conn.query_row(
+                "SELECT COUNT(*) FROM customers WHERE company_id = ? AND customer_name = ?",
+                rusqlite::params![company_id, customer.customer_name],
+                |row| row.get(0),
+            )?;
+
+            if existing_count > 0 || !seen_names.insert(customer.customer_name.clone()) {
+                report.reject_duplicate(
+                    row_index,
+                    "customer_name",
+                    format!("Customer '{}' already exists", customer.customer_name),
+                    fields,
+                );
+                continue;
+            }
+
+            let customer_name = customer.customer_name.clone();
+            if let Err(e) = accept(customer) {
+                if mode == ImportMode::AllOrNothing {
+                    return Err(e);
+                }
+                let field = insert_error_field(&e, "customer_name");
+                report.reject(
+                    row_index,
+                    field,
+                    format!("Could not insert row ({e})"),
+                    fields,
+                );
+                continue;
+            }
+            report.imported += 1;
+            println!("Accepted customer '{customer_name}' from row {row_index}");
+        }
+
+        Ok(())
+    }
+
+    // Sales Report methods
+    pub fn get_sales_reports_by_company(&self, company_id: i32) -> Result<Vec<SalesReport>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SALES_REPORT_COLUMNS}
+             FROM sales_reports 
+             WHERE company_id = ? 
+             ORDER BY inv_date DESC, invno, line_no"
+        ))?;
+
+        let rows = stmt.query_map([company_id], SalesReport::from_row)?;
+
+        let mut reports = Vec::new();
+        for row in rows {
+            reports.push(row?);
+        }
+
+        Ok(reports)
+    }
+
+    // Paginated sales reports with filtering
+    pub fn get_sales_reports_paginated(
+        &self,
+        company_id: i32,
+        page: i32,
+        page_size: i32,
+        filters: Option<SalesReportFilters>,
+    ) -> Result<(Vec<SalesReport>, i32)> {
+        let offset = (page - 1) * page_size;
+
+        // Build WHERE clause based on filters
+        let mut where_clauses = vec!["company_id = ?".to_string()];
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(company_id)];
+
+        if let Some(f) = filters {
+            if let Some(date_from) = f.date_from {
+                where_clauses.push("inv_date >= ?".to_string());
+                params.push(Box::new(date_from));
+            }
+            if let Some(date_to) = f.date_to {
+                where_clauses.push("inv_date <= ?".to_string());
+                params.push(Box::new(date_to));
+            }
+            // Universal search - if both customer and invoice are the same, search across all fields
+            if let (Some(customer), Some(invoice)) = (&f.customer, &f.invoice) {
+                if customer == invoice && !customer.is_empty() {
+                    where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ? OR invno LIKE ? OR part_name LIKE ? OR part_code LIKE ?)".to_string());
+                    let search_pattern = format!("%{customer}%");
+                    params.push(Box::new(search_pattern.clone()));
+                    params.push(Box::new(search_pattern.clone()));
+                    params.push(Box::new(search_pattern.clone()));
+                    params.push(Box::new(search_pattern.clone()));
+                    params.push(Box::new(search_pattern));
+                } else {
+                    // Individual field searches
+                    if !customer.is_empty() {
+                        where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ?)".to_string());
+                        let customer_pattern = format!("%{customer}%");
+                        params.push(Box::new(customer_pattern.clone()));
+                        params.push(Box::new(customer_pattern));
+                    }
+                    if !invoice.is_empty() {
+                        where_clauses.push("invno LIKE ?".to_string());
+                        params.push(Box::new(format!("%{invoice}%")));
+                    }
+                }
+            } else {
+                // Individual field searches when only one is provided
+                if let Some(customer) = &f.customer {
+                    if !customer.is_empty() {
+                        where_clauses.push("(cust_name LIKE ? OR cust_code LIKE ?)".to_string());
+                        let customer_pattern = format!("%{customer}%");
+                        params.push(Box::new(customer_pattern.clone()));
+                        params.push(Box::new(customer_pattern));
+                    }
+                }
+                if let Some(invoice) = &f.invoice {
+                    if !invoice.is_empty() {
+                        where_clauses.push("invno LIKE ?".to_string());
+                        params.push(Box::new(format!("%{invoice}%")));
+                    }
+                }
+            }
+            if let Some(min_amount) = f.min_amount {
+                where_clauses.push("inv_val >= ?".to_string());
+                params.push(Box::new(Money::from_rupees(min_amount)));
+            }
+            if let Some(max_amount) = f.max_amount {
+                where_clauses.push("inv_val <= ?".to_string());
+                params.push(Box::new(Money::from_rupees(max_amount)));
+            }
+        }
+
+        let where_clause = where_clauses.join(" AND ");
+
+        // Pages are counted in invoices rather than rows, so the lines of one
+        // invoice are never split across two pages.
+        let count_sql =
+            format!("SELECT COUNT(DISTINCT invno) FROM sales_reports WHERE {where_clause}");
+        let mut count_stmt = self.conn.prepare(&count_sql)?;
+        let total_count: i32 = count_stmt.query_row(
+            rusqlite::params_from_iter(params.iter().map(|v| v.as_ref())),
+            |row| row.get(0),
+        )?;
+
+        // Get paginated data
+        let data_sql = format!(
+            "SELECT {SALES_REPORT_COLUMNS}
+             FROM sales_reports 
+             WHERE {where_clause} AND invno IN (
+                 SELECT invno FROM sales_reports 
+                 WHERE {where_clause} 
+                 GROUP BY invno 
+                 ORDER BY MAX(inv_date) DESC, invno 
+                 LIMIT ? OFFSET ?
+             )
+             ORDER BY inv_date DESC, invno, line_no"
+        );
+
+        let mut stmt = self.conn.prepare(&data_sql)?;
+        let mut final_params: Vec<&dyn rusqlite::ToSql> =
+            params.iter().map(|v| v.as_ref()).collect();
+        final_params.extend(params.iter().map(|v| v.as_ref()));
+        final_params.push(&page_size);
+        final_params.push(&offset);
+
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(final_params),
+            SalesReport::from_row,
+        )?;
+
+        let mut reports = Vec::new();
+        for row in rows {
+            reports.push(row?);
+        }
+
+        Ok((reports, total_count))
+    }
+
+    pub fn search_sales_reports(
+        &self,
+        company_id: i32,
+        search_term: String,
+    ) -> Result<Vec<SalesReport>> {
+        let search_pattern = format!("%{search_term}%");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SALES_REPORT_COLUMNS}
+             FROM sales_reports 
+             WHERE company_id = ? AND (
+                 cust_code LIKE ? OR 
+                 cust_name LIKE ? OR 
+                 invno LIKE ? OR 
+                 part_code LIKE ? OR 
+                 part_name LIKE ?
+             )
+             ORDER BY inv_date DESC, invno, line_no"
+        ))?;
+
+        let rows = stmt.query_map(
+            rusqlite::params![
+                company_id,
+                search_pattern,
+                search_pattern,
+                search_pattern,
+                search_pattern,
+                search_pattern
+            ],
+            SalesReport::from_row,
+        )?;
+
+        let mut reports = Vec::new();
+        for row in rows {
+            reports.push(row?);
+        }
+
+        Ok(reports)
+    }
+
+    pub fn create_sales_report(&self, mut report: CreateSalesReportRequest) -> AppResult<i32> {
+        let company_id = report.company_id;
+        if let Some(re) = re_code::re_code(&report.inv_date, &self.year_codes(company_id)?) {
+            report.RE = re;
+        }
+        let (igst_yes_no, percentage) = gst_split::igst_flags(
+            GstRates::default(),
+            report.percentage,
+            report.ass_val,
+            report.c_gst,
+            report.s_gst,
+            report.igst,
+        );
+        report.igst_yes_no = igst_yes_no.to_string();
+        report.percentage = percentage;
+        check_amounts((&report).into(), self.reconcile_tolerance(company_id)?)?;
+        let tx = self.conn.unchecked_transaction()?;
+        let id = self.insert_sales_report(report)?;
+
+        let after = self.get_sales_report(id)?;
+        self.log_change(
+            Some(company_id),
+            "create_sales_report",
+            id,
+            None,
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    fn insert_sales_report(&self, report: CreateSalesReportRequest) -> Result<i32> {
+        let line_no = match report.line_no {
+            Some(line_no) => line_no,
+            None => self.next_invoice_line_no(report.company_id, &report.invno)?,
+        };
+
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO sales_reports (
+                company_id, cust_code, cust_name, inv_date, RE, invno, line_no, 
+                part_code, part_name, tariff, qty, bas_price, ass_val, 
+                c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+
+        stmt.execute(rusqlite::params![
+            report.company_id,
+            report.cust_code,
+            report.cust_name,
+            report.inv_date,
+            report.RE,
+            report.invno,
+            line_no,
+            report.part_code,
+            report.part_name,
+            report.tariff,
+            report.qty,
+            report.bas_price,
+            report.ass_val,
+            report.c_gst,
+            report.s_gst,
+            report.igst,
+            report.amot,
+            report.inv_val,
+            report.igst_yes_no,
+            report.percentage,
+        ])?;
+
+        Ok(self.conn.last_insert_rowid() as i32)
+    }
+
+    // Next free line number for an invoice (1 for a new invoice)
+    fn next_invoice_line_no(&self, company_id: i32, invno: &str) -> Result<i32> {
+        self.conn.query_row(
+            "SELECT COALESCE(MAX(line_no), 0) + 1 FROM sales_reports WHERE company_id = ? AND invno = ?",
+            rusqlite::params![company_id, invno],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn update_sales_report(
+        &self,
+        id: i32,
+        report: UpdateSalesReportRequest,
+    ) -> AppResult<bool> {
+        // A new date gets the RE code of its month unless one was given
+        let re_date = report.inv_date.clone().filter(|_| report.RE.is_none());
+        let mut fields = Vec::new();
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(cust_code) = report.cust_code {
+            fields.push("cust_code = ?");
+            values.push(Box::new(cust_code));
+        }
+        if let Some(cust_name) = report.cust_name {
+            fields.push("cust_name = ?");
+            values.push(Box::new(cust_name));
+        }
+        if let Some(inv_date) = report.inv_date {
+            fields.push("inv_date = ?");
+            values.push(Box::new(inv_date));
+        }
+        if let Some(re) = report.RE {
+            fields.push("RE = ?");
+            values.push(Box::new(re));
+        }
+        if let Some(invno) = report.invno {
+            fields.push("invno = ?");
+            values.push(Box::new(invno));
+        }
+        if let Some(line_no) = report.line_no {
+            fields.push("line_no = ?");
+            values.push(Box::new(line_no));
+        }
+        if let Some(part_code) = report.part_code {
+            fields.push("part_code = ?");
+            values.push(Box::new(part_code));
+        }
+        if let Some(part_name) = report.part_name {
+            fields.push("part_name = ?");
+            values.push(Box::new(part_name));
+        }
+        if let Some(tariff) = report.tariff {
+            fields.push("tariff = ?");
+            values.push(Box::new(tariff));
+        }
+        if let Some(qty) = report.qty {
+            fields.push("qty = ?");
+            values.push(Box::new(qty));
+        }
+        if let Some(bas_price) = report.bas_price {
+            fields.push("bas_price = ?");
+            values.push(Box::new(bas_price));
+        }
+        if let Some(ass_val) = report.ass_val {
+            fields.push("ass_val = ?");
+            values.push(Box::new(ass_val));
+        }
+        if let Some(c_gst) = report.c_gst {
+            fields.push("c_gst = ?");
+            values.push(Box::new(c_gst));
+        }
+        if let Some(s_gst) = report.s_gst {
+            fields.push("s_gst = ?");
+            values.push(Box::new(s_gst));
+        }
+        if let Some(igst) = report.igst {
+            fields.push("igst = ?");
+            values.push(Box::new(igst));
+        }
+        if let Some(amot) = report.amot {
+            fields.push("amot = ?");
+            values.push(Box::new(amot));
+        }
+        if let Some(inv_val) = report.inv_val {
+            fields.push("inv_val = ?");
+            values.push(Box::new(inv_val));
         }
         if let Some(igst_yes_no) = report.igst_yes_no {
             fields.push("igst_yes_no = ?");
             values.push(Box::new(igst_yes_no));
         }
-        if let Some(percentage) = report.percentage {
-            fields.push("percentage = ?");
-            values.push(Box::new(percentage));
+        if let Some(percentage) = report.percentage {
+            fields.push("percentage = ?");
+            values.push(Box::new(percentage));
+        }
+
+        if fields.is_empty() {
+            return Ok(false);
+        }
+        let Some(before) = self.get_sales_report(id)? else {
+            return Ok(false);
+        };
+
+        if let Some(inv_date) = re_date {
+            let year_codes = self.year_codes(before.company_id)?;
+            if let Some(re) = re_code::re_code(&inv_date, &year_codes) {
+                fields.push("RE = ?");
+                values.push(Box::new(re));
+            }
+        }
+
+        values.push(Box::new(id));
+
+        let query = format!(
+            "UPDATE sales_reports SET {} WHERE id = ?",
+            fields.join(", ")
+        );
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(&query)?;
+
+        let changes = stmt.execute(rusqlite::params_from_iter(
+            values.iter().map(|v| v.as_ref()),
+        ))?;
+
+        // The line is checked as it ends up; the update is rolled back when it fails
+        let after = self.get_sales_report(id)?;
+        if let Some(after) = &after {
+            check_amounts(after.into(), self.reconcile_tolerance(after.company_id)?)?;
+        }
+        self.log_change(
+            Some(before.company_id),
+            "update_sales_report",
+            id,
+            Some(&before),
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(changes > 0)
+    }
+
+    pub fn delete_sales_report(&self, id: i32) -> Result<bool> {
+        let Some(before) = self.get_sales_report(id)? else {
+            return Ok(false);
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self
+            .conn
+            .prepare("DELETE FROM sales_reports WHERE id = ?")?;
+        let changes = stmt.execute([id])?;
+
+        self.log_change::<SalesReport>(
+            Some(before.company_id),
+            "delete_sales_report",
+            id,
+            Some(&before),
+            None,
+        )?;
+        tx.commit()?;
+        Ok(changes > 0)
+    }
+
+    pub fn export_sales_reports_csv(&self, company_id: i32) -> AppResult<String> {
+        let reports = self.get_sales_reports_by_company(company_id)?;
+
+        let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new()));
+
+        // Write header
+        wtr.write_record([
+            "cust_code",
+            "cust_name",
+            "inv_date",
+            "RE",
+            "invno",
+            "line_no",
+            "part_code",
+            "part_name",
+            "tariff",
+            "qty",
+            "bas_price",
+            "ass_val",
+            "c_gst",
+            "s_gst",
+            "igst",
+            "amot",
+            "inv_val",
+            "igst_yes_no",
+            "percentage",
+        ])?;
+
+        // Write data
+        for report in reports {
+            wtr.write_record(&[
+                report.cust_code,
+                report.cust_name,
+                report.inv_date,
+                report.RE,
+                report.invno,
+                report.line_no.to_string(),
+                report.part_code.unwrap_or_default(),
+                report.part_name.unwrap_or_default(),
+                report.tariff.unwrap_or_default(),
+                report.qty.to_string(),
+                report.bas_price.to_string(),
+                report.ass_val.to_string(),
+                report.c_gst.to_string(),
+                report.s_gst.to_string(),
+                report.igst.to_string(),
+                report.amot.to_string(),
+                report.inv_val.to_string(),
+                report.igst_yes_no,
+                report.percentage.to_string(),
+            ])?;
+        }
+
+        wtr.flush()?;
+        let data = wtr.into_inner()?.into_inner();
+        Ok(String::from_utf8(data)?)
+    }
+
+    /// Write the sales report lines of a company to an .xlsx file with typed cells and
+    /// a totals row, returning the number of lines written
+    pub fn export_sales_reports_xlsx(&self, company_id: i32, path: &Path) -> AppResult<usize> {
+        let reports = self.get_sales_reports_by_company(company_id)?;
+        xlsx::sales_reports_workbook(&reports)?.save(path)?;
+        Ok(reports.len())
+    }
+
+    pub fn import_sales_reports_csv(
+        &self,
+        company_id: i32,
+        csv_data: String,
+        filename: Option<String>,
+        force: bool,
+        mode: ImportMode,
+        profile_id: Option<i32>,
+    ) -> AppResult<ImportReport> {
+        let (headers, rows) = read_csv_rows(&csv_data)?;
+        println!("CSV Headers: {headers:?}");
+
+        let rows = rows
+            .into_iter()
+            .enumerate()
+            .map(|(row_num, fields)| (row_num + 2, fields)) // +2 because we're 0-indexed and skipped header row
+            .collect();
+        let filename = filename.unwrap_or_else(|| "import.csv".to_string());
+        self.import_sales_rows(
+            company_id,
+            "import_sales_reports_csv",
+            headers,
+            rows,
+            filename,
+            force,
+            mode,
+            profile_id,
+        )
+    }
+
+    /// Import sales report lines from a worksheet of an .xlsx/.xls file. The first sheet
+    /// is used unless `sheet` is given, and the header row is detected unless
+    /// `header_row` (1-based, as shown in Excel) is given. Date cells are read as dates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_sales_reports_excel(
+        &self,
+        company_id: i32,
+        path: &Path,
+        sheet: Option<String>,
+        header_row: Option<usize>,
+        force: bool,
+        mode: ImportMode,
+        profile_id: Option<i32>,
+    ) -> AppResult<ImportReport> {
+        let data = excel::read_sheet(path, sheet.as_deref())?;
+        // A chosen profile's headers also count when looking for the header row
+        let profile = match profile_id {
+            Some(id) => self.resolve_mapping_profile(company_id, Some(id), &[])?,
+            None => None,
+        };
+        let aliases = FieldAliases::new(SALES_REPORT_FIELDS, profile.as_ref());
+
+        let header_index = match header_row {
+            Some(row) if row >= data.first_row => row - data.first_row,
+            Some(row) => {
+                return Err(AppError::validation(
+                    "header_row",
+                    Some(row),
+                    format!("Row {row} is above the data of sheet '{}'", data.sheet_name),
+                ))
+            }
+            None => detect_header_row(&data.cells, &aliases).unwrap_or_default(),
+        };
+        let Some(header_cells) = data.cells.get(header_index) else {
+            return Err(AppError::validation(
+                "header_row",
+                header_row,
+                format!("Sheet '{}' has no header row", data.sheet_name),
+            ));
+        };
+        let headers: Vec<String> = header_cells.iter().map(|h| h.trim().to_string()).collect();
+        println!(
+            "Sheet '{}' headers at row {}: {headers:?}",
+            data.sheet_name,
+            data.first_row + header_index
+        );
+
+        let rows = data
+            .cells
+            .iter()
+            .enumerate()
+            .skip(header_index + 1)
+            .filter(|(_, cells)| cells.iter().any(|cell| !cell.trim().is_empty()))
+            .map(|(index, cells)| {
+                let fields: FileRow = headers
+                    .iter()
+                    .zip(cells)
+                    .filter(|(header, _)| !header.is_empty())
+                    .map(|(header, value)| (header.clone(), value.clone()))
+                    .collect();
+                (data.first_row + index, fields)
+            })
+            .collect();
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "import.xlsx".to_string());
+        let headers = headers.into_iter().filter(|h| !h.is_empty()).collect();
+        self.import_sales_rows(
+            company_id,
+            "import_sales_reports_excel",
+            headers,
+            rows,
+            format!("{file_name} [{}]", data.sheet_name),
+            force,
+            mode,
+            profile_id,
+        )
+    }
+
+    // Record an upload with its raw rows and turn them into sales report lines, all in
+    // one transaction. Rows carry their 1-based row number in the source file.
+    #[allow(clippy::too_many_arguments)]
+    fn import_sales_rows(
+        &self,
+        company_id: i32,
+        action: &str,
+        headers: Vec<String>,
+        rows: Vec<(usize, FileRow)>,
+        filename: String,
+        force: bool,
+        mode: ImportMode,
+        profile_id: Option<i32>,
+    ) -> AppResult<ImportReport> {
+        let hash = content_hash(rows.iter().map(|(_, fields)| fields));
+        if !force {
+            if let Some(previous) = self.find_upload_by_hash(company_id, &hash)? {
+                return Err(AppError::Duplicate {
+                    message: format!(
+                        "This file was already imported as upload #{} ({}) on {}",
+                        previous.id, previous.filename, previous.uploaded_at
+                    ),
+                    existing_id: Some(previous.id),
+                });
+            }
+        }
+
+        let profile = self.resolve_mapping_profile(company_id, profile_id, &headers)?;
+        let profile_id = profile.as_ref().map(|p| p.id);
+        let aliases = FieldAliases::new(SALES_REPORT_FIELDS, profile.as_ref());
+
+        let tx = self.conn.unchecked_transaction()?;
+        let upload_id = self.create_uploaded_report(company_id, &filename, &hash, profile_id)?;
+        println!(
+            "Recording upload {upload_id} ({filename}) with {} rows",
+            rows.len()
+        );
+
+        for (row_index, fields) in &rows {
+            self.insert_report_row(upload_id, &aliases, *row_index, fields)?;
+        }
+        self.set_upload_status(upload_id, UPLOAD_STATUS_PARSED)?;
+
+        let mut report = self.transform_upload(company_id, upload_id, mode)?;
+        report.headers = headers;
+        report.check_mode(mode)?;
+        self.log_action(
+            Some(company_id),
+            action,
+            serde_json::json!({
+                "uploaded_report_id": upload_id,
+                "filename": filename,
+                "rows": rows.len(),
+                "imported": report.imported,
+                "skipped": report.skipped,
+                "duplicates": report.duplicates,
+                "forced": force,
+                "mode": mode,
+                "mapping_profile_id": profile_id,
+            }),
+        )?;
+        tx.commit()?;
+        Ok(report)
+    }
+
+    // Store one raw row of an upload. Columns that no sales report field maps from are
+    // kept in extra_json; the full original record is kept in raw_json for re-processing.
+    fn insert_report_row(
+        &self,
+        upload_id: i32,
+        aliases: &FieldAliases,
+        row_index: usize,
+        fields: &[(String, String)],
+    ) -> Result<()> {
+        let row = SourceRow::new(aliases, fields);
+
+        let extra: serde_json::Map<String, serde_json::Value> = fields
+            .iter()
+            .filter(|(header, _)| !aliases.is_mapped(header))
+            .map(|(header, value)| (header.clone(), serde_json::Value::from(value.as_str())))
+            .collect();
+        let raw: serde_json::Map<String, serde_json::Value> = fields
+            .iter()
+            .map(|(header, value)| (header.clone(), serde_json::Value::from(value.as_str())))
+            .collect();
+
+        let invoice_date = row.get("inv_date");
+        let customer_name = match row.get("cust_name") {
+            name if name.is_empty() => row.get("cust_code"),
+            name => name,
+        };
+
+        self.conn.execute(
+            "INSERT INTO report_rows (
+                uploaded_report_id, row_number, invoice_no, invoice_date, customer_name_raw,
+                gst_rate, cgst_amt, sgst_amt, igst_amt, total_amount, extra_json, raw_json
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                upload_id,
+                row_index as i64,
+                row.get("invno"),
+                parse_date(&invoice_date).unwrap_or(invoice_date),
+                customer_name,
+                parse_number(&row.get("percentage")),
+                parse_money(&row.get("c_gst")),
+                parse_money(&row.get("s_gst")),
+                parse_money(&row.get("igst")),
+                parse_money(&row.get("inv_val")),
+                if extra.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::Value::Object(extra).to_string())
+                },
+                serde_json::Value::Object(raw).to_string(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Dry run of `import_sales_reports_csv`: the resolved columns, the first lines as
+    /// they would be stored and the predicted counts, plus the upload this file repeats
+    /// if it was imported before. Nothing is written.
+    pub fn preview_sales_reports_csv(
+        &self,
+        company_id: i32,
+        csv_data: String,
+        preview_rows: Option<usize>,
+        profile_id: Option<i32>,
+    ) -> AppResult<ImportPreview<CreateSalesReportRequest>> {
+        let (headers, rows) = read_csv_rows(&csv_data)?;
+        let limit = preview_rows.unwrap_or(DEFAULT_PREVIEW_ROWS);
+        let profile = self.resolve_mapping_profile(company_id, profile_id, &headers)?;
+        let aliases = FieldAliases::new(SALES_REPORT_FIELDS, profile.as_ref());
+        let (column_mapping, unmapped_headers) = resolve_columns(&aliases, &headers);
+        let duplicate_upload_id = self
+            .find_upload_by_hash(company_id, &content_hash(&rows))?
+            .map(|upload| upload.id);
+
+        let mut report = ImportReport {
+            mapping_profile_id: profile.map(|p| p.id),
+            headers,
+            ..Default::default()
+        };
+        let numbered: Vec<(usize, Vec<(String, String)>)> = rows
+            .into_iter()
+            .enumerate()
+            .map(|(row_num, fields)| (row_num + 2, fields))
+            .collect();
+
+        let mut preview = Vec::new();
+        self.process_sales_rows(
+            company_id,
+            &aliases,
+            &numbered,
+            ImportMode::BestEffort,
+            &mut report,
+            |line| {
+                if preview.len() < limit {
+                    preview.push(line);
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(ImportPreview {
+            column_mapping,
+            unmapped_headers,
+            rows: preview,
+            report,
+            duplicate_upload_id,
+        })
+    }
+
+    // Run `f` inside a savepoint of the current transaction so that a failure undoes
+    // only the writes made by `f`
+    fn with_savepoint<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT import_row")?;
+        match f() {
+            Ok(value) => {
+                self.conn.execute_batch("RELEASE import_row")?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.conn
+                    .execute_batch("ROLLBACK TO import_row; RELEASE import_row")?;
+                Err(e)
+            }
+        }
+    }
+
+    // Turn the stored raw rows of an upload into sales report lines
+    fn transform_upload(
+        &self,
+        company_id: i32,
+        upload_id: i32,
+        mode: ImportMode,
+    ) -> Result<ImportReport> {
+        let profile_id: Option<i32> = self.conn.query_row(
+            "SELECT mapping_profile_id FROM uploaded_reports WHERE id = ?",
+            [upload_id],
+            |row| row.get(0),
+        )?;
+        let profile = match profile_id {
+            Some(id) => self.get_column_mapping_profile(id)?,
+            None => None,
+        };
+        let aliases = FieldAliases::new(SALES_REPORT_FIELDS, profile.as_ref());
+        let mut report = ImportReport {
+            uploaded_report_id: Some(upload_id),
+            mapping_profile_id: profile.map(|p| p.id),
+            ..Default::default()
+        };
+
+        let raw_rows: Vec<(i64, String)> = self
+            .conn
+            .prepare(
+                "SELECT row_number, raw_json FROM report_rows
+                 WHERE uploaded_report_id = ? ORDER BY row_number",
+            )?
+            .query_map([upload_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        let mut rows = Vec::with_capacity(raw_rows.len());
+        for (row_index, raw_json) in raw_rows {
+            match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&raw_json) {
+                Ok(map) => {
+                    let fields: Vec<(String, String)> = map
+                        .into_iter()
+                        .map(|(header, value)| {
+                            let value = match value {
+                                serde_json::Value::String(s) => s,
+                                other => other.to_string(),
+                            };
+                            (header, value)
+                        })
+                        .collect();
+                    rows.push((row_index as usize, fields));
+                }
+                Err(e) => {
+                    report.reject(
+                        row_index as usize,
+                        "",
+                        format!("Unreadable raw data ({e})"),
+                        &[],
+                    );
+                }
+            }
+        }
+
+        self.process_sales_rows(company_id, &aliases, &rows, mode, &mut report, |line| {
+            self.with_savepoint(|| {
+                let id = self.insert_sales_report(line)?;
+                self.conn.execute(
+                    "UPDATE sales_reports SET uploaded_report_id = ? WHERE id = ?",
+                    [upload_id, id],
+                )?;
+                Ok(())
+            })
+        })?;
+
+        self.set_upload_status(upload_id, UPLOAD_STATUS_TRANSFORMED)?;
+
+        println!(
+            "Import completed: {} imported, {} skipped, {} duplicates",
+            report.imported, report.skipped, report.duplicates
+        );
+
+        Ok(report)
+    }
+
+    /// Validate sales rows and number the lines of each invoice. An invoice that
+    /// existed before this import is a duplicate, including all of its lines; later
+    /// lines of an invoice first seen in the file are new lines. Lines that break a
+    /// reconciliation rule are rejected, and so is every line of an invoice whose lines
+    /// do not add up to its value. Each accepted line is passed to `accept` and
+    /// counted; when `accept` fails, best-effort mode rejects the row and
+    /// all-or-nothing returns the error.
+    fn process_sales_rows(
+        &self,
+        company_id: i32,
+        aliases: &FieldAliases,
+        rows: &[(usize, FileRow)],
+        mode: ImportMode,
+        report: &mut ImportReport,
+        mut accept: impl FnMut(CreateSalesReportRequest) -> Result<()>,
+    ) -> Result<()> {
+        // Invoices accepted from this file, with the next line number to assign
+        let mut file_invoices: std::collections::HashMap<String, i32> =
+            std::collections::HashMap::new();
+        // Invoices that were already in the database before this import
+        let mut existing_invoices: std::collections::HashSet<String> =
+            std::collections::HashSet::new();
+        let year_codes = self.year_codes(company_id)?;
+        let tolerance = self.reconcile_tolerance(company_id)?;
+        let mut pending = Vec::new();
+
+        for (row_index, fields) in rows {
+            let row_index = *row_index;
+            println!("Processing row {row_index} with {} columns", fields.len());
+
+            let mut line = match normalize_sales_row(company_id, aliases, fields) {
+                Ok(line) => line,
+                Err((field, reason)) => {
+                    report.reject(row_index, field, reason, fields);
+                    continue;
+                }
+            };
+            // The RE column of the file is kept only for years without a code
+            if let Some(re) = re_code::re_code(&line.inv_date, &year_codes) {
+                line.RE = re;
+            }
+            if let Some(issue) = reconcile::check_line((&line).into(), tolerance).first() {
+                report.reject(row_index, issue.rule.field(), issue.message.clone(), fields);
+                continue;
+            }
+            let invno = line.invno.clone();
+
+            if existing_invoices.contains(&invno) {
+                report.reject_duplicate(
+                    row_index,
+                    "invno",
+                    format!("Invoice {invno} already exists"),
+                    fields,
+                );
+                continue;
+            }
+
+            if !file_invoices.contains_key(&invno) {
+                let existing_count: i32 = self.conn.query_row(
+                    "SELECT COUNT(*) FROM sales_reports WHERE company_id = ? AND invno = ?",
+                    rusqlite::params![company_id, invno],
+                    |row| row.get(0),
+                )?;
+
+                if existing_count > 0 {
+                    report.reject_duplicate(
+                        row_index,
+                        "invno",
+                        format!("Invoice {invno} already exists"),
+                        fields,
+                    );
+                    existing_invoices.insert(invno);
+                    continue;
+                }
+            }
+
+            let next_line_no = file_invoices.entry(invno).or_insert(1);
+            line.line_no = Some(*next_line_no);
+            *next_line_no += 1;
+            pending.push((row_index, fields, line));
+        }
+
+        // Invoice values can only be checked once all lines of the invoice are read
+        let mut invoices: std::collections::HashMap<&str, Vec<reconcile::LineAmounts>> =
+            std::collections::HashMap::new();
+        for (_, _, line) in &pending {
+            invoices
+                .entry(line.invno.as_str())
+                .or_default()
+                .push(line.into());
+        }
+        let broken: std::collections::HashMap<String, Discrepancy> = invoices
+            .into_iter()
+            .filter_map(|(invno, lines)| {
+                reconcile::check_invoice(&lines, tolerance).map(|issue| (invno.to_string(), issue))
+            })
+            .collect();
+
+        for (row_index, fields, line) in pending {
+            if let Some(issue) = broken.get(&line.invno) {
+                report.reject(row_index, issue.rule.field(), issue.message.clone(), fields);
+                continue;
+            }
+            let invno = line.invno.clone();
+            let line_no = line.line_no.unwrap_or_default();
+
+            if let Err(e) = accept(line) {
+                if mode == ImportMode::AllOrNothing {
+                    return Err(e);
+                }
+                let field = insert_error_field(&e, "invno");
+                report.reject(
+                    row_index,
+                    field,
+                    format!("Could not insert row ({e})"),
+                    fields,
+                );
+                continue;
+            }
+            report.imported += 1;
+            println!("Accepted invoice {invno} line {line_no} from row {row_index}");
+        }
+
+        Ok(())
+    }
+
+    // Column mapping profile methods
+    pub fn get_column_mapping_profiles(
+        &self,
+        company_id: i32,
+    ) -> Result<Vec<ColumnMappingProfile>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, name, mappings_json, created_at, updated_at
+             FROM column_mapping_profiles WHERE company_id = ? ORDER BY name",
+        )?;
+        let profiles = stmt.query_map([company_id], ColumnMappingProfile::from_row)?;
+        profiles.collect()
+    }
+
+    pub fn get_column_mapping_profile(&self, id: i32) -> Result<Option<ColumnMappingProfile>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, name, mappings_json, created_at, updated_at
+             FROM column_mapping_profiles WHERE id = ?",
+        )?;
+        let mut rows = stmt.query_map([id], ColumnMappingProfile::from_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn create_column_mapping_profile(
+        &self,
+        profile: CreateColumnMappingProfileRequest,
+    ) -> AppResult<i32> {
+        validate_profile_name(&profile.name)?;
+        validate_profile_mappings(&profile.mappings)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO column_mapping_profiles (company_id, name, mappings_json)
+             VALUES (?, ?, ?)",
+        )?;
+        let id = stmt.insert(rusqlite::params![
+            profile.company_id,
+            profile.name.trim(),
+            serde_json::to_string(&profile.mappings)?,
+        ])? as i32;
+
+        let after = self.get_column_mapping_profile(id)?;
+        self.log_change(
+            Some(profile.company_id),
+            "create_column_mapping_profile",
+            id,
+            None,
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn update_column_mapping_profile(
+        &self,
+        id: i32,
+        profile: UpdateColumnMappingProfileRequest,
+    ) -> AppResult<bool> {
+        let mut updates = Vec::new();
+        let mut params = Vec::new();
+
+        if let Some(name) = profile.name {
+            validate_profile_name(&name)?;
+            updates.push("name = ?");
+            params.push(name.trim().to_string());
+        }
+        if let Some(mappings) = profile.mappings {
+            validate_profile_mappings(&mappings)?;
+            updates.push("mappings_json = ?");
+            params.push(serde_json::to_string(&mappings)?);
+        }
+
+        if updates.is_empty() {
+            return Ok(false);
+        }
+        let Some(before) = self.get_column_mapping_profile(id)? else {
+            return Ok(false);
+        };
+
+        params.push(id.to_string());
+
+        let query = format!(
+            "UPDATE column_mapping_profiles SET {}, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            updates.join(", ")
+        );
+        let tx = self.conn.unchecked_transaction()?;
+        let rows_affected = self
+            .conn
+            .execute(&query, rusqlite::params_from_iter(params.iter()))?;
+
+        let after = self.get_column_mapping_profile(id)?;
+        self.log_change(
+            Some(before.company_id),
+            "update_column_mapping_profile",
+            id,
+            Some(&before),
+            after.as_ref(),
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    pub fn delete_column_mapping_profile(&self, id: i32) -> Result<bool> {
+        let Some(before) = self.get_column_mapping_profile(id)? else {
+            return Ok(false);
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let rows_affected = self
+            .conn
+            .execute("DELETE FROM column_mapping_profiles WHERE id = ?", [id])?;
+
+        self.log_change::<ColumnMappingProfile>(
+            Some(before.company_id),
+            "delete_column_mapping_profile",
+            id,
+            Some(&before),
+            None,
+        )?;
+        tx.commit()?;
+        Ok(rows_affected > 0)
+    }
+
+    /// The profile of a company whose headers match the most of `headers`, if any
+    /// matches at all. Ties go to the profile that comes first by name.
+    pub fn detect_column_mapping_profile(
+        &self,
+        company_id: i32,
+        headers: &[String],
+    ) -> Result<Option<ColumnMappingProfile>> {
+        let headers: std::collections::HashSet<String> =
+            headers.iter().map(|h| normalize_header(h.trim())).collect();
+
+        let mut best: Option<(usize, ColumnMappingProfile)> = None;
+        for profile in self.get_column_mapping_profiles(company_id)? {
+            let matched = profile
+                .mappings
+                .values()
+                .flatten()
+                .map(|header| normalize_header(header.trim()))
+                .collect::<std::collections::HashSet<_>>()
+                .intersection(&headers)
+                .count();
+            if matched > 0 && best.as_ref().map_or(true, |(count, _)| matched > *count) {
+                best = Some((matched, profile));
+            }
+        }
+        Ok(best.map(|(_, profile)| profile))
+    }
+
+    // The profile an import reads its rows with: the one asked for, or else the best
+    // match for the file's headers
+    fn resolve_mapping_profile(
+        &self,
+        company_id: i32,
+        profile_id: Option<i32>,
+        headers: &[String],
+    ) -> AppResult<Option<ColumnMappingProfile>> {
+        match profile_id {
+            Some(id) => match self.get_column_mapping_profile(id)? {
+                Some(profile) if profile.company_id == company_id => Ok(Some(profile)),
+                _ => Err(AppError::not_found(format!(
+                    "Column mapping profile {id} not found"
+                ))),
+            },
+            None => Ok(self.detect_column_mapping_profile(company_id, headers)?),
+        }
+    }
+
+    // Reconciliation methods
+
+    /// Every invoice of a company that breaks an arithmetic rule, one entry per rule
+    /// and line, ordered by invoice. `tolerance` overrides the company's setting.
+    pub fn reconcile_sales_reports(
+        &self,
+        company_id: i32,
+        tolerance: Option<f64>,
+    ) -> AppResult<Vec<ReconciliationIssue>> {
+        self.require_company(company_id)?;
+        let tolerance = match tolerance {
+            Some(tolerance) => {
+                check_tolerance(tolerance)?;
+                Money::from_rupees(tolerance)
+            }
+            None => self.reconcile_tolerance(company_id)?,
+        };
+
+        let mut invoices: std::collections::BTreeMap<String, Vec<SalesReport>> =
+            std::collections::BTreeMap::new();
+        for line in self.get_sales_reports_by_company(company_id)? {
+            invoices.entry(line.invno.clone()).or_default().push(line);
+        }
+
+        let mut issues = Vec::new();
+        for (invno, mut lines) in invoices {
+            lines.sort_by_key(|line| line.line_no);
+            let amounts: Vec<reconcile::LineAmounts> = lines.iter().map(Into::into).collect();
+            if let Some(Discrepancy { rule, message }) =
+                reconcile::check_invoice(&amounts, tolerance)
+            {
+                issues.push(ReconciliationIssue {
+                    sales_report_id: None,
+                    invno: invno.clone(),
+                    line_no: None,
+                    inv_date: lines[0].inv_date.clone(),
+                    cust_name: lines[0].cust_name.clone(),
+                    rule,
+                    message,
+                });
+            }
+
+            for (line, amounts) in lines.iter().zip(amounts) {
+                for Discrepancy { rule, message } in reconcile::check_line(amounts, tolerance) {
+                    issues.push(ReconciliationIssue {
+                        sales_report_id: Some(line.id),
+                        invno: invno.clone(),
+                        line_no: Some(line.line_no),
+                        inv_date: line.inv_date.clone(),
+                        cust_name: line.cust_name.clone(),
+                        rule,
+                        message,
+                    });
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    fn reconcile_tolerance(&self, company_id: i32) -> Result<Money> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT reconcile_tolerance FROM companies WHERE id = ?")?;
+        let mut rows = stmt.query_map([company_id], |row| row.get(0))?;
+        let tolerance = rows
+            .next()
+            .transpose()?
+            .unwrap_or(reconcile::DEFAULT_TOLERANCE);
+        Ok(Money::from_rupees(tolerance))
+    }
+
+    // RE code methods
+    pub fn get_re_year_codes(&self, company_id: i32) -> Result<Vec<YearCode>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT year, code FROM re_year_codes WHERE company_id = ? ORDER BY year")?;
+        let codes = stmt.query_map([company_id], |row| {
+            Ok(YearCode {
+                year: row.get(0)?,
+                code: row.get(1)?,
+            })
+        })?;
+        codes.collect()
+    }
+
+    /// Replace the year map of a company. Existing lines keep their RE code until
+    /// `recompute_re_codes` is run.
+    pub fn set_re_year_codes(&self, company_id: i32, codes: Vec<YearCode>) -> AppResult<()> {
+        re_code::validate_year_codes(&codes)?;
+        self.require_company(company_id)?;
+        let before = self.get_re_year_codes(company_id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "DELETE FROM re_year_codes WHERE company_id = ?",
+            [company_id],
+        )?;
+        for code in &codes {
+            self.conn.execute(
+                "INSERT INTO re_year_codes (company_id, year, code) VALUES (?, ?, ?)",
+                rusqlite::params![company_id, code.year, code.code],
+            )?;
+        }
+        self.log_action(
+            Some(company_id),
+            "set_re_year_codes",
+            serde_json::json!({ "before": before, "after": codes }),
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Recalculate the RE code of every line of a company from its invoice date.
+    /// Lines whose year has no code are left as they are. Returns the number of lines
+    /// changed.
+    pub fn recompute_re_codes(&self, company_id: i32) -> AppResult<usize> {
+        self.require_company(company_id)?;
+        let year_codes = self.year_codes(company_id)?;
+
+        let lines: Vec<(i32, String, String)> = self
+            .conn
+            .prepare("SELECT id, inv_date, RE FROM sales_reports WHERE company_id = ?")?
+            .query_map([company_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<_>>()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut changed = 0;
+        for (id, inv_date, current) in lines {
+            match re_code::re_code(&inv_date, &year_codes) {
+                Some(re) if re != current => {
+                    self.conn.execute(
+                        "UPDATE sales_reports SET RE = ? WHERE id = ?",
+                        rusqlite::params![re, id],
+                    )?;
+                    changed += 1;
+                }
+                _ => {}
+            }
+        }
+        self.log_action(
+            Some(company_id),
+            "recompute_re_codes",
+            serde_json::json!({ "changed": changed }),
+        )?;
+        tx.commit()?;
+
+        Ok(changed)
+    }
+
+    fn year_codes(&self, company_id: i32) -> Result<std::collections::BTreeMap<i32, String>> {
+        Ok(self
+            .get_re_year_codes(company_id)?
+            .into_iter()
+            .map(|code| (code.year, code.code))
+            .collect())
+    }
+
+    fn insert_default_year_codes(&self, company_id: i32) -> Result<()> {
+        for (year, code) in re_code::DEFAULT_YEAR_CODES {
+            self.conn.execute(
+                "INSERT INTO re_year_codes (company_id, year, code) VALUES (?, ?, ?)",
+                rusqlite::params![company_id, year, code.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Upload tracking methods
+    fn create_uploaded_report(
+        &self,
+        company_id: i32,
+        filename: &str,
+        hash: &str,
+        mapping_profile_id: Option<i32>,
+    ) -> Result<i32> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO uploaded_reports (company_id, filename, status, parsed_hash, mapping_profile_id)
+             VALUES (?, ?, ?, ?, ?)",
+        )?;
+        let id = stmt.insert(rusqlite::params![
+            company_id,
+            filename,
+            UPLOAD_STATUS_UPLOADED,
+            hash,
+            mapping_profile_id
+        ])?;
+        Ok(id as i32)
+    }
+
+    // Most recent live upload of the same content; rolled back uploads don't count
+    fn find_upload_by_hash(&self, company_id: i32, hash: &str) -> Result<Option<UploadedReport>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {UPLOADED_REPORT_COLUMNS}
+             FROM uploaded_reports u
+             WHERE u.company_id = ? AND u.parsed_hash = ? AND u.status != ?
+             ORDER BY u.id DESC
+             LIMIT 1"
+        ))?;
+
+        let mut rows = stmt.query_map(
+            rusqlite::params![company_id, hash, UPLOAD_STATUS_ROLLED_BACK],
+            UploadedReport::from_row,
+        )?;
+        rows.next().transpose()
+    }
+
+    fn set_upload_status(&self, upload_id: i32, status: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE uploaded_reports SET status = ? WHERE id = ?",
+            rusqlite::params![status, upload_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_uploaded_reports(&self, company_id: i32) -> Result<Vec<UploadedReport>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {UPLOADED_REPORT_COLUMNS}
+             FROM uploaded_reports u
+             WHERE u.company_id = ?
+             ORDER BY u.uploaded_at DESC, u.id DESC"
+        ))?;
+
+        let uploads = stmt.query_map([company_id], UploadedReport::from_row)?;
+        uploads.collect()
+    }
+
+    pub fn get_uploaded_report(&self, upload_id: i32) -> Result<Option<UploadedReport>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {UPLOADED_REPORT_COLUMNS} FROM uploaded_reports u WHERE u.id = ?"
+        ))?;
+
+        let mut rows = stmt.query_map([upload_id], UploadedReport::from_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn get_report_rows(&self, upload_id: i32) -> Result<Vec<ReportRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uploaded_report_id, row_number, invoice_no, invoice_date, customer_name_raw,
+                    gst_rate, cgst_amt, sgst_amt, igst_amt, total_amount, extra_json
+             FROM report_rows
+             WHERE uploaded_report_id = ?
+             ORDER BY row_number",
+        )?;
+
+        let rows = stmt.query_map([upload_id], |row| {
+            Ok(ReportRow {
+                id: row.get(0)?,
+                uploaded_report_id: row.get(1)?,
+                row_number: row.get(2)?,
+                invoice_no: row.get(3)?,
+                invoice_date: row.get(4)?,
+                customer_name_raw: row.get(5)?,
+                gst_rate: row.get(6)?,
+                cgst_amt: row.get(7)?,
+                sgst_amt: row.get(8)?,
+                igst_amt: row.get(9)?,
+                total_amount: row.get(10)?,
+                extra_json: row.get(11)?,
+            })
+        })?;
+
+        rows.collect()
+    }
+
+    /// Rebuild the sales report lines of an upload from its stored raw rows
+    pub fn reprocess_upload(&self, upload_id: i32) -> AppResult<ImportReport> {
+        let upload = self.require_upload(upload_id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "DELETE FROM sales_reports WHERE uploaded_report_id = ?",
+            [upload_id],
+        )?;
+        let report = self.transform_upload(upload.company_id, upload_id, ImportMode::BestEffort)?;
+        self.log_action(
+            Some(upload.company_id),
+            "reprocess_upload",
+            serde_json::json!({
+                "uploaded_report_id": upload_id,
+                "imported": report.imported,
+                "skipped": report.skipped,
+                "duplicates": report.duplicates,
+            }),
+        )?;
+        tx.commit()?;
+
+        Ok(report)
+    }
+
+    /// Remove every sales report line created by an upload. The raw rows are kept
+    /// so the upload can be re-processed later.
+    pub fn rollback_upload(&self, upload_id: i32) -> AppResult<i32> {
+        let upload = self.require_upload(upload_id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = self.conn.execute(
+            "DELETE FROM sales_reports WHERE uploaded_report_id = ?",
+            [upload_id],
+        )?;
+        self.set_upload_status(upload_id, UPLOAD_STATUS_ROLLED_BACK)?;
+        self.log_action(
+            Some(upload.company_id),
+            "rollback_upload",
+            serde_json::json!({ "uploaded_report_id": upload_id, "removed": removed }),
+        )?;
+        tx.commit()?;
+
+        println!("Rolled back upload {upload_id}: {removed} sales report lines removed");
+        Ok(removed as i32)
+    }
+
+    /// Split a company's invoices by GST rate, optionally limited to one upload, and
+    /// record every resulting invoice number in invoice_mappings. Splitting the same
+    /// invoices again yields the same numbers.
+    pub fn split_invoices(
+        &self,
+        company_id: i32,
+        uploaded_report_id: Option<i32>,
+    ) -> Result<Vec<SplitInvoice>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SALES_REPORT_COLUMNS}
+             FROM sales_reports
+             WHERE company_id = ?1 AND (?2 IS NULL OR uploaded_report_id = ?2)
+             ORDER BY inv_date, invno, line_no"
+        ))?;
+        let lines = stmt
+            .query_map(
+                rusqlite::params![company_id, uploaded_report_id],
+                SalesReport::from_row,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+
+        // Numbers used by other invoices, either as originals or as earlier splits
+        let originals: std::collections::HashSet<&str> =
+            lines.iter().map(|l| l.invno.as_str()).collect();
+        let mut taken = std::collections::HashSet::new();
+        let mut stmt = self.conn.prepare(
+            "SELECT invno, invno FROM sales_reports WHERE company_id = ?1
+             UNION
+             SELECT original_invoice_no, split_invoice_no FROM invoice_mappings WHERE company_id = ?1",
+        )?;
+        let numbers = stmt.query_map([company_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for number in numbers {
+            let (original, number) = number?;
+            if !originals.contains(original.as_str()) {
+                taken.insert(number);
+            }
+        }
+
+        let split = gst_split::split_invoices(&lines, &taken);
+
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = self.conn.prepare(
+                "INSERT INTO invoice_mappings (company_id, original_invoice_no, split_invoice_no, gst_rate)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(company_id, split_invoice_no)
+                 DO UPDATE SET original_invoice_no = excluded.original_invoice_no,
+                               gst_rate = excluded.gst_rate",
+            )?;
+            for invoice in &split {
+                stmt.execute(rusqlite::params![
+                    company_id,
+                    invoice.original_invno,
+                    invoice.invno,
+                    invoice.gst_rate
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        println!(
+            "Split {} invoices of company {company_id} into {} records",
+            originals.len(),
+            split.len()
+        );
+        Ok(split)
+    }
+
+    /// Look up the original invoice behind a (possibly split) invoice number
+    pub fn get_invoice_mapping(
+        &self,
+        company_id: i32,
+        invoice_no: &str,
+    ) -> Result<Option<InvoiceMapping>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, original_invoice_no, split_invoice_no, gst_rate, created_at
+             FROM invoice_mappings
+             WHERE company_id = ? AND split_invoice_no = ?",
+        )?;
+
+        let mut rows = stmt.query_map(rusqlite::params![company_id, invoice_no], |row| {
+            Ok(InvoiceMapping {
+                id: row.get(0)?,
+                company_id: row.get(1)?,
+                original_invoice_no: row.get(2)?,
+                split_invoice_no: row.get(3)?,
+                gst_rate: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })?;
+        rows.next().transpose()
+    }
+
+    /// Customers in the sales data (optionally of one upload) without a customer record,
+    /// largest total value first. A line's cust_name is matched against the name and
+    /// tally name of customers, its cust_code against the tally name (customer imports
+    /// store the code there).
+    pub fn get_unmapped_customers(
+        &self,
+        company_id: i32,
+        uploaded_report_id: Option<i32>,
+    ) -> Result<Vec<UnmappedCustomer>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(s.cust_code), MIN(s.cust_name), COUNT(DISTINCT s.invno), COUNT(*),
+                    SUM(s.inv_val)
+             FROM sales_reports s
+             WHERE s.company_id = ?1
+               AND (?2 IS NULL OR s.uploaded_report_id = ?2)
+               AND NOT EXISTS (
+                   SELECT 1 FROM customers c
+                   WHERE c.company_id = s.company_id
+                     AND (LOWER(TRIM(c.customer_name)) = LOWER(TRIM(s.cust_name))
+                          OR LOWER(TRIM(c.tally_name)) = LOWER(TRIM(s.cust_name))
+                          OR (TRIM(s.cust_code) <> ''
+                              AND LOWER(TRIM(c.tally_name)) = LOWER(TRIM(s.cust_code))))
+               )
+             GROUP BY LOWER(TRIM(s.cust_name))
+             ORDER BY SUM(s.inv_val) DESC, MIN(s.cust_name)",
+        )?;
+
+        let customers =
+            stmt.query_map(rusqlite::params![company_id, uploaded_report_id], |row| {
+                Ok(UnmappedCustomer {
+                    cust_code: row.get(0)?,
+                    cust_name: row.get(1)?,
+                    invoice_count: row.get(2)?,
+                    line_count: row.get(3)?,
+                    total_inv_val: row.get(4)?,
+                })
+            })?;
+        customers.collect()
+    }
+
+    /// Tally ledger of each customer
+    fn customer_ledgers(&self, company_id: i32) -> Result<CustomerLookup<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT customer_name, tally_name FROM customers WHERE company_id = ?")?;
+        let rows = stmt.query_map([company_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut ledgers = CustomerLookup::default();
+        for row in rows {
+            let (customer_name, tally_name) = row?;
+            ledgers.insert(&customer_name, &tally_name, tally_name.clone());
+        }
+        Ok(ledgers)
+    }
+
+    /// GST state code of each customer with a valid GSTIN
+    fn customer_states(&self, company_id: i32) -> Result<CustomerLookup<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT customer_name, tally_name, gst_state_code FROM customers
+             WHERE company_id = ? AND gst_state_code IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([company_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut states = CustomerLookup::default();
+        for row in rows {
+            let (customer_name, tally_name, state_code) = row?;
+            states.insert(&customer_name, &tally_name, state_code);
+        }
+        Ok(states)
+    }
+
+    /// Invoices whose GST contradicts the place of supply: customers in the company's
+    /// state pay CGST+SGST, customers elsewhere IGST. Customers without a valid GSTIN
+    /// have no known state and are not checked.
+    pub fn check_place_of_supply(
+        &self,
+        company_id: i32,
+        uploaded_report_id: Option<i32>,
+    ) -> AppResult<Vec<PlaceOfSupplyIssue>> {
+        let company = self.require_company(company_id)?;
+        let Some(company_state) = company.state_code else {
+            return Err(AppError::validation(
+                "state_code",
+                None,
+                format!(
+                    "Set the state or GSTIN of {} to check place of supply",
+                    company.name
+                ),
+            ));
+        };
+        self.place_of_supply_issues(company_id, &company_state, uploaded_report_id)
+            .map_err(AppError::from)
+    }
+
+    fn place_of_supply_issues(
+        &self,
+        company_id: i32,
+        company_state: &str,
+        uploaded_report_id: Option<i32>,
+    ) -> Result<Vec<PlaceOfSupplyIssue>> {
+        let states = self.customer_states(company_id)?;
+        let mut stmt = self.conn.prepare(
+            "SELECT invno, MIN(inv_date), MIN(cust_name), MIN(cust_code), SUM(c_gst + s_gst),
+                    SUM(igst)
+             FROM sales_reports
+             WHERE company_id = ?1 AND (?2 IS NULL OR uploaded_report_id = ?2)
+             GROUP BY invno
+             ORDER BY MIN(inv_date), invno",
+        )?;
+        let invoices =
+            stmt.query_map(rusqlite::params![company_id, uploaded_report_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Money>(4)?,
+                    row.get::<_, Money>(5)?,
+                ))
+            })?;
+
+        let mut issues = Vec::new();
+        for invoice in invoices {
+            let (invno, inv_date, cust_name, cust_code, cgst_sgst, igst) = invoice?;
+            let Some(customer_state) = states.get(&cust_name, &cust_code) else {
+                continue;
+            };
+            let expected = place_of_supply::expected_tax_type(company_state, customer_state);
+            if let Some(message) = place_of_supply::check(expected, cgst_sgst, igst) {
+                issues.push(PlaceOfSupplyIssue {
+                    invno,
+                    inv_date,
+                    cust_name,
+                    customer_state_code: customer_state.clone(),
+                    expected,
+                    message,
+                });
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Write the transformed sales of an upload as a Tally Prime XML file of sales
+    /// vouchers and record it in tally_exports. Files go to `output_dir`, or to a
+    /// tally_exports folder next to the database. When the company's state is known,
+    /// invoices must pass the place-of-supply check first.
+    pub fn generate_tally_export(
+        &self,
+        uploaded_report_id: i32,
+        output_dir: Option<String>,
+    ) -> AppResult<TallyExport> {
+        let upload = self.require_upload(uploaded_report_id)?;
+        if upload.status != UPLOAD_STATUS_TRANSFORMED && upload.status != UPLOAD_STATUS_EXPORTED {
+            return Err(AppError::validation(
+                "status",
+                None,
+                format!(
+                    "Upload {uploaded_report_id} is {}; only transformed uploads can be exported to Tally",
+                    upload.status
+                ),
+            ));
+        }
+
+        let company = self.require_company(upload.company_id)?;
+
+        let unmapped = self.get_unmapped_customers(upload.company_id, Some(uploaded_report_id))?;
+        if !unmapped.is_empty() {
+            let names: Vec<&str> = unmapped.iter().map(|c| c.cust_name.as_str()).collect();
+            return Err(AppError::UnmappedCustomers {
+                message: format!(
+                    "Map or create these customers before generating the Tally export: {}",
+                    names.join(", ")
+                ),
+                customers: unmapped,
+            });
+        }
+
+        if let Some(company_state) = &company.state_code {
+            let issues = self.place_of_supply_issues(
+                upload.company_id,
+                company_state,
+                Some(uploaded_report_id),
+            )?;
+            if !issues.is_empty() {
+                let invoices: Vec<&str> = issues.iter().map(|i| i.invno.as_str()).collect();
+                return Err(AppError::PlaceOfSupply {
+                    message: format!(
+                        "Fix the GST type of these invoices before generating the Tally export: {}",
+                        invoices.join(", ")
+                    ),
+                    issues,
+                });
+            }
+        }
+
+        let invoices = self.split_invoices(upload.company_id, Some(uploaded_report_id))?;
+        if invoices.is_empty() {
+            return Err(AppError::validation(
+                "uploaded_report_id",
+                None,
+                format!("Upload {uploaded_report_id} has no sales lines to export"),
+            ));
+        }
+
+        let ledgers = self.customer_ledgers(upload.company_id)?;
+        let mut vouchers = Vec::with_capacity(invoices.len());
+        let mut missing: Vec<&str> = Vec::new();
+        for invoice in &invoices {
+            match ledgers.get(&invoice.cust_name, &invoice.cust_code) {
+                Some(party_ledger) => vouchers.push(TallyVoucher {
+                    invoice,
+                    party_ledger,
+                }),
+                None if !missing.contains(&invoice.cust_name.as_str()) => {
+                    missing.push(&invoice.cust_name)
+                }
+                None => {}
+            }
+        }
+        // The unmapped check above should have caught these
+        if !missing.is_empty() {
+            return Err(AppError::not_found(format!(
+                "No Tally ledger found for these customers: {}",
+                missing.join(", ")
+            )));
+        }
+        let xml = tally::sales_vouchers_xml(&company.name, &vouchers, &TallyLedgers::default())?;
+
+        let dir = match output_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => self
+                .database_path()
+                .filter(|path| !path.is_empty())
+                .and_then(|path| Path::new(&path).parent().map(|p| p.join(TALLY_EXPORT_DIR)))
+                .ok_or_else(|| {
+                    AppError::validation(
+                        "output_dir",
+                        None,
+                        "No output folder given for the Tally export",
+                    )
+                })?,
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let timestamp: String =
+            self.conn
+                .query_row("SELECT strftime('%Y%m%d_%H%M%S', 'now')", [], |row| {
+                    row.get(0)
+                })?;
+        let file_path = dir.join(format!("tally_upload{uploaded_report_id}_{timestamp}.xml"));
+        std::fs::write(&file_path, xml)?;
+
+        let original_count = invoices
+            .iter()
+            .map(|i| i.original_invno.as_str())
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        let notes = format!("{} vouchers from {original_count} invoices", invoices.len());
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "INSERT INTO tally_exports (company_id, uploaded_report_id, file_path, notes)
+             VALUES (?, ?, ?, ?)",
+            rusqlite::params![
+                upload.company_id,
+                uploaded_report_id,
+                file_path.to_string_lossy(),
+                notes
+            ],
+        )?;
+        let export_id = self.conn.last_insert_rowid();
+        self.set_upload_status(uploaded_report_id, UPLOAD_STATUS_EXPORTED)?;
+        self.log_action(
+            Some(upload.company_id),
+            "generate_tally_export",
+            serde_json::json!({
+                "uploaded_report_id": uploaded_report_id,
+                "tally_export_id": export_id,
+                "file_path": file_path,
+                "notes": notes,
+            }),
+        )?;
+        tx.commit()?;
+
+        println!(
+            "Exported upload {uploaded_report_id} to Tally: {}",
+            file_path.display()
+        );
+        Ok(self.conn.query_row(
+            "SELECT id, company_id, uploaded_report_id, generated_at, file_path, notes
+             FROM tally_exports WHERE id = ?",
+            [export_id],
+            TallyExport::from_row,
+        )?)
+    }
+
+    pub fn get_tally_exports(&self, company_id: i32) -> Result<Vec<TallyExport>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, uploaded_report_id, generated_at, file_path, notes
+             FROM tally_exports
+             WHERE company_id = ?
+             ORDER BY generated_at DESC, id DESC",
+        )?;
+
+        let exports = stmt.query_map([company_id], TallyExport::from_row)?;
+        exports.collect()
+    }
+
+    // Single-record lookups used for audit before/after values
+    fn get_company(&self, id: i32) -> Result<Option<Company>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {COMPANY_COLUMNS} FROM companies WHERE id = ?"
+        ))?;
+        let mut rows = stmt.query_map([id], Company::from_row)?;
+        rows.next().transpose()
+    }
+
+    fn get_customer(&self, id: i32) -> Result<Option<Customer>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {CUSTOMER_COLUMNS}
+             FROM customers c
+             LEFT JOIN categories cat ON c.category_id = cat.id
+             WHERE c.id = ?"
+        ))?;
+        let mut rows = stmt.query_map([id], Customer::from_row)?;
+        rows.next().transpose()
+    }
+
+    fn get_category(&self, id: i32) -> Result<Option<Category>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, company_id, name, description, created_at FROM categories WHERE id = ?",
+        )?;
+        let mut rows = stmt.query_map([id], |row| {
+            Ok(Category {
+                id: row.get(0)?,
+                company_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        rows.next().transpose()
+    }
+
+    fn get_sales_report(&self, id: i32) -> Result<Option<SalesReport>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SALES_REPORT_COLUMNS} FROM sales_reports WHERE id = ?"
+        ))?;
+        let mut rows = stmt.query_map([id], SalesReport::from_row)?;
+        rows.next().transpose()
+    }
+
+    // Audit log methods
+    fn log_action(
+        &self,
+        company_id: Option<i32>,
+        user_action: &str,
+        details: serde_json::Value,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO audit_logs (company_id, user_action, details_json) VALUES (?, ?, ?)",
+            rusqlite::params![company_id, user_action, details.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Record a change to one record with its values before and after
+    fn log_change<T: Serialize>(
+        &self,
+        company_id: Option<i32>,
+        user_action: &str,
+        id: i32,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<()> {
+        self.log_action(
+            company_id,
+            user_action,
+            serde_json::json!({ "id": id, "before": before, "after": after }),
+        )
+    }
+
+    pub fn get_audit_logs(
+        &self,
+        page: i32,
+        page_size: i32,
+        filters: Option<AuditLogFilters>,
+    ) -> Result<(Vec<AuditLog>, i32)> {
+        let offset = (page - 1) * page_size;
+
+        let mut where_clauses = vec!["1 = 1".to_string()];
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(f) = filters {
+            if let Some(company_id) = f.company_id {
+                where_clauses.push("company_id = ?".to_string());
+                params.push(Box::new(company_id));
+            }
+            if let Some(user_action) = f.user_action.filter(|a| !a.is_empty()) {
+                where_clauses.push("user_action = ?".to_string());
+                params.push(Box::new(user_action));
+            }
+            if let Some(search) = f.search.filter(|s| !s.is_empty()) {
+                where_clauses.push("details_json LIKE ?".to_string());
+                params.push(Box::new(format!("%{search}%")));
+            }
+            if let Some(date_from) = f.date_from {
+                where_clauses.push("date(timestamp) >= date(?)".to_string());
+                params.push(Box::new(date_from));
+            }
+            if let Some(date_to) = f.date_to {
+                where_clauses.push("date(timestamp) <= date(?)".to_string());
+                params.push(Box::new(date_to));
+            }
+        }
+
+        let where_clause = where_clauses.join(" AND ");
+
+        let total_count: i32 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM audit_logs WHERE {where_clause}"),
+            rusqlite::params_from_iter(params.iter().map(|v| v.as_ref())),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, company_id, user_action, details_json, timestamp
+             FROM audit_logs
+             WHERE {where_clause}
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ? OFFSET ?"
+        ))?;
+        let mut final_params: Vec<&dyn rusqlite::ToSql> =
+            params.iter().map(|v| v.as_ref()).collect();
+        final_params.push(&page_size);
+        final_params.push(&offset);
+
+        let logs = stmt.query_map(rusqlite::params_from_iter(final_params), |row| {
+            Ok(AuditLog {
+                id: row.get(0)?,
+                company_id: row.get(1)?,
+                user_action: row.get(2)?,
+                details_json: row.get(3)?,
+                timestamp: row.get(4)?,
+            })
+        })?;
+
+        Ok((logs.collect::<Result<Vec<_>>>()?, total_count))
+    }
+
+    fn require_company(&self, company_id: i32) -> AppResult<Company> {
+        self.get_company(company_id)?
+            .ok_or_else(|| AppError::not_found(format!("Company {company_id} not found")))
+    }
+
+    fn require_upload(&self, upload_id: i32) -> AppResult<UploadedReport> {
+        self.get_uploaded_report(upload_id)?
+            .ok_or_else(|| AppError::not_found(format!("Upload {upload_id} not found")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> DatabaseManager {
+        let db = DatabaseManager::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+        db.seed_sample_data().unwrap();
+        db
+    }
+
+    /// A seeded database in `dir`, for tests that take backups
+    fn file_db(dir: &Path) -> DatabaseManager {
+        let conn = Connection::open(dir.join(DB_FILE_NAME)).unwrap();
+        let db = DatabaseManager::from_connection(conn).unwrap();
+        db.seed_sample_data().unwrap();
+        db
+    }
+
+    const MULTI_LINE_CSV: &str = "invno,cust_code,cust_name,inv_date,part_code,qty,inv_val
+INV001,C1,Alpha,2025-05-01,P1,1,100
+INV001,C1,Alpha,2025-05-01,P2,2,200
+INV001,C1,Alpha,2025-05-01,P3,3,300
+INV002,C2,Beta,2025-05-02,P1,1,50
+";
+
+    #[test]
+    fn test_import_keeps_every_invoice_line() {
+        let db = test_db();
+
+        let imported = db
+            .import_sales_reports_csv(
+                1,
+                MULTI_LINE_CSV.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap()
+            .imported;
+        assert_eq!(imported, 4);
+
+        let lines: Vec<(String, i32)> = db
+            .get_sales_reports_by_company(1)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.invno, r.line_no))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("INV002".to_string(), 1),
+                ("INV001".to_string(), 1),
+                ("INV001".to_string(), 2),
+                ("INV001".to_string(), 3),
+            ]
+        );
+
+        // Forcing a re-import of the same file treats every line of known invoices as duplicates
+        let imported = db
+            .import_sales_reports_csv(
+                1,
+                MULTI_LINE_CSV.to_string(),
+                None,
+                true,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap()
+            .imported;
+        assert_eq!(imported, 0);
+    }
+
+    #[test]
+    fn test_rejects_reimport_of_identical_content() {
+        let db = test_db();
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            Some("may.csv".to_string()),
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        let first = db.get_uploaded_reports(1).unwrap()[0].id;
+
+        // Same content with different header case, column spacing and trailing blank row
+        let reformatted =
+            MULTI_LINE_CSV.replace("invno,cust_code", "INVNO, Cust_Code") + ",,,,,,\n";
+        let err = db
+            .import_sales_reports_csv(
+                1,
+                reformatted.clone(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap_err();
+        match err {
+            AppError::Duplicate {
+                message,
+                existing_id,
+            } => {
+                assert_eq!(existing_id, Some(first));
+                assert!(message.contains("may.csv"));
+            }
+            other => panic!("expected a duplicate error, got {other:?}"),
         }
+        assert_eq!(db.get_uploaded_reports(1).unwrap().len(), 1);
 
-        if fields.is_empty() {
-            return Ok(false);
+        // Forcing records a new upload even though the content matches
+        db.import_sales_reports_csv(1, reformatted, None, true, ImportMode::BestEffort, None)
+            .unwrap();
+        assert_eq!(db.get_uploaded_reports(1).unwrap().len(), 2);
+
+        // Once rolled back, the content can be imported again without forcing
+        for upload in db.get_uploaded_reports(1).unwrap() {
+            db.rollback_upload(upload.id).unwrap();
         }
+        let imported = db
+            .import_sales_reports_csv(
+                1,
+                MULTI_LINE_CSV.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap()
+            .imported;
+        assert_eq!(imported, 4);
+    }
 
-        values.push(Box::new(id));
+    #[test]
+    fn test_split_invoices_records_mappings() {
+        let db = test_db();
+        let csv = "invno,cust_code,cust_name,inv_date,part_code,ass_val,c_gst,s_gst,inv_val
+251,C1,Alpha,2025-05-01,P1,100,9,9,118
+251,C1,Alpha,2025-05-01,P2,50,7,7,64
+252,C2,Beta,2025-05-02,P1,10,0.9,0.9,11.8
+";
+        db.import_sales_reports_csv(
+            1,
+            csv.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        let split = db.split_invoices(1, None).unwrap();
+        let numbers: Vec<&str> = split.iter().map(|s| s.invno.as_str()).collect();
+        assert_eq!(numbers, vec!["251", "251A", "252"]);
+
+        let mapping = db.get_invoice_mapping(1, "251A").unwrap().unwrap();
+        assert_eq!(mapping.original_invoice_no, "251");
+        assert_eq!(mapping.gst_rate, 28.0);
+        assert!(db.get_invoice_mapping(1, "999").unwrap().is_none());
+
+        // Splitting again reuses the recorded numbers
+        let again = db.split_invoices(1, None).unwrap();
+        assert_eq!(again.len(), 3);
+        assert_eq!(again[1].invno, "251A");
+    }
 
-        let query = format!(
-            "UPDATE sales_reports SET {} WHERE id = ?",
-            fields.join(", ")
+    #[test]
+    fn test_generate_tally_export() {
+        let db = test_db();
+        db.create_customer(CreateCustomerRequest {
+            company_id: 1,
+            customer_name: "Alpha".to_string(),
+            tally_name: "Alpha Traders".to_string(),
+            gst_no: None,
+            category_id: None,
+        })
+        .unwrap();
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        let upload_id = db.get_uploaded_reports(1).unwrap()[0].id;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = Some(dir.path().to_string_lossy().to_string());
+
+        // Beta has no customer record, so generation is refused
+        let err = db
+            .generate_tally_export(upload_id, output_dir.clone())
+            .unwrap_err();
+        let AppError::UnmappedCustomers {
+            customers: unmapped,
+            ..
+        } = err
+        else {
+            panic!("expected unmapped customers, got {err:?}");
+        };
+        assert_eq!(unmapped.len(), 1);
+        assert_eq!(unmapped[0].cust_name, "Beta");
+        assert_eq!(unmapped[0].invoice_count, 1);
+        assert_eq!(unmapped[0].total_inv_val, Money::from_rupees(50.0));
+        assert!(db.get_tally_exports(1).unwrap().is_empty());
+
+        db.create_customer(CreateCustomerRequest {
+            company_id: 1,
+            customer_name: "BETA ".to_string(),
+            tally_name: "Beta Ltd".to_string(),
+            gst_no: None,
+            category_id: None,
+        })
+        .unwrap();
+        assert!(db.get_unmapped_customers(1, None).unwrap().is_empty());
+
+        let export = db.generate_tally_export(upload_id, output_dir).unwrap();
+        assert_eq!(export.uploaded_report_id, upload_id);
+        assert_eq!(export.notes.as_deref(), Some("2 vouchers from 2 invoices"));
+
+        let xml = std::fs::read_to_string(&export.file_path).unwrap();
+        assert!(xml.contains("<PARTYLEDGERNAME>Alpha Traders</PARTYLEDGERNAME>"));
+        assert!(xml.contains("<PARTYLEDGERNAME>Beta Ltd</PARTYLEDGERNAME>"));
+
+        let upload = db.get_uploaded_report(upload_id).unwrap().unwrap();
+        assert_eq!(upload.status, UPLOAD_STATUS_EXPORTED);
+        assert_eq!(db.get_tally_exports(1).unwrap().len(), 1);
+
+        db.rollback_upload(upload_id).unwrap();
+        assert!(db.generate_tally_export(upload_id, None).is_err());
+    }
+
+    #[test]
+    fn test_customers_match_by_code() {
+        let db = test_db();
+        // Customer imports keep the customer code as the tally name
+        db.create_customer(CreateCustomerRequest {
+            company_id: 1,
+            customer_name: "Alpha Traders Pvt Ltd".to_string(),
+            tally_name: "C1".to_string(),
+            gst_no: Some("29AAGCB7383J1Z4".to_string()),
+            category_id: None,
+        })
+        .unwrap();
+        let csv = "invno,cust_code,cust_name,inv_date,part_code,qty,ass_val,c_gst,s_gst,inv_val
+INV001,C1,Alpha Trdrs,2025-05-01,P1,1,100,9,9,118
+INV002,,Beta,2025-05-02,P1,1,50,0,0,50
+";
+        db.import_sales_reports_csv(
+            1,
+            csv.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        let upload_id = db.get_uploaded_reports(1).unwrap()[0].id;
+
+        // Only Beta is unmapped; its blank code matches no blank tally name
+        db.create_customer(CreateCustomerRequest {
+            company_id: 1,
+            customer_name: "Gamma".to_string(),
+            tally_name: String::new(),
+            gst_no: None,
+            category_id: None,
+        })
+        .unwrap();
+        let unmapped = db.get_unmapped_customers(1, None).unwrap();
+        assert_eq!(unmapped.len(), 1);
+        assert_eq!(unmapped[0].cust_name, "Beta");
+        db.create_customer(CreateCustomerRequest {
+            company_id: 1,
+            customer_name: "Beta".to_string(),
+            tally_name: "Beta Ltd".to_string(),
+            gst_no: None,
+            category_id: None,
+        })
+        .unwrap();
+        assert!(db.get_unmapped_customers(1, None).unwrap().is_empty());
+
+        // The state of the customer found by code is checked
+        db.update_company(
+            1,
+            UpdateCompanyRequest {
+                state_code: Some("27".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let issues = db.check_place_of_supply(1, None).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].invno, "INV001");
+        assert_eq!(issues[0].customer_state_code, "29");
+
+        // And its tally name becomes the party ledger
+        db.update_company(
+            1,
+            UpdateCompanyRequest {
+                state_code: Some(String::new()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let export = db
+            .generate_tally_export(upload_id, Some(dir.path().to_string_lossy().to_string()))
+            .unwrap();
+        let xml = std::fs::read_to_string(&export.file_path).unwrap();
+        assert!(xml.contains("<PARTYLEDGERNAME>C1</PARTYLEDGERNAME>"));
+    }
+
+    #[test]
+    fn test_place_of_supply_gates_tally_export() {
+        let db = test_db();
+        let company = |gstin: Option<&str>, state_code: Option<&str>| UpdateCompanyRequest {
+            gstin: gstin.map(str::to_string),
+            state_code: state_code.map(str::to_string),
+            ..Default::default()
+        };
+        assert!(matches!(
+            db.check_place_of_supply(1, None),
+            Err(AppError::Validation { ref field, .. }) if field == "state_code"
+        ));
+        assert!(db.update_company(1, company(None, Some("99"))).is_err());
+        assert!(db
+            .update_company(1, company(Some("27AAPFU0939F1ZX"), None))
+            .is_err());
+        db.update_company(1, company(Some("27aapfu0939f1zv"), None))
+            .unwrap();
+        let stored = db.get_company(1).unwrap().unwrap();
+        assert_eq!(stored.gstin.as_deref(), Some("27AAPFU0939F1ZV"));
+        assert_eq!(stored.state_code.as_deref(), Some("27"));
+        assert!(db.update_company(1, company(None, Some("29"))).is_err());
+
+        for (name, gst_no) in [
+            ("Alpha", Some("27AAACR5055K1Z7")),
+            ("Beta", Some("29AAGCB7383J1Z4")),
+            ("Gamma", None),
+        ] {
+            db.create_customer(CreateCustomerRequest {
+                company_id: 1,
+                customer_name: name.to_string(),
+                tally_name: name.to_string(),
+                gst_no: gst_no.map(str::to_string),
+                category_id: None,
+            })
+            .unwrap();
+        }
+        let csv = "invno,cust_name,inv_date,ass_val,c_gst,s_gst,igst,inv_val\n\
+                   I1,Alpha,2025-05-01,100,9,9,0,118\n\
+                   I2,Alpha,2025-05-01,100,0,0,18,118\n\
+                   I3,Beta,2025-05-02,100,0,0,18,118\n\
+                   I4,Beta,2025-05-02,100,9,9,0,118\n\
+                   I5,Gamma,2025-05-03,100,0,0,18,118\n";
+        db.import_sales_reports_csv(
+            1,
+            csv.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        let upload_id = db.get_uploaded_reports(1).unwrap()[0].id;
+
+        // Gamma has no GSTIN, so its state is unknown and I5 is not checked
+        let issues = db.check_place_of_supply(1, None).unwrap();
+        let found: Vec<(&str, &str, TaxType)> = issues
+            .iter()
+            .map(|i| (i.invno.as_str(), i.customer_state_code.as_str(), i.expected))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("I2", "27", TaxType::CgstSgst), ("I4", "29", TaxType::Igst)]
         );
-        let mut stmt = self.conn.prepare(&query)?;
 
-        let changes = stmt.execute(rusqlite::params_from_iter(
-            values.iter().map(|v| v.as_ref()),
-        ))?;
-        Ok(changes > 0)
+        let dir = tempfile::tempdir().unwrap();
+        let err = db
+            .generate_tally_export(upload_id, Some(dir.path().to_string_lossy().to_string()))
+            .unwrap_err();
+        let AppError::PlaceOfSupply { issues, .. } = err else {
+            panic!("expected place of supply issues, got {err:?}");
+        };
+        assert_eq!(issues.len(), 2);
+        assert!(db.get_tally_exports(1).unwrap().is_empty());
     }
 
-    pub fn delete_sales_report(&self, id: i32) -> Result<bool> {
-        let mut stmt = self
-            .conn
-            .prepare("DELETE FROM sales_reports WHERE id = ?")?;
-        let changes = stmt.execute([id])?;
-        Ok(changes > 0)
+    #[test]
+    fn test_company_lifecycle() {
+        // A new database starts without companies
+        let db = DatabaseManager::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(db.get_companies(true).unwrap().is_empty());
+        assert!(db.seed_sample_data().unwrap());
+        assert!(!db.seed_sample_data().unwrap());
+
+        let request = |key: &str| CreateCompanyRequest {
+            name: "Delta Industries".to_string(),
+            key: key.to_string(),
+            gstin: Some("29AAGCB7383J1Z4".to_string()),
+            address: Some("Peenya, Bengaluru".to_string()),
+            ..Default::default()
+        };
+        let id = db.create_company(request("delta")).unwrap();
+        let company = db.get_company(id).unwrap().unwrap();
+        assert_eq!(company.state_code.as_deref(), Some("29"));
+        assert_eq!(company.fiscal_year_start, 4);
+        assert_eq!(db.get_re_year_codes(id).unwrap().len(), 10);
+        assert!(matches!(
+            db.create_company(request("delta")),
+            Err(AppError::Duplicate { .. })
+        ));
+        assert!(db
+            .create_company(CreateCompanyRequest {
+                fiscal_year_start: Some(13),
+                ..request("delta_2")
+            })
+            .is_err());
+
+        db.create_customer(CreateCustomerRequest {
+            company_id: id,
+            customer_name: "Alpha".to_string(),
+            tally_name: "Alpha".to_string(),
+            gst_no: None,
+            category_id: None,
+        })
+        .unwrap();
+        db.import_sales_reports_csv(
+            id,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        let preview = db.company_deletion_preview(id).unwrap();
+        let rows = |table: &str| {
+            preview
+                .tables
+                .iter()
+                .find(|t| t.table == table)
+                .map(|t| t.rows)
+        };
+        assert_eq!(rows("sales_reports"), Some(4));
+        assert_eq!(rows("uploaded_reports"), Some(1));
+        assert_eq!(rows("customers"), Some(1));
+        assert_eq!(rows("re_year_codes"), Some(10));
+        assert_eq!(
+            preview.total_rows,
+            preview.tables.iter().map(|t| t.rows).sum::<i64>()
+        );
+
+        // Updates trim the name and key and refuse blank ones
+        let rename = |name: &str, key: &str| UpdateCompanyRequest {
+            name: Some(name.to_string()),
+            key: Some(key.to_string()),
+            ..Default::default()
+        };
+        assert!(db.update_company(id, rename("Delta", "  ")).is_err());
+        assert!(db.update_company(id, rename(" ", "delta")).is_err());
+        assert!(db
+            .update_company(id, rename(" Delta Industries ", " delta "))
+            .unwrap());
+        let company = db.get_company(id).unwrap().unwrap();
+        assert_eq!(
+            (company.name.as_str(), company.key.as_str()),
+            ("Delta Industries", "delta")
+        );
+
+        // Only archived companies can be deleted, and only with their key
+        assert!(db.delete_company(id, "delta").is_err());
+        assert!(db.archive_company(id).unwrap());
+        assert!(!db.archive_company(id).unwrap());
+        assert!(db.get_companies(false).unwrap().iter().all(|c| c.id != id));
+        assert!(db.get_companies(true).unwrap().iter().any(|c| c.id == id));
+        assert!(matches!(
+            db.delete_company(id, "Delta"),
+            Err(AppError::Validation { ref field, .. }) if field == "confirm_key"
+        ));
+
+        let deleted = db.delete_company(id, "delta").unwrap();
+        assert_eq!(
+            deleted.iter().map(|t| t.rows).sum::<i64>(),
+            preview.total_rows
+        );
+        assert!(db.get_company(id).unwrap().is_none());
+        assert_eq!(
+            db.company_row_counts(id)
+                .unwrap()
+                .iter()
+                .map(|t| t.rows)
+                .sum::<i64>(),
+            0
+        );
+        assert_eq!(db.get_customers_by_company(1).unwrap().len(), 1);
+
+        let (logs, _) = db.get_audit_logs(1, 1, None).unwrap();
+        assert_eq!(logs[0].user_action, "delete_company");
     }
 
-    pub fn export_sales_reports_csv(
-        &self,
-        company_id: i32,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let reports = self.get_sales_reports_by_company(company_id)?;
+    #[test]
+    fn test_mutations_are_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = file_db(dir.path());
+        let id = db
+            .create_customer(CreateCustomerRequest {
+                company_id: 1,
+                customer_name: "Alpha".to_string(),
+                tally_name: "Alpha".to_string(),
+                gst_no: None,
+                category_id: None,
+            })
+            .unwrap();
+        db.update_customer(
+            id,
+            UpdateCustomerRequest {
+                customer_name: None,
+                tally_name: Some("Alpha Traders".to_string()),
+                gst_no: None,
+                category_id: None,
+            },
+        )
+        .unwrap();
+        db.delete_customer(id).unwrap();
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        // Lines created by an import are summarised in one entry
+        let (logs, total) = db.get_audit_logs(1, 10, None).unwrap();
+        let actions: Vec<&str> = logs.iter().map(|l| l.user_action.as_str()).collect();
+        assert_eq!(total, 4);
+        assert_eq!(
+            actions,
+            vec![
+                "import_sales_reports_csv",
+                "delete_customer",
+                "update_customer",
+                "create_customer"
+            ]
+        );
 
-        let mut wtr = csv::Writer::from_writer(Cursor::new(Vec::new()));
+        let update: serde_json::Value = serde_json::from_str(&logs[2].details_json).unwrap();
+        assert_eq!(update["before"]["tally_name"], "Alpha");
+        assert_eq!(update["after"]["tally_name"], "Alpha Traders");
+
+        let filters = AuditLogFilters {
+            company_id: Some(1),
+            user_action: Some("update_customer".to_string()),
+            search: Some("Traders".to_string()),
+            date_from: None,
+            date_to: None,
+        };
+        let (logs, total) = db.get_audit_logs(1, 10, Some(filters)).unwrap();
+        assert_eq!((logs.len(), total), (1, 1));
+
+        // Clearing keeps the trail and records itself
+        let token = db.prepare_clear_data(ClearScope::Everything).unwrap().token;
+        db.clear_data(ClearScope::Everything, &token).unwrap();
+        let (logs, total) = db.get_audit_logs(1, 2, None).unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(logs[0].user_action, "clear_data");
+        assert!(logs.iter().all(|l| l.company_id.is_none()));
+    }
 
-        // Write header
-        wtr.write_record([
-            "cust_code",
-            "cust_name",
-            "inv_date",
-            "RE",
-            "invno",
-            "part_code",
-            "part_name",
-            "tariff",
-            "qty",
-            "bas_price",
-            "ass_val",
-            "c_gst",
-            "s_gst",
-            "igst",
-            "amot",
-            "inv_val",
-            "igst_yes_no",
-            "percentage",
-        ])?;
+    #[test]
+    fn test_clear_data_is_scoped_confirmed_and_recoverable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = file_db(dir.path());
+        for company_id in [1, 2] {
+            db.import_sales_reports_csv(
+                company_id,
+                MULTI_LINE_CSV.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        }
+        db.conn
+            .execute(
+                "INSERT INTO invoice_mappings (company_id, original_invoice_no, split_invoice_no, gst_rate)
+                 VALUES (1, 'INV001', 'INV001-A', 18), (1, 'INV002', 'INV002-A', 18),
+                        (2, 'INV001', 'INV001-A', 18)",
+                [],
+            )
+            .unwrap();
+        let count = |db: &DatabaseManager, sql: &str| -> i64 {
+            db.conn.query_row(sql, [], |row| row.get(0)).unwrap()
+        };
+
+        // Sales of one company on one day
+        let scope = ClearScope::Sales {
+            company_id: Some(1),
+            date_from: Some("01/05/2025".to_string()),
+            date_to: Some("2025-05-01".to_string()),
+        };
+        let preview = db.prepare_clear_data(scope.clone()).unwrap();
+        assert_eq!(preview.total_rows, 4);
+        assert_eq!(preview.tables[0].rows, 1);
+        assert_eq!(preview.tables[1].rows, 3);
+
+        // Tokens must match the scope and are used up by a failed attempt
+        let other = ClearScope::Company { company_id: 1 };
+        assert!(db.clear_data(other, &preview.token).is_err());
+        assert!(db.clear_data(scope.clone(), &preview.token).is_err());
+        db.prepare_clear_data(scope.clone()).unwrap();
+        assert!(db.clear_data(scope.clone(), "guess").is_err());
+
+        let token = db.prepare_clear_data(scope.clone()).unwrap().token;
+        let result = db.clear_data(scope.clone(), &token).unwrap();
+        assert_eq!(result.deleted.iter().map(|t| t.rows).sum::<i64>(), 4);
+        assert!(Path::new(&result.backup_path).starts_with(dir.path().join(BACKUP_DIR)));
+        assert!(db.clear_data(scope, &token).is_err());
+        assert_eq!(
+            count(
+                &db,
+                "SELECT COUNT(*) FROM sales_reports WHERE company_id = 1"
+            ),
+            1
+        );
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM sales_reports"), 5);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM invoice_mappings"), 2);
+
+        // Restoring the snapshot undoes the clear
+        db.restore_backup(Path::new(&result.backup_path)).unwrap();
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM sales_reports"), 8);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM invoice_mappings"), 3);
+        let (logs, _) = db.get_audit_logs(1, 1, None).unwrap();
+        assert_eq!(logs[0].user_action, "restore_backup");
+
+        // Clearing a company keeps the company and its settings
+        let scope = ClearScope::Company { company_id: 2 };
+        let token = db.prepare_clear_data(scope.clone()).unwrap().token;
+        db.clear_data(scope, &token).unwrap();
+        assert_eq!(
+            count(
+                &db,
+                "SELECT COUNT(*) FROM sales_reports WHERE company_id = 2"
+            ),
+            0
+        );
+        assert_eq!(
+            count(
+                &db,
+                "SELECT COUNT(*) FROM uploaded_reports WHERE company_id = 2"
+            ),
+            0
+        );
+        assert!(db.get_company(2).unwrap().is_some());
+        assert_eq!(db.get_re_year_codes(2).unwrap().len(), 10);
+        assert_eq!(
+            count(
+                &db,
+                "SELECT COUNT(*) FROM sales_reports WHERE company_id = 1"
+            ),
+            4
+        );
 
-        // Write data
-        for report in reports {
-            wtr.write_record(&[
-                report.cust_code,
-                report.cust_name,
-                report.inv_date,
-                report.RE,
-                report.invno,
-                report.part_code.unwrap_or_default(),
-                report.part_name.unwrap_or_default(),
-                report.tariff.unwrap_or_default(),
-                report.qty.to_string(),
-                report.bas_price.to_string(),
-                report.ass_val.to_string(),
-                report.c_gst.to_string(),
-                report.s_gst.to_string(),
-                report.igst.to_string(),
-                report.amot.to_string(),
-                report.inv_val.to_string(),
-                report.igst_yes_no,
-                report.percentage.to_string(),
-            ])?;
+        // Scopes are checked before a token is issued
+        let bad_range = ClearScope::Sales {
+            company_id: None,
+            date_from: Some("2025-06-01".to_string()),
+            date_to: Some("2025-05-01".to_string()),
+        };
+        assert!(db.prepare_clear_data(bad_range).is_err());
+        assert!(db
+            .prepare_clear_data(ClearScope::Company { company_id: 99 })
+            .is_err());
+
+        // Backups from a newer app are refused
+        let newer = dir.path().join("newer.db");
+        let conn = Connection::open(&newer).unwrap();
+        conn.execute_batch("CREATE TABLE companies (id INTEGER PRIMARY KEY)")
+            .unwrap();
+        conn.pragma_update(None, "user_version", migrations::latest_version() + 1)
+            .unwrap();
+        drop(conn);
+        assert!(matches!(
+            db.restore_backup(&newer),
+            Err(AppError::SchemaTooNew { .. })
+        ));
+        assert!(db.restore_backup(&dir.path().join("missing.db")).is_err());
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM sales_reports"), 4);
+
+        // In-memory databases cannot take the snapshot, so nothing is deleted
+        let mut db = test_db();
+        let token = db.prepare_clear_data(ClearScope::Everything).unwrap().token;
+        assert!(db.clear_data(ClearScope::Everything, &token).is_err());
+        assert_eq!(db.get_companies(true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_backups_rotate_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = file_db(dir.path());
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        let count = |db: &DatabaseManager| -> i64 {
+            db.conn
+                .query_row("SELECT COUNT(*) FROM sales_reports", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert!(db.list_backups().unwrap().is_empty());
+
+        // Backups to a chosen path, never over the open database
+        assert!(db.backup_database(Path::new("relative.db")).is_err());
+        assert!(db.backup_database(&dir.path().join(DB_FILE_NAME)).is_err());
+        let manual = dir.path().join("chosen").join("manual.db");
+        let info = db.backup_database(&manual).unwrap();
+        assert_eq!(info.kind, BackupKind::Other);
+        assert!(info.size_bytes > 0);
+
+        // One daily backup a day, keeping the newest
+        let backups = dir.path().join(BACKUP_DIR);
+        std::fs::create_dir_all(&backups).unwrap();
+        for day in 1..=5 {
+            let name = backup::daily_file_name(&format!("2020-01-0{day}"));
+            std::fs::copy(&manual, backups.join(name)).unwrap();
+        }
+        assert!(db.daily_backup(3).unwrap().is_some());
+        assert!(db.daily_backup(3).unwrap().is_none());
+        let mut daily: Vec<String> = db
+            .list_backups()
+            .unwrap()
+            .into_iter()
+            .filter(|b| b.kind == BackupKind::Daily)
+            .map(|b| b.file_name)
+            .collect();
+        daily.sort();
+        assert_eq!(daily.len(), 3);
+        assert_eq!(daily[..2], ["daily-2020-01-04.db", "daily-2020-01-05.db"]);
+
+        // A lower setting prunes at once; none at all is refused
+        assert!(db.daily_backup(1).unwrap().is_none());
+        assert_eq!(
+            db.list_backups()
+                .unwrap()
+                .iter()
+                .filter(|b| b.kind == BackupKind::Daily)
+                .count(),
+            1
+        );
+        assert!(matches!(
+            db.daily_backup(0),
+            Err(AppError::Validation { field, .. }) if field == "daily_backups_kept"
+        ));
+        let kept = |config: serde_json::Value| config_daily_backups_kept(&config);
+        assert_eq!(
+            kept(serde_json::json!({})).unwrap(),
+            backup::DEFAULT_DAILY_BACKUPS_KEPT
+        );
+        assert_eq!(
+            kept(serde_json::json!({ "daily_backups_kept": 14 })).unwrap(),
+            14
+        );
+        for invalid in [
+            serde_json::json!(0),
+            serde_json::json!(-1),
+            serde_json::json!("7"),
+        ] {
+            assert!(kept(serde_json::json!({ "daily_backups_kept": invalid })).is_err());
         }
 
-        wtr.flush()?;
-        let data = wtr.into_inner()?.into_inner();
-        Ok(String::from_utf8(data)?)
+        // Restoring replaces the contents in place and keeps what was there
+        db.conn.execute("DELETE FROM sales_reports", []).unwrap();
+        db.restore_backup(&manual).unwrap();
+        assert_eq!(count(&db), 4);
+        assert_eq!(
+            migrations::current_version(&db.conn).unwrap(),
+            migrations::latest_version()
+        );
+        let before_restore = db
+            .list_backups()
+            .unwrap()
+            .into_iter()
+            .find(|b| b.kind == BackupKind::BeforeRestore)
+            .unwrap();
+        db.restore_backup(Path::new(&before_restore.path)).unwrap();
+        assert_eq!(count(&db), 0);
+
+        let not_a_database = dir.path().join("notes.db");
+        std::fs::write(&not_a_database, "not a database").unwrap();
+        assert!(db.restore_backup(&not_a_database).is_err());
+        assert_eq!(count(&db), 0);
     }
 
-    pub fn import_sales_reports_csv(
-        &self,
-        company_id: i32,
-        csv_data: String,
-    ) -> Result<i32, Box<dyn std::error::Error>> {
-        let mut reader = csv::Reader::from_reader(csv_data.as_bytes());
-        let mut imported_count = 0;
-        let mut skipped_count = 0;
-        let mut duplicate_count = 0;
-
-        // Read headers first
-        let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
-        println!("CSV Headers: {headers:?}");
+    #[test]
+    fn test_import_reports_rejected_rows() {
+        let db = test_db();
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        let csv = "invno,cust_name,inv_date\n\
+                   INV001,Alpha,2025-05-01\n\
+                   INV003,,2025-05-03\n\
+                   INV004,Gamma,not a date\n\
+                   INV005,Delta,\n\
+                   INV006,Epsilon,2025-05-06\n";
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                csv.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
 
-        // Create header mapping for flexible column access
-        let header_map: std::collections::HashMap<String, usize> = headers
+        assert_eq!(
+            (report.imported, report.skipped, report.duplicates),
+            (1, 3, 1)
+        );
+        let rejected: Vec<(usize, &str, &str, bool)> = report
+            .rejected_rows
             .iter()
-            .enumerate()
-            .map(|(i, h)| (h.to_lowercase().replace(" ", "_"), i))
+            .map(|r| {
+                (
+                    r.row_number,
+                    r.field.as_str(),
+                    r.reason.as_str(),
+                    r.duplicate,
+                )
+            })
             .collect();
+        assert_eq!(
+            rejected,
+            vec![
+                (2, "invno", "Invoice INV001 already exists", true),
+                (3, "cust_name", "Missing customer", false),
+                (4, "inv_date", "Invalid date 'not a date'", false),
+                (5, "inv_date", "Missing invoice date", false),
+            ]
+        );
 
-        println!("Header mapping: {header_map:?}");
-
-        for (row_num, result) in reader.records().enumerate() {
-            let record = result?;
-            let row_index = row_num + 2; // +2 because we're 0-indexed and skipped header row
+        let customers = db
+            .import_customers_csv(
+                1,
+                "Customer Name,Tally Name\nAlpha,Alpha\n,Nameless\nAlpha,Again\n".to_string(),
+                ImportMode::BestEffort,
+            )
+            .unwrap();
+        assert_eq!(
+            (customers.imported, customers.skipped, customers.duplicates),
+            (1, 1, 1)
+        );
+        assert_eq!(customers.rejected_rows[0].field, "customer_name");
+        assert!(customers
+            .error_csv()
+            .unwrap()
+            .starts_with("Customer Name,Tally Name,error_row"));
+    }
 
-            println!("Processing row {} with {} columns", row_index, record.len());
-
-            // Helper function to get field value by header name and clean it
-            let get_field = |field_names: &[&str]| -> String {
-                for field_name in field_names {
-                    if let Some(&col_index) =
-                        header_map.get(&field_name.to_lowercase().replace(" ", "_"))
-                    {
-                        if col_index < record.len() {
-                            let value = record.get(col_index).unwrap_or("").to_string();
-                            // Remove surrounding quotes and trim whitespace
-                            return value.trim_matches('"').trim().to_string();
-                        }
-                    }
-                }
-                String::new()
+    #[test]
+    fn test_insert_failures_name_a_field() {
+        let db = test_db();
+        let rows: Vec<FileRow> = ["Gamma", "Delta", "Epsilon", "Zeta"]
+            .iter()
+            .map(|name| {
+                vec![
+                    ("customer_name".to_string(), name.to_string()),
+                    ("tally_name".to_string(), name.to_string()),
+                ]
+            })
+            .collect();
+        let mut attempt = 0;
+        let mut report = ImportReport::default();
+        db.process_customer_rows(1, &rows, ImportMode::BestEffort, &mut report, |_| {
+            attempt += 1;
+            let tally_name = match attempt {
+                1 | 2 => Some("Gamma"),
+                3 => None,
+                _ => return Err(rusqlite::Error::InvalidQuery),
             };
+            db.conn.execute(
+                "INSERT INTO customers (company_id, customer_name, tally_name) VALUES (1, 'Gamma', ?)",
+                [tally_name],
+            )?;
+            Ok(())
+        })
+        .unwrap();
 
-            // Helper function to validate and format dates
-            let parse_date = |date_str: String| -> String {
-                if date_str.is_empty() {
-                    return String::new();
-                }
+        assert_eq!((report.imported, report.skipped), (1, 3));
+        let fields: Vec<&str> = report
+            .rejected_rows
+            .iter()
+            .map(|r| r.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["customer_name", "tally_name", "row"]);
+    }
 
-                // Try different date formats
-                let formats = [
-                    ("YYYY-MM-DD", r"^(\d{4})-(\d{1,2})-(\d{1,2})$"),
-                    ("DD/MM/YYYY", r"^(\d{1,2})/(\d{1,2})/(\d{4})$"),
-                    ("DD-MM-YYYY", r"^(\d{1,2})-(\d{1,2})-(\d{4})$"),
-                ];
-
-                for (format_name, pattern) in &formats {
-                    if let Some(captures) = regex::Regex::new(pattern).unwrap().captures(&date_str)
-                    {
-                        let (year, month, day) = if *format_name == "YYYY-MM-DD" {
-                            (
-                                captures.get(1).unwrap().as_str(),
-                                captures.get(2).unwrap().as_str(),
-                                captures.get(3).unwrap().as_str(),
-                            )
-                        } else {
-                            (
-                                captures.get(3).unwrap().as_str(),
-                                captures.get(2).unwrap().as_str(),
-                                captures.get(1).unwrap().as_str(),
-                            )
-                        };
-
-                        // Validate date components
-                        if let (Ok(y), Ok(m), Ok(d)) = (
-                            year.parse::<i32>(),
-                            month.parse::<i32>(),
-                            day.parse::<i32>(),
-                        ) {
-                            if (1900..=2100).contains(&y)
-                                && (1..=12).contains(&m)
-                                && (1..=31).contains(&d)
-                            {
-                                return format!("{y:04}-{m:02}-{d:02}");
-                            }
-                        }
-                    }
-                }
+    #[test]
+    fn test_preview_predicts_import_without_writing() {
+        let db = test_db();
+        let csv = "INVNO,Customer Name,Invoice Date,Qty,Total Inv Value,Remarks\n\
+                   INV001,Alpha,01/05/2025,2,\"₹1,180.00\",first\n\
+                   INV001,Alpha,01/05/2025,1,(50),second\n\
+                   INV002,,2025-05-02,1,10,\n";
+        let preview = db
+            .preview_sales_reports_csv(1, csv.to_string(), Some(1), None)
+            .unwrap();
+
+        assert_eq!((preview.report.imported, preview.report.skipped), (2, 1));
+        assert_eq!(preview.rows.len(), 1);
+        assert_eq!(preview.rows[0].inv_date, "2025-05-01");
+        assert_eq!(preview.rows[0].inv_val, Money::from_rupees(1180.0));
+        assert_eq!(preview.rows[0].line_no, Some(1));
+        let invno = preview
+            .column_mapping
+            .iter()
+            .find(|m| m.field == "invno")
+            .unwrap();
+        assert_eq!(invno.header.as_deref(), Some("INVNO"));
+        assert_eq!(preview.unmapped_headers, vec!["Remarks".to_string()]);
+        assert_eq!(preview.duplicate_upload_id, None);
+
+        // Nothing was written, and the real import matches the prediction
+        assert!(db.get_sales_reports_by_company(1).unwrap().is_empty());
+        assert!(db.get_uploaded_reports(1).unwrap().is_empty());
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                csv.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 1));
+
+        let again = db
+            .preview_sales_reports_csv(1, csv.to_string(), None, None)
+            .unwrap();
+        assert_eq!(again.duplicate_upload_id, report.uploaded_report_id);
+        assert_eq!(again.report.duplicates, 2);
+
+        let customer_count = db.get_customers_by_company(1).unwrap().len();
+        let customers = db
+            .preview_customers_csv(
+                1,
+                "Name,Code,Group\nAlpha,A1,Retail\nAlpha,A2,\n,B1,\n".to_string(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            (
+                customers.report.imported,
+                customers.report.skipped,
+                customers.report.duplicates
+            ),
+            (1, 1, 1)
+        );
+        assert_eq!(customers.rows[0].category.as_deref(), Some("Retail"));
+        assert_eq!(
+            db.get_customers_by_company(1).unwrap().len(),
+            customer_count
+        );
+        assert!(db.get_categories_by_company(1).unwrap().is_empty());
+    }
 
-                println!("Warning: Invalid date format '{date_str}' in row {row_index}");
-                String::new()
-            };
+    #[test]
+    fn test_import_modes() {
+        let db = test_db();
+        db.conn
+            .execute_batch(
+                "CREATE TRIGGER reject_bad BEFORE INSERT ON sales_reports
+                 WHEN NEW.invno = 'BAD' BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+            )
+            .unwrap();
+
+        // An invalid row stops an all-or-nothing import, leaving no trace of it
+        let invalid = format!("{MULTI_LINE_CSV}INV003,C3,Gamma,not a date,P1,1,10\n");
+        let err = db
+            .import_sales_reports_csv(
+                1,
+                invalid.clone(),
+                None,
+                false,
+                ImportMode::AllOrNothing,
+                None,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Validation { row: Some(6), ref field, .. } if field == "inv_date"
+        ));
+        // So does a row whose insert fails
+        let failing = format!("{MULTI_LINE_CSV}BAD,C3,Gamma,2025-05-03,P1,1,10\n");
+        assert!(db
+            .import_sales_reports_csv(
+                1,
+                failing.clone(),
+                None,
+                false,
+                ImportMode::AllOrNothing,
+                None
+            )
+            .is_err());
+        assert!(db.get_sales_reports_by_company(1).unwrap().is_empty());
+        assert!(db.get_uploaded_reports(1).unwrap().is_empty());
+
+        // Best effort keeps every other row, including the other lines of the file
+        let report = db
+            .import_sales_reports_csv(1, failing, None, false, ImportMode::BestEffort, None)
+            .unwrap();
+        assert_eq!((report.imported, report.skipped), (4, 1));
+        assert_eq!(report.rejected_rows[0].row_number, 6);
+        assert!(report.rejected_rows[0].reason.contains("rejected"));
+        assert_eq!(db.get_sales_reports_by_company(1).unwrap().len(), 4);
+
+        // Duplicates alone do not stop an all-or-nothing import
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                format!("{MULTI_LINE_CSV}INV004,C4,Delta,2025-05-04,P1,1,10\n"),
+                None,
+                false,
+                ImportMode::AllOrNothing,
+                None,
+            )
+            .unwrap();
+        assert_eq!((report.imported, report.duplicates), (1, 4));
+
+        let err = db
+            .import_customers_csv(
+                1,
+                "Name,Code\nOmega,O1\nNoCode,\n".to_string(),
+                ImportMode::AllOrNothing,
+            )
+            .unwrap_err();
+        assert!(matches!(err, AppError::Validation { row: Some(3), .. }));
+        assert!(!db
+            .get_customers_by_company(1)
+            .unwrap()
+            .iter()
+            .any(|c| c.customer_name == "Omega"));
+    }
 
-            // Extract fields using flexible header mapping
-            let cust_code = get_field(&["cust_code", "cust_cde", "customer_code"]);
-            let cust_name = get_field(&["cust_name", "customer_name"]);
-            let inv_date = parse_date(get_field(&["inv_date", "io_date", "invoice_date"]));
-            let re = get_field(&["re", "region"]);
-            let invno = get_field(&[
-                "invno",
-                "invoice_no",
-                "invoice_number",
-                "invoiceno",
-                "invoice",
-                "inv_no",
-                "inv_number",
-            ]);
-            let part_code = get_field(&["part_code", "prod_cde", "product_code"]);
-            let part_name = get_field(&["part_name", "prod_name_ko", "product_name"]);
-            let tariff = get_field(&["tariff", "tariff_code"]);
-
-            // Helper function to parse numbers with currency symbol removal
-            let parse_number = |value: String| -> f64 {
-                if value.is_empty() {
-                    return 0.0;
-                }
+    #[test]
+    fn test_imports_excel_workbooks() {
+        let db = test_db();
+        let workbook = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_reports.xlsx");
+
+        let report = db
+            .import_sales_reports_excel(
+                1,
+                &workbook,
+                None,
+                None,
+                false,
+                ImportMode::AllOrNothing,
+                None,
+            )
+            .unwrap();
+        assert_eq!((report.imported, report.skipped), (154, 0));
+        let upload = &db.get_uploaded_reports(1).unwrap()[0];
+        assert_eq!(upload.filename, "test_reports.xlsx [Sheet1]");
+        let line = &db.get_sales_reports_by_company(1).unwrap()[0];
+        assert_eq!(line.inv_date, "2025-08-24");
+        assert_eq!(line.qty, 4032.0);
+
+        let err = db
+            .import_sales_reports_excel(
+                1,
+                &workbook,
+                Some("Missing".to_string()),
+                None,
+                true,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap_err();
+        assert!(matches!(err, AppError::Validation { ref field, .. } if field == "sheet"));
+
+        // Tab-separated text saved as .xlsx, with a title above the header row and an
+        // Excel date serial
+        let dir = tempfile::tempdir().unwrap();
+        let exported = dir.path().join("register.xlsx");
+        std::fs::write(
+            &exported,
+            "Sales register\t\t\n\t\t\ninvno\tcust_name\tinv_date\nINV9\tAlpha\t45778\n",
+        )
+        .unwrap();
+        assert_eq!(excel::sheet_names(&exported).unwrap(), vec!["register"]);
+        let report = db
+            .import_sales_reports_excel(
+                1,
+                &exported,
+                None,
+                None,
+                false,
+                ImportMode::AllOrNothing,
+                None,
+            )
+            .unwrap();
+        assert_eq!(report.imported, 1);
+        let line = db
+            .get_sales_reports_by_company(1)
+            .unwrap()
+            .into_iter()
+            .find(|l| l.invno == "INV9")
+            .unwrap();
+        assert_eq!(line.inv_date, "2025-05-01");
+        assert_eq!(
+            db.get_report_rows(report.uploaded_report_id.unwrap())
+                .unwrap()[0]
+                .row_number,
+            4
+        );
+    }
 
-                // Remove currency symbols, commas, and spaces
-                let cleaned = value
-                    .replace("₹", "")
-                    .replace("$", "")
-                    .replace("€", "")
-                    .replace("£", "")
-                    .replace("¥", "")
-                    .replace(",", "")
-                    .replace(" ", "");
-
-                // Handle negative numbers in parentheses (accounting format)
-                let final_value = if value.contains('(') && value.contains(')') {
-                    format!("-{}", cleaned.replace("(", "").replace(")", ""))
-                } else {
-                    cleaned
-                };
+    #[test]
+    fn test_column_mapping_profiles() {
+        let db = test_db();
+        let mappings = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(field, header)| (field.to_string(), vec![header.to_string()]))
+                .collect::<std::collections::BTreeMap<_, _>>()
+        };
+
+        let unknown = CreateColumnMappingProfileRequest {
+            company_id: 1,
+            name: "Broken".to_string(),
+            mappings: mappings(&[("vehicle", "Vehicle No")]),
+        };
+        assert!(matches!(
+            db.create_column_mapping_profile(unknown),
+            Err(AppError::Validation { .. })
+        ));
+
+        let profile_id = db
+            .create_column_mapping_profile(CreateColumnMappingProfileRequest {
+                company_id: 1,
+                name: "Tally ERP".to_string(),
+                mappings: mappings(&[("invno", "Bill No"), ("inv_date", "Bill Date")]),
+            })
+            .unwrap();
+
+        // The profile is picked up from the headers without being named
+        let csv = "Bill No,cust_code,Bill Date,qty\nB1,C1,01/05/2025,2\n";
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                csv.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.mapping_profile_id, Some(profile_id));
+        let lines = db.get_sales_reports_by_company(1).unwrap();
+        assert_eq!(
+            (lines[0].invno.as_str(), lines[0].inv_date.as_str()),
+            ("B1", "2025-05-01")
+        );
 
-                final_value.parse().unwrap_or(0.0)
-            };
+        // Reprocessing the stored rows maps them through the same profile
+        let upload_id = report.uploaded_report_id.unwrap();
+        db.rollback_upload(upload_id).unwrap();
+        assert_eq!(db.reprocess_upload(upload_id).unwrap().imported, 1);
+
+        // A profile of another company cannot be used
+        assert!(matches!(
+            db.import_sales_reports_csv(
+                2,
+                csv.to_string(),
+                None,
+                true,
+                ImportMode::BestEffort,
+                Some(profile_id),
+            ),
+            Err(AppError::NotFound { .. })
+        ));
+
+        assert!(db.delete_column_mapping_profile(profile_id).unwrap());
+        assert!(db.get_column_mapping_profiles(1).unwrap().is_empty());
+    }
 
-            // Parse numeric fields with currency symbol handling
-            let qty: f64 = parse_number(get_field(&["qty", "io_qty", "quantity"]));
-            let bas_price: f64 =
-                parse_number(get_field(&["bas_price", "rate_pre_unit", "base_price"]));
-            let ass_val: f64 = parse_number(get_field(&["ass_val", "assessable_value"]));
-            let c_gst: f64 = parse_number(get_field(&["c_gst", "cgst_amt", "c gst"]));
-            let s_gst: f64 = parse_number(get_field(&["s_gst", "sgst_amt", "s gst"]));
-            let igst: f64 = parse_number(get_field(&["igst", "igst_amt"]));
-            let amot: f64 = parse_number(get_field(&[
-                "amot",
-                "amortisation_cost",
-                "total_amorization",
-            ]));
-            let inv_val: f64 = parse_number(get_field(&[
-                "inv_val",
-                "total_inv_value",
-                "invoice_total",
-                "grand_total",
-            ]));
-            let igst_yes_no = get_field(&["igst_yes_no", "igst_flag"]);
-            let percentage: f64 = parse_number(get_field(&[
-                "percentage",
-                "cgst_rate",
-                "sgst_rate",
-                "igst_rate",
-            ]));
-
-            // Validate required fields
-            if invno.is_empty() {
-                println!("Skipping row {row_index}: Missing invoice number");
-                skipped_count += 1;
-                continue;
-            }
+    #[test]
+    fn test_re_codes_follow_company_year_map() {
+        let db = test_db();
+        let csv = MULTI_LINE_CSV.replace("part_code", "RE,part_code");
+        let csv = csv.replace(",P", ",XX,P");
+        db.import_sales_reports_csv(1, csv, None, false, ImportMode::BestEffort, None)
+            .unwrap();
+        let lines = db.get_sales_reports_by_company(1).unwrap();
+        assert!(lines.iter().all(|line| line.RE == "RE"));
+
+        let mut codes = db.get_re_year_codes(1).unwrap();
+        assert_eq!(codes.len(), re_code::DEFAULT_YEAR_CODES.len());
+        codes.retain(|code| code.year != 2025);
+        codes.push(YearCode {
+            year: 2025,
+            code: "Z".to_string(),
+        });
+        db.set_re_year_codes(1, codes).unwrap();
+        assert!(db
+            .set_re_year_codes(
+                1,
+                vec![YearCode {
+                    year: 2025,
+                    code: "ZZ".to_string()
+                }]
+            )
+            .is_err());
+
+        // Existing lines change only when recomputed; company B keeps the defaults
+        assert_eq!(db.recompute_re_codes(1).unwrap(), 4);
+        assert_eq!(db.recompute_re_codes(1).unwrap(), 0);
+        let lines = db.get_sales_reports_by_company(1).unwrap();
+        assert!(lines.iter().all(|line| line.RE == "ZE"));
+        assert_eq!(db.get_re_year_codes(2).unwrap()[4].code, "R");
+
+        // Moving a line to another month moves its code along
+        let update = UpdateSalesReportRequest {
+            inv_date: Some("2024-12-31".to_string()),
+            ..Default::default()
+        };
+        db.update_sales_report(lines[0].id, update).unwrap();
+        let line = db.get_sales_report(lines[0].id).unwrap().unwrap();
+        assert_eq!(line.RE, "QL");
+    }
 
-            if cust_code.is_empty() && cust_name.is_empty() {
-                println!("Skipping row {row_index}: Missing customer information");
-                skipped_count += 1;
-                continue;
-            }
+    #[test]
+    fn test_parse_money_is_exact() {
+        assert_eq!(parse_money("1.005").paise(), 101);
+        assert_eq!(parse_money("₹ 1,234.565").paise(), 123_457);
+        assert_eq!(parse_money("(1,200.00)").paise(), -120_000);
+        assert_eq!(parse_money("1.5E3").paise(), 150_000);
+        assert_eq!(parse_money("n/a"), Money::ZERO);
+        assert_eq!(parse_money(""), Money::ZERO);
+    }
 
-            if inv_date.is_empty() {
-                println!("Skipping row {row_index}: Missing or invalid invoice date");
-                skipped_count += 1;
-                continue;
-            }
+    #[test]
+    fn test_import_derives_igst_flags() {
+        let db = test_db();
+        let csv = "invno,cust_code,inv_date,ass_val,c_gst,s_gst,igst,cgst_rate,sgst_rate,igst_rate,igst_yes_no\n\
+                   I1,C1,2025-05-01,100,9,9,0,9,9,,no\n\
+                   I2,C1,2025-05-01,100,0,0,18,,,18,no\n\
+                   I3,C1,2025-05-01,200,0,0,0,,,12,\n\
+                   I4,C1,2025-05-01,200,6,6,0,,,,\n";
+        db.import_sales_reports_csv(
+            1,
+            csv.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        let flags: Vec<(String, f64)> = db
+            .get_sales_reports_by_company(1)
+            .unwrap()
+            .into_iter()
+            .map(|line| (line.igst_yes_no, line.percentage))
+            .collect();
+        assert!(flags.contains(&("no".to_string(), 18.0)));
+        assert!(flags.contains(&("yes".to_string(), 18.0)));
+        assert!(flags.contains(&("yes".to_string(), 12.0)));
+        assert!(flags.contains(&("no".to_string(), 6.0)));
+
+        // Lines created by hand are derived the same way, whatever they were sent with
+        let id = db
+            .create_sales_report(CreateSalesReportRequest {
+                company_id: 1,
+                cust_code: "C1".to_string(),
+                cust_name: "Alpha".to_string(),
+                inv_date: "2025-05-02".to_string(),
+                RE: String::new(),
+                invno: "I5".to_string(),
+                line_no: None,
+                part_code: None,
+                part_name: None,
+                tariff: None,
+                qty: 1.0,
+                bas_price: 100.0,
+                ass_val: Money::from_rupees(100.0),
+                c_gst: Money::from_rupees(0.0),
+                s_gst: Money::from_rupees(0.0),
+                igst: Money::from_rupees(28.0),
+                amot: Money::from_rupees(0.0),
+                inv_val: Money::from_rupees(128.0),
+                igst_yes_no: "no".to_string(),
+                percentage: 14.0,
+            })
+            .unwrap();
+        let line = db.get_sales_report(id).unwrap().unwrap();
+        assert_eq!((line.igst_yes_no.as_str(), line.percentage), ("yes", 28.0));
+        assert_eq!(line.RE, "RE");
+    }
 
-            // Check if report already exists
-            let existing_count: i32 = self.conn.query_row(
-                "SELECT COUNT(*) FROM sales_reports WHERE company_id = ? AND invno = ?",
-                rusqlite::params![company_id, invno],
-                |row| row.get(0),
-            )?;
+    #[test]
+    fn test_reconciliation_checks() {
+        let db = test_db();
+        let csv = "invno,cust_code,inv_date,qty,bas_price,ass_val,c_gst,s_gst,inv_val\n\
+                   I1,C1,2025-05-01,2,100,200,18,18,295\n\
+                   I1,C1,2025-05-01,1,50,50,4.5,4.5,295\n\
+                   I2,C1,2025-05-01,1,100,100,9,9,150\n\
+                   I2,C1,2025-05-01,1,100,100,9,9,150\n\
+                   I3,C1,2025-05-01,1,100,100,9,5,114\n";
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                csv.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 3));
+        let rejected: Vec<(usize, &str)> = report
+            .rejected_rows
+            .iter()
+            .map(|r| (r.row_number, r.field.as_str()))
+            .collect();
+        assert_eq!(rejected, vec![(6, "s_gst"), (4, "inv_val"), (5, "inv_val")]);
+
+        let mut line = CreateSalesReportRequest {
+            company_id: 1,
+            cust_code: "C1".to_string(),
+            cust_name: "Alpha".to_string(),
+            inv_date: "2025-05-02".to_string(),
+            RE: String::new(),
+            invno: "I4".to_string(),
+            line_no: None,
+            part_code: None,
+            part_name: None,
+            tariff: None,
+            qty: 2.0,
+            bas_price: 100.0,
+            ass_val: Money::from_rupees(150.0),
+            c_gst: Money::from_rupees(0.0),
+            s_gst: Money::from_rupees(0.0),
+            igst: Money::from_rupees(27.0),
+            amot: Money::from_rupees(0.0),
+            inv_val: Money::from_rupees(177.0),
+            igst_yes_no: String::new(),
+            percentage: 0.0,
+        };
+        assert!(matches!(
+            db.create_sales_report(line.clone()),
+            Err(AppError::Validation { ref field, .. }) if field == "ass_val"
+        ));
+        line.ass_val = Money::from_rupees(200.0);
+        let id = db.create_sales_report(line).unwrap();
+
+        // An update that breaks a rule is not applied
+        let update = UpdateSalesReportRequest {
+            c_gst: Some(Money::from_rupees(10.0)),
+            ..Default::default()
+        };
+        assert!(db.update_sales_report(id, update).is_err());
+        assert_eq!(db.get_sales_report(id).unwrap().unwrap().c_gst, Money::ZERO);
+
+        // The scan finds invoices that no longer add up, per invoice and per line
+        db.conn
+            .execute(
+                "UPDATE sales_reports SET inv_val = 30000 WHERE invno = 'I1'",
+                [],
+            )
+            .unwrap();
+        db.conn
+            .execute("UPDATE sales_reports SET c_gst = 200 WHERE id = ?", [id])
+            .unwrap();
+        let issues = db.reconcile_sales_reports(1, None).unwrap();
+        let found: Vec<(&str, Option<i32>, Rule)> = issues
+            .iter()
+            .map(|issue| (issue.invno.as_str(), issue.line_no, issue.rule))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("I1", None, Rule::InvoiceValue),
+                ("I4", None, Rule::InvoiceValue),
+                ("I4", Some(1), Rule::CgstSgstMismatch),
+                ("I4", Some(1), Rule::MixedGst),
+            ]
+        );
 
-            if existing_count > 0 {
-                println!("Skipping row {row_index}: Invoice {invno} already exists");
-                duplicate_count += 1;
-                continue;
-            }
+        // A looser company tolerance accepts the rounding of I1
+        db.update_company(
+            1,
+            UpdateCompanyRequest {
+                reconcile_tolerance: Some(10.0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(db.reconcile_sales_reports(1, None).unwrap().len(), 2);
+        assert_eq!(db.reconcile_sales_reports(1, Some(0.0)).unwrap().len(), 4);
+        for tolerance in [-1.0, f64::NAN] {
+            assert!(matches!(
+                db.reconcile_sales_reports(1, Some(tolerance)),
+                Err(AppError::Validation { .. })
+            ));
+        }
 
-            // Insert new report
-            let report = CreateSalesReportRequest {
-                company_id,
-                cust_code: if cust_code.is_empty() {
-                    cust_name.clone()
-                } else {
-                    cust_code.clone()
-                },
-                cust_name: if cust_name.is_empty() {
-                    cust_code.clone()
-                } else {
-                    cust_name
-                },
-                inv_date,
-                RE: re,
-                invno: invno.clone(),
-                part_code: if part_code.is_empty() {
-                    None
-                } else {
-                    Some(part_code)
-                },
-                part_name: if part_name.is_empty() {
-                    None
-                } else {
-                    Some(part_name)
-                },
-                tariff: if tariff.is_empty() {
-                    None
-                } else {
-                    Some(tariff)
-                },
-                qty,
-                bas_price,
-                ass_val,
-                c_gst,
-                s_gst,
-                igst,
-                amot,
-                inv_val,
-                igst_yes_no: if igst_yes_no.is_empty() {
-                    "no".to_string()
-                } else {
-                    igst_yes_no
-                },
-                percentage,
-            };
+        // Unit rates keep their decimals, so large quantities still add up
+        let csv = "invno,cust_code,inv_date,qty,bas_price,ass_val,c_gst,s_gst,inv_val\n\
+                   I9,C1,2025-05-01,10000,0.125,1250,112.5,112.5,1475\n";
+        let report = db
+            .import_sales_reports_csv(
+                2,
+                csv.to_string(),
+                None,
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        assert_eq!(report.imported, 1);
+        let line = &db.get_sales_reports_by_company(2).unwrap()[0];
+        assert_eq!(line.bas_price, 0.125);
+    }
 
-            self.create_sales_report(report)?;
-            imported_count += 1;
-            println!("Successfully imported invoice {invno} from row {row_index}");
-        }
+    #[test]
+    fn test_customer_gstins_are_validated() {
+        let db = test_db();
+        let customer = |gst_no: &str| CreateCustomerRequest {
+            company_id: 1,
+            customer_name: format!("Customer {gst_no}"),
+            tally_name: "CUSTOMER".to_string(),
+            gst_no: Some(gst_no.to_string()),
+            category_id: None,
+        };
+
+        let err = db.create_customer(customer("27AAPFU0939F1ZW")).unwrap_err();
+        assert!(
+            matches!(err, AppError::Validation { ref field, ref message, .. }
+                if field == "gst_no" && message.contains("check digit"))
+        );
 
-        println!(
-            "Import completed: {imported_count} imported, {skipped_count} skipped, {duplicate_count} duplicates"
+        let id = db.create_customer(customer(" 27aapfu0939f1zv")).unwrap();
+        let stored = db.get_customer(id).unwrap().unwrap();
+        assert_eq!(stored.gst_no.as_deref(), Some("27AAPFU0939F1ZV"));
+        assert_eq!(stored.gst_state_code.as_deref(), Some("27"));
+
+        let update = |gst_no: &str| UpdateCustomerRequest {
+            customer_name: None,
+            tally_name: None,
+            gst_no: Some(gst_no.to_string()),
+            category_id: None,
+        };
+        assert!(db.update_customer(id, update("29AAGCB7383J1Z5")).is_err());
+        db.update_customer(id, update("29AAGCB7383J1Z4")).unwrap();
+        let stored = db.get_customer(id).unwrap().unwrap();
+        assert_eq!(stored.gst_state_code.as_deref(), Some("29"));
+        db.update_customer(id, update("")).unwrap();
+        let stored = db.get_customer(id).unwrap().unwrap();
+        assert_eq!((stored.gst_no, stored.gst_state_code), (None, None));
+
+        let report = db
+            .import_customers_csv(
+                1,
+                "customer_name,tally_name,gst_no\n\
+                 Alpha,ALPHA,33AAACI1195H1ZT\n\
+                 Beta,BETA,33AAACI1195H1Z\n\
+                 Gamma,GAMMA,\n"
+                    .to_string(),
+                ImportMode::BestEffort,
+            )
+            .unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 1));
+        assert_eq!(report.rejected_rows[0].field, "gst_no");
+
+        // Customers saved before validation are flagged in bulk; the seeded sample is valid
+        db.conn
+            .execute(
+                "UPDATE customers SET gst_no = '33AAACI1195H1ZX' WHERE customer_name = 'Alpha'",
+                [],
+            )
+            .unwrap();
+        let invalid = db.find_invalid_gstins(1).unwrap();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].customer_name, "Alpha");
+        assert!(invalid[0].message.contains("expected 'T'"));
+    }
+
+    #[test]
+    fn test_pagination_counts_invoices() {
+        let db = test_db();
+        db.import_sales_reports_csv(
+            1,
+            MULTI_LINE_CSV.to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+
+        let (page, total) = db.get_sales_reports_paginated(1, 2, 1, None).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(page.len(), 3);
+        assert!(page.iter().all(|r| r.invno == "INV001"));
+
+        let csv = db.export_sales_reports_csv(1).unwrap();
+        assert!(csv.lines().next().unwrap().contains("invno,line_no"));
+        assert!(csv.contains("INV001,3,P3"));
+    }
+
+    #[test]
+    fn test_import_records_upload_and_can_roll_back() {
+        let db = test_db();
+        let csv = "invno,cust_code,inv_date,qty,vehicle_no\n\
+                   INV001,C1,01/05/2025,1,KA01\n\
+                   ,C1,01/05/2025,1,KA02\n";
+
+        let report = db
+            .import_sales_reports_csv(
+                1,
+                csv.to_string(),
+                Some("may.csv".to_string()),
+                false,
+                ImportMode::BestEffort,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            (report.imported, report.skipped, report.duplicates),
+            (1, 1, 0)
+        );
+        assert_eq!(report.rejected_rows.len(), 1);
+        assert_eq!(report.rejected_rows[0].row_number, 3);
+        assert_eq!(report.rejected_rows[0].field, "invno");
+        assert_eq!(report.rejected_rows[0].reason, "Missing invoice number");
+
+        // The error CSV keeps the file's columns so it can be fixed and re-imported
+        assert_eq!(
+            report.error_csv().unwrap(),
+            "invno,cust_code,inv_date,qty,vehicle_no,error_row,error_field,error_reason\n\
+             ,C1,01/05/2025,1,KA02,3,invno,Missing invoice number\n"
         );
 
-        Ok(imported_count)
+        let uploads = db.get_uploaded_reports(1).unwrap();
+        assert_eq!(uploads.len(), 1);
+        let upload = &uploads[0];
+        assert_eq!(upload.filename, "may.csv");
+        assert_eq!(upload.status, UPLOAD_STATUS_TRANSFORMED);
+        assert_eq!(upload.row_count, 2);
+        assert_eq!(upload.sales_line_count, 1);
+
+        // Every raw row is kept, including the one that was skipped
+        let rows = db.get_report_rows(upload.id).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].invoice_date, "2025-05-01");
+        assert_eq!(
+            rows[0].extra_json.as_deref(),
+            Some(r#"{"vehicle_no":"KA01"}"#)
+        );
+
+        assert_eq!(db.rollback_upload(upload.id).unwrap(), 1);
+        assert!(db.get_sales_reports_by_company(1).unwrap().is_empty());
+        let upload = db.get_uploaded_report(upload.id).unwrap().unwrap();
+        assert_eq!(upload.status, UPLOAD_STATUS_ROLLED_BACK);
+
+        assert_eq!(db.reprocess_upload(upload.id).unwrap().imported, 1);
+        assert_eq!(db.get_sales_reports_by_company(1).unwrap().len(), 1);
+        assert!(db.rollback_upload(999).is_err());
+    }
+
+    #[test]
+    fn test_moves_legacy_database_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let legacy = dir.path().join("sales_report.db");
+        let target = dir.path().join("data").join("sales_report.db");
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+        // Nothing to move yet
+        assert!(!move_legacy_database(&legacy, &target).unwrap());
+
+        std::fs::write(&legacy, b"db").unwrap();
+        std::fs::write(dir.path().join("sales_report.db-journal"), b"journal").unwrap();
+        assert!(move_legacy_database(&legacy, &target).unwrap());
+        assert!(!legacy.exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"db");
+        assert!(target.with_file_name("sales_report.db-journal").exists());
+
+        // An existing database at the target is never overwritten
+        std::fs::write(&legacy, b"other").unwrap();
+        assert!(!move_legacy_database(&legacy, &target).unwrap());
+        assert_eq!(std::fs::read(&target).unwrap(), b"db");
+    }
+
+    #[test]
+    fn test_migrates_single_line_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE companies (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                key TEXT NOT NULL UNIQUE,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO companies (name, key) VALUES ('Company A', 'company_a');
+            CREATE TABLE sales_reports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                company_id INTEGER NOT NULL,
+                cust_code TEXT NOT NULL,
+                cust_name TEXT NOT NULL,
+                inv_date TEXT NOT NULL,
+                RE TEXT NOT NULL,
+                invno TEXT NOT NULL,
+                part_code TEXT,
+                part_name TEXT,
+                tariff TEXT,
+                qty REAL NOT NULL,
+                bas_price REAL NOT NULL,
+                ass_val REAL NOT NULL,
+                c_gst REAL NOT NULL,
+                s_gst REAL NOT NULL,
+                igst REAL NOT NULL,
+                amot REAL NOT NULL,
+                inv_val REAL NOT NULL,
+                igst_yes_no TEXT NOT NULL,
+                percentage REAL NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+                UNIQUE(company_id, invno)
+            );
+            INSERT INTO sales_reports (company_id, cust_code, cust_name, inv_date, RE, invno,
+                qty, bas_price, ass_val, c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage)
+            VALUES (1, 'C1', 'Alpha', '2025-05-01', 'RE', 'INV001',
+                1, 100, 100, 9, 9, 0, 0, 118, 'no', 18);",
+        )
+        .unwrap();
+
+        let db = DatabaseManager::from_connection(conn).unwrap();
+        let reports = db.get_sales_reports_by_company(1).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].line_no, 1);
+
+        // A second line on the migrated invoice is now accepted
+        db.import_sales_reports_csv(
+            1,
+            "invno,cust_code,inv_date,qty\nINV002,C1,2025-05-02,1\nINV002,C1,2025-05-02,2\n"
+                .to_string(),
+            None,
+            false,
+            ImportMode::BestEffort,
+            None,
+        )
+        .unwrap();
+        assert_eq!(db.get_sales_reports_by_company(1).unwrap().len(), 3);
     }
 }
@@ -0,0 +1,233 @@
+use crate::database::{PlaceOfSupplyIssue, UnmappedCustomer};
+use serde::Serialize;
+
+/// Error returned by every Tauri command. Serialized as JSON tagged by `kind`,
+/// e.g. `{"kind": "Validation", "field": "invno", "row": 4, "message": "..."}`,
+/// so the frontend can react to the kind instead of matching on message text.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum AppError {
+    NotFound {
+        message: String,
+    },
+    /// The record or content already exists; `existing_id` points at it when known
+    Duplicate {
+        message: String,
+        existing_id: Option<i32>,
+    },
+    /// Bad input. `row` is the 1-based file row for import errors (the header is row 1)
+    Validation {
+        message: String,
+        field: String,
+        row: Option<usize>,
+    },
+    Constraint {
+        message: String,
+    },
+    /// The database was written by a newer version of the app; `version` is its schema
+    /// version and `supported` the newest this build understands
+    SchemaTooNew {
+        message: String,
+        version: i32,
+        supported: i32,
+    },
+    /// Sales reference customers that have no customer record yet
+    UnmappedCustomers {
+        message: String,
+        customers: Vec<UnmappedCustomer>,
+    },
+    /// Invoices charge IGST where CGST+SGST is due, or the other way round
+    PlaceOfSupply {
+        message: String,
+        issues: Vec<PlaceOfSupplyIssue>,
+    },
+    Io {
+        message: String,
+    },
+    Csv {
+        message: String,
+    },
+    Database {
+        message: String,
+    },
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn validation(field: &str, row: Option<usize>, message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            field: field.to_string(),
+            row,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound { message }
+            | AppError::Duplicate { message, .. }
+            | AppError::Validation { message, .. }
+            | AppError::Constraint { message }
+            | AppError::SchemaTooNew { message, .. }
+            | AppError::UnmappedCustomers { message, .. }
+            | AppError::PlaceOfSupply { message, .. }
+            | AppError::Io { message }
+            | AppError::Csv { message }
+            | AppError::Database { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        match &error {
+            rusqlite::Error::QueryReturnedNoRows => AppError::not_found("Record not found"),
+            rusqlite::Error::SqliteFailure(failure, _)
+                if failure.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                let message = error.to_string();
+                match failure.extended_code {
+                    rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                    | rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY => AppError::Duplicate {
+                        message,
+                        existing_id: None,
+                    },
+                    _ => AppError::Constraint { message },
+                }
+            }
+            _ => AppError::Database {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<csv::Error> for AppError {
+    fn from(error: csv::Error) -> Self {
+        AppError::Csv {
+            message: format!("CSV error: {error}"),
+        }
+    }
+}
+
+impl<W> From<csv::IntoInnerError<W>> for AppError {
+    fn from(error: csv::IntoInnerError<W>) -> Self {
+        AppError::Csv {
+            message: format!("CSV error: {}", error.error()),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for AppError {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        AppError::Csv {
+            message: format!("UTF-8 error: {error}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::Io {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<calamine::Error> for AppError {
+    fn from(error: calamine::Error) -> Self {
+        AppError::Io {
+            message: format!("Excel error: {error}"),
+        }
+    }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for AppError {
+    fn from(error: rust_xlsxwriter::XlsxError) -> Self {
+        AppError::Io {
+            message: format!("Excel error: {error}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::Database {
+            message: format!("Invalid JSON: {error}"),
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AppError {
+    fn from(error: std::sync::PoisonError<T>) -> Self {
+        AppError::Database {
+            message: format!("Database lock poisoned: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_with_kind_tag() {
+        let error = AppError::validation("invno", Some(4), "Missing invoice number");
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "Validation",
+                "message": "Missing invoice number",
+                "field": "invno",
+                "row": 4
+            })
+        );
+        assert_eq!(error.to_string(), "Missing invoice number");
+    }
+
+    #[test]
+    fn test_maps_sqlite_constraint_errors() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT UNIQUE, qty INTEGER CHECK (qty > 0));
+             INSERT INTO t (name, qty) VALUES ('a', 1);",
+        )
+        .unwrap();
+
+        let duplicate = conn
+            .execute("INSERT INTO t (name, qty) VALUES ('a', 1)", [])
+            .unwrap_err();
+        assert!(matches!(
+            AppError::from(duplicate),
+            AppError::Duplicate { .. }
+        ));
+
+        let check = conn
+            .execute("INSERT INTO t (name, qty) VALUES ('b', 0)", [])
+            .unwrap_err();
+        assert!(matches!(AppError::from(check), AppError::Constraint { .. }));
+
+        let missing = conn
+            .query_row("SELECT name FROM t WHERE id = 99", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .unwrap_err();
+        assert!(matches!(AppError::from(missing), AppError::NotFound { .. }));
+    }
+}
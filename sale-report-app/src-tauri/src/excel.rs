@@ -0,0 +1,149 @@
+use crate::error::{AppError, AppResult};
+use calamine::{open_workbook_auto, Data, Reader};
+use std::path::Path;
+
+/// The cells of one worksheet as text, plus the names of every sheet in the workbook
+pub struct SheetData {
+    pub sheet_names: Vec<String>,
+    pub sheet_name: String,
+    /// Sheet row number (1-based) of the first row in `cells`
+    pub first_row: usize,
+    pub cells: Vec<Vec<String>>,
+}
+
+/// Names of the worksheets of an .xlsx/.xls workbook
+pub fn sheet_names(path: &Path) -> AppResult<Vec<String>> {
+    match open_workbook_auto(path) {
+        Ok(workbook) => Ok(workbook.sheet_names()),
+        Err(e) => read_delimited(path)
+            .map(|data| data.sheet_names)
+            .ok_or_else(|| e.into()),
+    }
+}
+
+/// Read a worksheet of an .xlsx/.xls workbook, the first one when `sheet` is `None`
+pub fn read_sheet(path: &Path, sheet: Option<&str>) -> AppResult<SheetData> {
+    let mut workbook = match open_workbook_auto(path) {
+        Ok(workbook) => workbook,
+        Err(e) => {
+            let data = read_delimited(path).ok_or(e)?;
+            select_sheet(&data.sheet_names, sheet)?;
+            return Ok(data);
+        }
+    };
+    let sheet_names = workbook.sheet_names();
+    let sheet_name = select_sheet(&sheet_names, sheet)?;
+
+    let range = workbook.worksheet_range(&sheet_name)?;
+    let first_row = range.start().map_or(1, |(row, _)| row as usize + 1);
+    let cells = range
+        .rows()
+        .map(|row| row.iter().map(cell_text).collect())
+        .collect();
+
+    Ok(SheetData {
+        sheet_names,
+        sheet_name,
+        first_row,
+        cells,
+    })
+}
+
+fn select_sheet(sheet_names: &[String], sheet: Option<&str>) -> AppResult<String> {
+    match sheet {
+        Some(name) if sheet_names.iter().any(|s| s == name) => Ok(name.to_string()),
+        Some(name) => Err(AppError::validation(
+            "sheet",
+            None,
+            format!("Sheet '{name}' not found"),
+        )),
+        None => sheet_names
+            .first()
+            .cloned()
+            .ok_or_else(|| AppError::validation("sheet", None, "The workbook has no sheets")),
+    }
+}
+
+// Some systems export tab-separated text under an .xls/.xlsx name, which Excel opens
+// after a warning. Such a file is read as one sheet named after the file.
+fn read_delimited(path: &Path) -> Option<SheetData> {
+    let text = String::from_utf8(std::fs::read(path).ok()?).ok()?;
+    let first_line = text.lines().next()?;
+    let delimiter = if first_line.contains('\t') {
+        b'\t'
+    } else if first_line.contains(',') {
+        b','
+    } else {
+        return None;
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let cells = reader
+        .records()
+        .map(|record| record.map(|r| r.iter().map(str::to_string).collect()))
+        .collect::<Result<Vec<Vec<String>>, _>>()
+        .ok()?;
+
+    let sheet_name = path.file_stem()?.to_string_lossy().into_owned();
+    Some(SheetData {
+        sheet_names: vec![sheet_name.clone()],
+        sheet_name,
+        first_row: 1,
+        cells,
+    })
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(s) | Data::DurationIso(s) => s.clone(),
+        Data::DateTimeIso(s) => s.split('T').next().unwrap_or_default().to_string(),
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::Bool(b) => b.to_string(),
+        Data::DateTime(dt) => {
+            serial_to_date(dt.as_f64()).unwrap_or_else(|| dt.as_f64().to_string())
+        }
+    }
+}
+
+/// Convert an Excel date serial (days since 1899-12-30, time of day ignored) to
+/// YYYY-MM-DD. Serials before 1900-03-01 are rejected because of Excel's 1900 leap
+/// year bug.
+pub fn serial_to_date(serial: f64) -> Option<String> {
+    if !(61.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+
+    // Days since 1970-01-01, then the civil-from-days algorithm by Howard Hinnant
+    let days = serial.floor() as i64 - 25_569;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    Some(format!("{year:04}-{month:02}-{day:02}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_to_date() {
+        assert_eq!(serial_to_date(45_778.0).as_deref(), Some("2025-05-01"));
+        assert_eq!(serial_to_date(45_778.75).as_deref(), Some("2025-05-01"));
+        assert_eq!(serial_to_date(61.0).as_deref(), Some("1900-03-01"));
+        assert_eq!(serial_to_date(36_526.0).as_deref(), Some("2000-01-01"));
+        assert_eq!(serial_to_date(60.0), None);
+    }
+}
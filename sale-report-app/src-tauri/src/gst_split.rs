@@ -0,0 +1,358 @@
+use crate::database::SalesReport;
+use crate::money::Money;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// One Tally record: the lines of an invoice that share a GST rate, summed.
+#[derive(Debug, Clone, Serialize)]
+#[allow(non_snake_case)]
+pub struct SplitInvoice {
+    pub invno: String,
+    pub original_invno: String,
+    pub gst_rate: f64,
+    pub cust_code: String,
+    pub cust_name: String,
+    pub inv_date: String,
+    pub RE: String,
+    pub qty: f64,
+    pub ass_val: Money,
+    pub c_gst: Money,
+    pub s_gst: Money,
+    pub igst: Money,
+    pub amot: Money,
+    pub inv_val: Money,
+    pub igst_yes_no: String,
+    pub line_count: usize,
+}
+
+pub fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// GST rate of a line in percent, derived from its tax amounts
+pub fn line_gst_rate(line: &SalesReport) -> f64 {
+    let tax = if line.igst > Money::ZERO {
+        line.igst
+    } else {
+        line.c_gst + line.s_gst
+    };
+    tax_rate(tax, line.ass_val)
+}
+
+/// Tax as a percentage of the assessable value, 0 without an assessable value
+fn tax_rate(tax: Money, ass_val: Money) -> f64 {
+    if ass_val <= Money::ZERO {
+        return 0.0;
+    }
+    round2(tax.paise() as f64 / ass_val.paise() as f64 * 100.0)
+}
+
+/// GST rates in percent as given by a source file, 0 when the column is missing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GstRates {
+    pub cgst: f64,
+    pub sgst: f64,
+    pub igst: f64,
+}
+
+/// `igst_yes_no` and `percentage` of a line. The line is IGST when it has an IGST
+/// amount or rate. Its percentage is the IGST rate, or CGST + SGST rates for an
+/// intra-state line; without rates it is computed from the tax amounts and the
+/// assessable value. The `given` percentage is only kept for lines without an
+/// assessable value, where nothing can be computed.
+pub fn igst_flags(
+    rates: GstRates,
+    given: f64,
+    ass_val: Money,
+    c_gst: Money,
+    s_gst: Money,
+    igst: Money,
+) -> (&'static str, f64) {
+    let is_igst = igst > Money::ZERO || rates.igst > 0.0;
+    let from_rates = if is_igst {
+        rates.igst
+    } else {
+        rates.cgst + rates.sgst
+    };
+    let tax = if is_igst { igst } else { c_gst + s_gst };
+
+    let percentage = if from_rates > 0.0 {
+        from_rates
+    } else if ass_val > Money::ZERO {
+        tax_rate(tax, ass_val)
+    } else {
+        given
+    };
+
+    (if is_igst { "yes" } else { "no" }, percentage)
+}
+
+/// Suffix for the n-th split (1-based): A..Z, then AA, AB, ...
+fn suffix(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap()
+}
+
+/// Split invoices by GST rate. Lines are grouped by (invno, rate); when an invoice
+/// has several rates, the group with the largest taxable value keeps the original
+/// number and the others get suffixes A, B, C... A suffixed number that is already
+/// in `taken` or produced earlier in this run gets a counter appended (251A1, 251A2...).
+pub fn split_invoices(lines: &[SalesReport], taken: &HashSet<String>) -> Vec<SplitInvoice> {
+    // Keep invoices in first-seen order so the output follows the input
+    let mut order: Vec<&str> = Vec::new();
+    let mut groups: BTreeMap<&str, BTreeMap<i64, Vec<&SalesReport>>> = BTreeMap::new();
+    for line in lines {
+        let invno = line.invno.as_str();
+        if !groups.contains_key(invno) {
+            order.push(invno);
+        }
+        let rate_key = (line_gst_rate(line) * 100.0).round() as i64;
+        groups
+            .entry(invno)
+            .or_default()
+            .entry(rate_key)
+            .or_default()
+            .push(line);
+    }
+
+    let mut used: HashSet<String> = order.iter().map(|invno| invno.to_string()).collect();
+    let mut result = Vec::new();
+
+    for invno in order {
+        let mut rate_groups: Vec<(f64, Vec<&SalesReport>)> = groups[invno]
+            .iter()
+            .map(|(rate_key, group)| (*rate_key as f64 / 100.0, group.clone()))
+            .collect();
+        // Largest taxable value first; ties keep ascending rate order
+        rate_groups.sort_by(|a, b| {
+            let a_total: Money = a.1.iter().map(|l| l.ass_val).sum();
+            let b_total: Money = b.1.iter().map(|l| l.ass_val).sum();
+            b_total.cmp(&a_total)
+        });
+
+        for (index, (rate, group)) in rate_groups.into_iter().enumerate() {
+            let split_no = if index == 0 {
+                invno.to_string()
+            } else {
+                let base = format!("{invno}{}", suffix(index));
+                let mut candidate = base.clone();
+                let mut counter = 0;
+                while taken.contains(&candidate) || used.contains(&candidate) {
+                    counter += 1;
+                    candidate = format!("{base}{counter}");
+                }
+                used.insert(candidate.clone());
+                candidate
+            };
+
+            result.push(sum_group(&split_no, invno, rate, &group));
+        }
+    }
+
+    result
+}
+
+/// Total the lines of one rate group. The invoice value is derived from the summed
+/// amounts, since sources that repeat the invoice total on every line would otherwise
+/// count it once per line.
+fn sum_group(split_no: &str, original: &str, rate: f64, group: &[&SalesReport]) -> SplitInvoice {
+    let first = group[0];
+    let sum = |field: fn(&SalesReport) -> Money| group.iter().map(|l| field(l)).sum();
+
+    let ass_val = sum(|l| l.ass_val);
+    let c_gst = sum(|l| l.c_gst);
+    let s_gst = sum(|l| l.s_gst);
+    let igst = sum(|l| l.igst);
+    let amot = sum(|l| l.amot);
+    SplitInvoice {
+        invno: split_no.to_string(),
+        original_invno: original.to_string(),
+        gst_rate: rate,
+        cust_code: first.cust_code.clone(),
+        cust_name: first.cust_name.clone(),
+        inv_date: first.inv_date.clone(),
+        RE: first.RE.clone(),
+        qty: round2(group.iter().map(|l| round2(l.qty)).sum()),
+        ass_val,
+        c_gst,
+        s_gst,
+        igst,
+        amot,
+        inv_val: ass_val + c_gst + s_gst + igst + amot,
+        igst_yes_no: if igst > Money::ZERO { "yes" } else { "no" }.to_string(),
+        line_count: group.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(invno: &str, ass_val: f64, c_gst: f64, igst: f64) -> SalesReport {
+        SalesReport {
+            id: 0,
+            company_id: 1,
+            cust_code: "C1".to_string(),
+            cust_name: "Alpha".to_string(),
+            inv_date: "2025-05-01".to_string(),
+            RE: String::new(),
+            invno: invno.to_string(),
+            line_no: 1,
+            part_code: None,
+            part_name: None,
+            tariff: None,
+            qty: 1.0,
+            bas_price: ass_val,
+            ass_val: Money::from_rupees(ass_val),
+            c_gst: Money::from_rupees(c_gst),
+            s_gst: Money::from_rupees(c_gst),
+            igst: Money::from_rupees(igst),
+            amot: Money::ZERO,
+            inv_val: Money::from_rupees(ass_val + 2.0 * c_gst + igst),
+            igst_yes_no: String::new(),
+            percentage: 0.0,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_igst_flags() {
+        let rates = |cgst, sgst, igst| GstRates { cgst, sgst, igst };
+        let igst_flags = |rates, given, ass_val, c_gst, s_gst, igst| {
+            igst_flags(
+                rates,
+                given,
+                Money::from_rupees(ass_val),
+                Money::from_rupees(c_gst),
+                Money::from_rupees(s_gst),
+                Money::from_rupees(igst),
+            )
+        };
+
+        // Intra-state rates add up instead of the first one winning
+        assert_eq!(
+            igst_flags(rates(9.0, 9.0, 0.0), 0.0, 100.0, 9.0, 9.0, 0.0),
+            ("no", 18.0)
+        );
+        assert_eq!(
+            igst_flags(rates(0.0, 0.0, 18.0), 0.0, 100.0, 0.0, 0.0, 0.0),
+            ("yes", 18.0)
+        );
+        // Without rates: tax / assessable value, whatever percentage was given
+        assert_eq!(
+            igst_flags(GstRates::default(), 9.0, 100.0, 9.0, 9.0, 0.0),
+            ("no", 18.0)
+        );
+        assert_eq!(
+            igst_flags(GstRates::default(), 0.0, 300.0, 0.0, 0.0, 84.0),
+            ("yes", 28.0)
+        );
+        assert_eq!(
+            igst_flags(GstRates::default(), 0.0, 0.0, 0.0, 0.0, 0.0),
+            ("no", 0.0)
+        );
+        // The given percentage only stands in when there is no assessable value
+        assert_eq!(
+            igst_flags(GstRates::default(), 12.0, 0.0, 0.0, 0.0, 0.0),
+            ("no", 12.0)
+        );
+    }
+
+    #[test]
+    fn test_single_rate_invoice_is_summed() {
+        let lines = vec![line("251", 100.0, 9.0, 0.0), line("251", 200.0, 18.0, 0.0)];
+        let split = split_invoices(&lines, &HashSet::new());
+
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].invno, "251");
+        assert_eq!(split[0].gst_rate, 18.0);
+        assert_eq!(split[0].ass_val, Money::from_rupees(300.0));
+        assert_eq!(split[0].c_gst, Money::from_rupees(27.0));
+        assert_eq!(split[0].inv_val, Money::from_rupees(354.0));
+        assert_eq!(split[0].line_count, 2);
+    }
+
+    #[test]
+    fn test_invoice_value_shared_by_lines() {
+        // The source repeats the invoice total of 472 on every line
+        let mut lines = vec![
+            line("251", 100.0, 9.0, 0.0),
+            line("251", 200.0, 18.0, 0.0),
+            line("251", 100.0, 0.0, 0.0),
+        ];
+        lines[2].igst = Money::from_rupees(18.0);
+        for line in &mut lines {
+            line.inv_val = Money::from_rupees(472.0);
+        }
+        let split = split_invoices(&lines, &HashSet::new());
+
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].inv_val, Money::from_rupees(472.0));
+
+        // A split group is worth its own lines, not the whole invoice
+        lines[2].igst = Money::ZERO;
+        lines[2].c_gst = Money::from_rupees(6.0);
+        lines[2].s_gst = Money::from_rupees(6.0);
+        let split = split_invoices(&lines, &HashSet::new());
+        let values: Vec<(&str, Money)> = split
+            .iter()
+            .map(|s| (s.invno.as_str(), s.inv_val))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("251", Money::from_rupees(354.0)),
+                ("251A", Money::from_rupees(112.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_rates_get_suffixes() {
+        let lines = vec![
+            line("251", 100.0, 9.0, 0.0),
+            line("251", 500.0, 0.0, 140.0),
+            line("251", 50.0, 1.25, 0.0),
+        ];
+        let split = split_invoices(&lines, &HashSet::new());
+
+        let numbers: Vec<(&str, f64)> = split
+            .iter()
+            .map(|s| (s.invno.as_str(), s.gst_rate))
+            .collect();
+        assert_eq!(numbers, vec![("251", 28.0), ("251A", 18.0), ("251B", 5.0)]);
+        assert!(split.iter().all(|s| s.original_invno == "251"));
+        assert_eq!(split[0].igst_yes_no, "yes");
+    }
+
+    #[test]
+    fn test_rounding_before_grouping() {
+        // 18.004% and 17.996% both round to 18% once amounts are rounded to paise
+        let lines = vec![line("7", 100.0, 9.002, 0.0), line("7", 100.0, 8.998, 0.0)];
+        let split = split_invoices(&lines, &HashSet::new());
+
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].c_gst, Money::from_rupees(18.0));
+    }
+
+    #[test]
+    fn test_suffix_avoids_existing_numbers() {
+        let lines = vec![
+            line("251", 100.0, 9.0, 0.0),
+            line("251", 50.0, 2.5, 0.0),
+            line("251A", 10.0, 0.9, 0.0),
+        ];
+        let taken: HashSet<String> = ["251A1".to_string()].into();
+        let split = split_invoices(&lines, &taken);
+
+        let numbers: Vec<&str> = split.iter().map(|s| s.invno.as_str()).collect();
+        assert_eq!(numbers, vec!["251", "251A2", "251A"]);
+        assert_eq!(suffix(27), "AA");
+    }
+}
@@ -0,0 +1,172 @@
+use serde::Serialize;
+
+/// GST state codes and names, 01 to 38
+pub const STATES: &[(&str, &str)] = &[
+    ("01", "Jammu and Kashmir"),
+    ("02", "Himachal Pradesh"),
+    ("03", "Punjab"),
+    ("04", "Chandigarh"),
+    ("05", "Uttarakhand"),
+    ("06", "Haryana"),
+    ("07", "Delhi"),
+    ("08", "Rajasthan"),
+    ("09", "Uttar Pradesh"),
+    ("10", "Bihar"),
+    ("11", "Sikkim"),
+    ("12", "Arunachal Pradesh"),
+    ("13", "Nagaland"),
+    ("14", "Manipur"),
+    ("15", "Mizoram"),
+    ("16", "Tripura"),
+    ("17", "Meghalaya"),
+    ("18", "Assam"),
+    ("19", "West Bengal"),
+    ("20", "Jharkhand"),
+    ("21", "Odisha"),
+    ("22", "Chhattisgarh"),
+    ("23", "Madhya Pradesh"),
+    ("24", "Gujarat"),
+    ("25", "Daman and Diu"),
+    ("26", "Dadra and Nagar Haveli and Daman and Diu"),
+    ("27", "Maharashtra"),
+    ("28", "Andhra Pradesh (before 2014)"),
+    ("29", "Karnataka"),
+    ("30", "Goa"),
+    ("31", "Lakshadweep"),
+    ("32", "Kerala"),
+    ("33", "Tamil Nadu"),
+    ("34", "Puducherry"),
+    ("35", "Andaman and Nicobar Islands"),
+    ("36", "Telangana"),
+    ("37", "Andhra Pradesh"),
+    ("38", "Ladakh"),
+];
+
+const CHECK_CHARS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// A GSTIN that passed validation, with the parts the app uses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Gstin {
+    pub gstin: String,
+    pub state_code: String,
+    pub state_name: String,
+    pub pan: String,
+}
+
+/// Name of a GST state code, e.g. "27" is Maharashtra
+pub fn state_name(code: &str) -> Option<&'static str> {
+    STATES
+        .iter()
+        .find(|(state, _)| *state == code)
+        .map(|(_, name)| *name)
+}
+
+/// Validate a GSTIN: two-digit state code, the holder's PAN (five letters, four
+/// digits, a letter), the registration number, 'Z' and the check digit. Input is
+/// trimmed and uppercased first. The error is a message for the user.
+pub fn parse(input: &str) -> Result<Gstin, String> {
+    let gstin = input.trim().to_ascii_uppercase();
+    let bytes = gstin.as_bytes();
+    if bytes.len() != 15 || !bytes.iter().all(u8::is_ascii_alphanumeric) {
+        return Err(format!(
+            "GSTIN '{}' must be 15 letters and digits",
+            input.trim()
+        ));
+    }
+
+    let state_code = &gstin[..2];
+    let Some(state_name) = state_name(state_code) else {
+        return Err(format!(
+            "GSTIN {gstin} has unknown state code '{state_code}', expected 01 to 38"
+        ));
+    };
+
+    let pan = &gstin[2..12];
+    let pan_valid = pan.bytes().enumerate().all(|(i, c)| match i {
+        0..=4 | 9 => c.is_ascii_uppercase(),
+        _ => c.is_ascii_digit(),
+    });
+    if !pan_valid {
+        return Err(format!(
+            "GSTIN {gstin} does not contain a valid PAN ({pan})"
+        ));
+    }
+    if bytes[12] == b'0' {
+        return Err(format!(
+            "GSTIN {gstin} has registration number 0, expected 1-9 or A-Z"
+        ));
+    }
+    if bytes[13] != b'Z' {
+        return Err(format!("GSTIN {gstin} must have 'Z' as the 14th character"));
+    }
+
+    let expected = check_char(&bytes[..14]);
+    if bytes[14] != expected {
+        return Err(format!(
+            "GSTIN {gstin} has check digit '{}', expected '{}'",
+            bytes[14] as char, expected as char
+        ));
+    }
+
+    Ok(Gstin {
+        state_code: state_code.to_string(),
+        state_name: state_name.to_string(),
+        pan: pan.to_string(),
+        gstin,
+    })
+}
+
+/// Mod-36 check character: each character's value is weighted 1 and 2 alternately,
+/// the quotient and remainder of every product by 36 are summed, and the check value
+/// brings the sum up to a multiple of 36
+fn check_char(body: &[u8]) -> u8 {
+    let sum: usize = body
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let value = CHECK_CHARS.iter().position(|x| x == c).unwrap_or(0);
+            let product = value * if i % 2 == 0 { 1 } else { 2 };
+            product / 36 + product % 36
+        })
+        .sum();
+    CHECK_CHARS[(36 - sum % 36) % 36]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_valid_gstins() {
+        let gstin = parse(" 27aapfu0939f1zv ").unwrap();
+        assert_eq!(gstin.gstin, "27AAPFU0939F1ZV");
+        assert_eq!(gstin.state_code, "27");
+        assert_eq!(gstin.state_name, "Maharashtra");
+        assert_eq!(gstin.pan, "AAPFU0939F");
+
+        assert!(parse("29AAGCB7383J1Z4").is_ok());
+        assert!(parse("33AAACI1195H1ZT").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_invalid_gstins() {
+        let message = |input: &str| parse(input).unwrap_err();
+
+        assert!(message("27AAPFU0939F1Z").contains("15 letters and digits"));
+        assert!(message("27AAPFU0939F1Z-").contains("15 letters and digits"));
+        assert!(message("39AAPFU0939F1ZV").contains("state code '39'"));
+        assert!(message("00AAPFU0939F1ZV").contains("state code '00'"));
+        assert!(message("27AAPF10939F1ZV").contains("valid PAN"));
+        assert!(message("27AAPFU0939F0ZV").contains("registration number"));
+        assert!(message("27AAPFU0939F1YV").contains("'Z'"));
+        assert!(message("27AAPFU0939F1ZW").contains("expected 'V'"));
+    }
+
+    #[test]
+    fn test_state_names() {
+        assert_eq!(state_name("01"), Some("Jammu and Kashmir"));
+        assert_eq!(state_name("38"), Some("Ladakh"));
+        assert_eq!(state_name("99"), None);
+        assert_eq!(STATES.len(), 38);
+    }
+}
@@ -1,10 +1,34 @@
+mod backup;
+mod clear;
 mod database;
-
+mod error;
+mod excel;
+mod gst_split;
+mod gstin;
+mod migrations;
+mod money;
+mod place_of_supply;
+mod re_code;
+mod reconcile;
+mod tally;
+mod xlsx;
+
+use backup::BackupInfo;
+use clear::{ClearDataPreview, ClearDataResult, ClearScope};
 use database::{
-    Category, Company, CreateCategoryRequest, CreateCustomerRequest, CreateSalesReportRequest,
-    Customer, DatabaseManager, SalesReport, SalesReportFilters, UpdateCategoryRequest,
-    UpdateCompanyRequest, UpdateCustomerRequest, UpdateSalesReportRequest,
+    AuditLog, AuditLogFilters, Category, ColumnMappingProfile, Company, CompanyDeletionPreview,
+    CreateCategoryRequest, CreateColumnMappingProfileRequest, CreateCompanyRequest,
+    CreateCustomerRequest, CreateSalesReportRequest, Customer, CustomerImportRow, DatabaseManager,
+    ImportMode, ImportPreview, ImportReport, InvalidGstin, InvoiceMapping, PlaceOfSupplyIssue,
+    ReconciliationIssue, ReportRow, SalesReport, SalesReportFilters, TableRowCount, TallyExport,
+    UnmappedCustomer, UpdateCategoryRequest, UpdateColumnMappingProfileRequest,
+    UpdateCompanyRequest, UpdateCustomerRequest, UpdateSalesReportRequest, UploadedReport,
 };
+use error::AppError;
+use gst_split::SplitInvoice;
+use gstin::Gstin;
+use re_code::YearCode;
+use std::path::Path;
 use std::sync::Mutex;
 use tauri::{Manager, State};
 
@@ -28,15 +52,25 @@ pub fn run() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            get_database_path,
             get_companies,
+            create_company,
             update_company,
+            archive_company,
+            unarchive_company,
+            company_deletion_preview,
+            delete_company,
             get_customers_by_company,
             search_customers,
             create_customer,
             update_customer,
             delete_customer,
             export_customers_csv,
+            export_customers_xlsx,
             import_customers_csv,
+            preview_customers_csv,
+            validate_gstin,
+            find_invalid_gstins,
             get_categories_by_company,
             create_category,
             update_category,
@@ -48,17 +82,66 @@ pub fn run() {
             update_sales_report,
             delete_sales_report,
             export_sales_reports_csv,
+            export_sales_reports_xlsx,
             import_sales_reports_csv,
-            clear_all_data,
+            preview_sales_reports_csv,
+            get_excel_sheets,
+            import_sales_reports_excel,
+            get_column_mapping_profiles,
+            create_column_mapping_profile,
+            update_column_mapping_profile,
+            delete_column_mapping_profile,
+            detect_column_mapping_profile,
+            reconcile_sales_reports,
+            check_place_of_supply,
+            get_re_year_codes,
+            set_re_year_codes,
+            recompute_re_codes,
+            get_uploaded_reports,
+            get_report_rows,
+            reprocess_upload,
+            rollback_upload,
+            split_invoices,
+            get_invoice_mapping,
+            get_unmapped_customers,
+            generate_tally_export,
+            get_tally_exports,
+            get_audit_logs,
+            get_import_error_csv,
+            prepare_clear_data,
+            clear_data,
+            backup_database,
+            list_backups,
+            restore_backup,
+            get_daily_backups_kept,
+            set_daily_backups_kept,
+            seed_sample_data,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 #[tauri::command]
-fn get_companies(db: State<DbState>) -> Result<Vec<Company>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager.get_companies().map_err(|e| e.to_string())
+fn get_database_path(db: State<DbState>) -> Result<Option<String>, AppError> {
+    let db_manager = db.lock()?;
+    Ok(db_manager.database_path())
+}
+
+#[tauri::command]
+fn get_companies(
+    include_archived: Option<bool>,
+    db: State<DbState>,
+) -> Result<Vec<Company>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_companies(include_archived.unwrap_or(false))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn create_company(company: CreateCompanyRequest, db: State<DbState>) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.create_company(company)
 }
 
 #[tauri::command]
@@ -66,19 +149,51 @@ fn update_company(
     id: i32,
     company: UpdateCompanyRequest,
     db: State<DbState>,
-) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .update_company(id, company)
-        .map_err(|e| e.to_string())
+) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.update_company(id, company)
+}
+
+#[tauri::command]
+fn archive_company(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.archive_company(id)
 }
 
 #[tauri::command]
-fn get_customers_by_company(company_id: i32, db: State<DbState>) -> Result<Vec<Customer>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+fn unarchive_company(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.unarchive_company(id)
+}
+
+#[tauri::command]
+fn company_deletion_preview(
+    id: i32,
+    db: State<DbState>,
+) -> Result<CompanyDeletionPreview, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.company_deletion_preview(id)
+}
+
+#[tauri::command]
+fn delete_company(
+    id: i32,
+    confirm_key: String,
+    db: State<DbState>,
+) -> Result<Vec<TableRowCount>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.delete_company(id, &confirm_key)
+}
+
+#[tauri::command]
+fn get_customers_by_company(
+    company_id: i32,
+    db: State<DbState>,
+) -> Result<Vec<Customer>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .get_customers_by_company(company_id)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -86,19 +201,17 @@ fn search_customers(
     company_id: i32,
     search_term: String,
     db: State<DbState>,
-) -> Result<Vec<Customer>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<Customer>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .search_customers(company_id, search_term)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-fn create_customer(customer: CreateCustomerRequest, db: State<DbState>) -> Result<i32, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .create_customer(customer)
-        .map_err(|e| e.to_string())
+fn create_customer(customer: CreateCustomerRequest, db: State<DbState>) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.create_customer(customer)
 }
 
 #[tauri::command]
@@ -106,53 +219,81 @@ fn update_customer(
     id: i32,
     customer: UpdateCustomerRequest,
     db: State<DbState>,
-) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .update_customer(id, customer)
-        .map_err(|e| e.to_string())
+) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.update_customer(id, customer)
 }
 
 #[tauri::command]
-fn delete_customer(id: i32, db: State<DbState>) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager.delete_customer(id).map_err(|e| e.to_string())
+fn delete_customer(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.delete_customer(id).map_err(AppError::from)
 }
 
 #[tauri::command]
-fn export_customers_csv(company_id: i32, db: State<DbState>) -> Result<String, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .export_customers_csv(company_id)
-        .map_err(|e| e.to_string())
+fn export_customers_csv(company_id: i32, db: State<DbState>) -> Result<String, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.export_customers_csv(company_id)
+}
+
+#[tauri::command]
+fn export_customers_xlsx(
+    company_id: i32,
+    path: String,
+    db: State<DbState>,
+) -> Result<usize, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.export_customers_xlsx(company_id, Path::new(&path))
 }
 
 #[tauri::command]
 fn import_customers_csv(
     company_id: i32,
     csv_data: String,
+    mode: Option<ImportMode>,
     db: State<DbState>,
-) -> Result<i32, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .import_customers_csv(company_id, csv_data)
-        .map_err(|e| e.to_string())
+) -> Result<ImportReport, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.import_customers_csv(company_id, csv_data, mode.unwrap_or_default())
+}
+
+#[tauri::command]
+fn preview_customers_csv(
+    company_id: i32,
+    csv_data: String,
+    preview_rows: Option<usize>,
+    db: State<DbState>,
+) -> Result<ImportPreview<CustomerImportRow>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.preview_customers_csv(company_id, csv_data, preview_rows)
+}
+
+#[tauri::command]
+fn validate_gstin(gstin: String) -> Result<Gstin, AppError> {
+    gstin::parse(&gstin).map_err(|message| AppError::validation("gst_no", None, message))
+}
+
+#[tauri::command]
+fn find_invalid_gstins(company_id: i32, db: State<DbState>) -> Result<Vec<InvalidGstin>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.find_invalid_gstins(company_id)
 }
 
 #[tauri::command]
-fn get_categories_by_company(company_id: i32, db: State<DbState>) -> Result<Vec<Category>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+fn get_categories_by_company(
+    company_id: i32,
+    db: State<DbState>,
+) -> Result<Vec<Category>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .get_categories_by_company(company_id)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-fn create_category(category: CreateCategoryRequest, db: State<DbState>) -> Result<i32, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .create_category(category)
-        .map_err(|e| e.to_string())
+fn create_category(category: CreateCategoryRequest, db: State<DbState>) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.create_category(category).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -160,17 +301,17 @@ fn update_category(
     id: i32,
     category: UpdateCategoryRequest,
     db: State<DbState>,
-) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .update_category(id, category)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-fn delete_category(id: i32, db: State<DbState>) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager.delete_category(id).map_err(|e| e.to_string())
+fn delete_category(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.delete_category(id).map_err(AppError::from)
 }
 
 // Sales Report commands
@@ -178,11 +319,11 @@ fn delete_category(id: i32, db: State<DbState>) -> Result<bool, String> {
 fn get_sales_reports_by_company(
     company_id: i32,
     db: State<DbState>,
-) -> Result<Vec<SalesReport>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<SalesReport>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .get_sales_reports_by_company(company_id)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -192,11 +333,11 @@ fn get_sales_reports_paginated(
     page_size: i32,
     filters: Option<SalesReportFilters>,
     db: State<DbState>,
-) -> Result<(Vec<SalesReport>, i32), String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<(Vec<SalesReport>, i32), AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .get_sales_reports_paginated(company_id, page, page_size, filters)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -204,22 +345,20 @@ fn search_sales_reports(
     company_id: i32,
     search_term: String,
     db: State<DbState>,
-) -> Result<Vec<SalesReport>, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<SalesReport>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
         .search_sales_reports(company_id, search_term)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 fn create_sales_report(
     report: CreateSalesReportRequest,
     db: State<DbState>,
-) -> Result<i32, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager
-        .create_sales_report(report)
-        .map_err(|e| e.to_string())
+) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.create_sales_report(report)
 }
 
 #[tauri::command]
@@ -227,50 +366,359 @@ fn update_sales_report(
     id: i32,
     report: UpdateSalesReportRequest,
     db: State<DbState>,
-) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.update_sales_report(id, report)
+}
+
+#[tauri::command]
+fn delete_sales_report(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.delete_sales_report(id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn export_sales_reports_csv(company_id: i32, db: State<DbState>) -> Result<String, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.export_sales_reports_csv(company_id)
+}
+
+#[tauri::command]
+fn export_sales_reports_xlsx(
+    company_id: i32,
+    path: String,
+    db: State<DbState>,
+) -> Result<usize, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.export_sales_reports_xlsx(company_id, Path::new(&path))
+}
+
+#[tauri::command]
+fn import_sales_reports_csv(
+    company_id: i32,
+    csv_data: String,
+    filename: Option<String>,
+    force: Option<bool>,
+    mode: Option<ImportMode>,
+    profile_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<ImportReport, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.import_sales_reports_csv(
+        company_id,
+        csv_data,
+        filename,
+        force.unwrap_or(false),
+        mode.unwrap_or_default(),
+        profile_id,
+    )
+}
+
+#[tauri::command]
+fn preview_sales_reports_csv(
+    company_id: i32,
+    csv_data: String,
+    preview_rows: Option<usize>,
+    profile_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<ImportPreview<CreateSalesReportRequest>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.preview_sales_reports_csv(company_id, csv_data, preview_rows, profile_id)
+}
+
+#[tauri::command]
+fn get_excel_sheets(path: String) -> Result<Vec<String>, AppError> {
+    excel::sheet_names(Path::new(&path))
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn import_sales_reports_excel(
+    company_id: i32,
+    path: String,
+    sheet: Option<String>,
+    header_row: Option<usize>,
+    force: Option<bool>,
+    mode: Option<ImportMode>,
+    profile_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<ImportReport, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.import_sales_reports_excel(
+        company_id,
+        Path::new(&path),
+        sheet,
+        header_row,
+        force.unwrap_or(false),
+        mode.unwrap_or_default(),
+        profile_id,
+    )
+}
+
+// Column mapping profile commands
+#[tauri::command]
+fn get_column_mapping_profiles(
+    company_id: i32,
+    db: State<DbState>,
+) -> Result<Vec<ColumnMappingProfile>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
-        .update_sales_report(id, report)
-        .map_err(|e| e.to_string())
+        .get_column_mapping_profiles(company_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn create_column_mapping_profile(
+    profile: CreateColumnMappingProfileRequest,
+    db: State<DbState>,
+) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.create_column_mapping_profile(profile)
 }
 
 #[tauri::command]
-fn delete_sales_report(id: i32, db: State<DbState>) -> Result<bool, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+fn update_column_mapping_profile(
+    id: i32,
+    profile: UpdateColumnMappingProfileRequest,
+    db: State<DbState>,
+) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.update_column_mapping_profile(id, profile)
+}
+
+#[tauri::command]
+fn delete_column_mapping_profile(id: i32, db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
     db_manager
-        .delete_sales_report(id)
-        .map_err(|e| e.to_string())
+        .delete_column_mapping_profile(id)
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-fn export_sales_reports_csv(company_id: i32, db: State<DbState>) -> Result<String, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+fn detect_column_mapping_profile(
+    company_id: i32,
+    headers: Vec<String>,
+    db: State<DbState>,
+) -> Result<Option<ColumnMappingProfile>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
-        .export_sales_reports_csv(company_id)
-        .map_err(|e| e.to_string())
+        .detect_column_mapping_profile(company_id, &headers)
+        .map_err(AppError::from)
 }
 
+// Reconciliation commands
 #[tauri::command]
-fn import_sales_reports_csv(
+fn reconcile_sales_reports(
     company_id: i32,
-    csv_data: String,
+    tolerance: Option<f64>,
     db: State<DbState>,
-) -> Result<i32, String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<ReconciliationIssue>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.reconcile_sales_reports(company_id, tolerance)
+}
+
+#[tauri::command]
+fn check_place_of_supply(
+    company_id: i32,
+    uploaded_report_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<Vec<PlaceOfSupplyIssue>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.check_place_of_supply(company_id, uploaded_report_id)
+}
+
+// RE code commands
+#[tauri::command]
+fn get_re_year_codes(company_id: i32, db: State<DbState>) -> Result<Vec<YearCode>, AppError> {
+    let db_manager = db.lock()?;
     db_manager
-        .import_sales_reports_csv(company_id, csv_data)
-        .map_err(|e| e.to_string())
+        .get_re_year_codes(company_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn set_re_year_codes(
+    company_id: i32,
+    codes: Vec<YearCode>,
+    db: State<DbState>,
+) -> Result<(), AppError> {
+    let db_manager = db.lock()?;
+    db_manager.set_re_year_codes(company_id, codes)
+}
+
+#[tauri::command]
+fn recompute_re_codes(company_id: i32, db: State<DbState>) -> Result<usize, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.recompute_re_codes(company_id)
+}
+
+// Upload commands
+#[tauri::command]
+fn get_uploaded_reports(
+    company_id: i32,
+    db: State<DbState>,
+) -> Result<Vec<UploadedReport>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_uploaded_reports(company_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_report_rows(
+    uploaded_report_id: i32,
+    db: State<DbState>,
+) -> Result<Vec<ReportRow>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_report_rows(uploaded_report_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn reprocess_upload(uploaded_report_id: i32, db: State<DbState>) -> Result<ImportReport, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.reprocess_upload(uploaded_report_id)
+}
+
+#[tauri::command]
+fn rollback_upload(uploaded_report_id: i32, db: State<DbState>) -> Result<i32, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.rollback_upload(uploaded_report_id)
+}
+
+#[tauri::command]
+fn split_invoices(
+    company_id: i32,
+    uploaded_report_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<Vec<SplitInvoice>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .split_invoices(company_id, uploaded_report_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_invoice_mapping(
+    company_id: i32,
+    invoice_no: String,
+    db: State<DbState>,
+) -> Result<Option<InvoiceMapping>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_invoice_mapping(company_id, &invoice_no)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_unmapped_customers(
+    company_id: i32,
+    uploaded_report_id: Option<i32>,
+    db: State<DbState>,
+) -> Result<Vec<UnmappedCustomer>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_unmapped_customers(company_id, uploaded_report_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn generate_tally_export(
+    uploaded_report_id: i32,
+    output_dir: Option<String>,
+    db: State<DbState>,
+) -> Result<TallyExport, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.generate_tally_export(uploaded_report_id, output_dir)
+}
+
+#[tauri::command]
+fn get_tally_exports(company_id: i32, db: State<DbState>) -> Result<Vec<TallyExport>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_tally_exports(company_id)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_import_error_csv(report: ImportReport) -> Result<String, AppError> {
+    report.error_csv()
+}
+
+#[tauri::command]
+fn get_audit_logs(
+    page: i32,
+    page_size: i32,
+    filters: Option<AuditLogFilters>,
+    db: State<DbState>,
+) -> Result<(Vec<AuditLog>, i32), AppError> {
+    let db_manager = db.lock()?;
+    db_manager
+        .get_audit_logs(page, page_size, filters)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn prepare_clear_data(scope: ClearScope, db: State<DbState>) -> Result<ClearDataPreview, AppError> {
+    let mut db_manager = db.lock()?;
+    db_manager.prepare_clear_data(scope)
+}
+
+#[tauri::command]
+fn clear_data(
+    scope: ClearScope,
+    token: String,
+    db: State<DbState>,
+) -> Result<ClearDataResult, AppError> {
+    let mut db_manager = db.lock()?;
+    db_manager.clear_data(scope, &token)
+}
+
+#[tauri::command]
+fn backup_database(path: String, db: State<DbState>) -> Result<BackupInfo, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.backup_database(Path::new(&path))
+}
+
+#[tauri::command]
+fn list_backups(db: State<DbState>) -> Result<Vec<BackupInfo>, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.list_backups()
+}
+
+#[tauri::command]
+fn restore_backup(path: String, db: State<DbState>) -> Result<(), AppError> {
+    let mut db_manager = db.lock()?;
+    db_manager.restore_backup(Path::new(&path))
+}
+
+#[tauri::command]
+fn get_daily_backups_kept(app_handle: tauri::AppHandle) -> Result<usize, AppError> {
+    database::daily_backups_kept(&app_handle)
+}
+
+/// Save the number of daily backups to keep and prune older ones now
+#[tauri::command]
+fn set_daily_backups_kept(
+    keep: usize,
+    app_handle: tauri::AppHandle,
+    db: State<DbState>,
+) -> Result<(), AppError> {
+    database::set_daily_backups_kept(&app_handle, keep)?;
+    let db_manager = db.lock()?;
+    db_manager.daily_backup(keep)?;
+    Ok(())
 }
 
 #[tauri::command]
-fn clear_all_data(db: State<DbState>) -> Result<(), String> {
-    let db_manager = db.lock().map_err(|e| e.to_string())?;
-    db_manager.clear_all_data().map_err(|e| e.to_string())
+fn seed_sample_data(db: State<DbState>) -> Result<bool, AppError> {
+    let db_manager = db.lock()?;
+    db_manager.seed_sample_data().map_err(AppError::from)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
     use std::fs;
     use tempfile::TempDir;
 
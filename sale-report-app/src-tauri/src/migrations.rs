@@ -0,0 +1,792 @@
+//! Versioned schema migrations.
+//!
+//! The schema version is stored in `PRAGMA user_version`. Each migration runs in its
+//! own transaction together with the version bump, so a failed migration leaves the
+//! database at the previous version. Migrations are append-only: never edit one that
+//! has shipped, add a new one instead.
+
+use crate::error::{AppError, AppResult};
+use rusqlite::{Connection, Result, Transaction};
+
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "move customers.category into categories",
+        up: customer_categories,
+    },
+    Migration {
+        version: 3,
+        description: "multi-line invoices in sales_reports",
+        up: sales_report_lines,
+    },
+    Migration {
+        version: 4,
+        description: "link sales_reports and report_rows to uploads",
+        up: upload_tracking,
+    },
+    Migration {
+        version: 5,
+        description: "index uploads by content hash",
+        up: upload_hash_index,
+    },
+    Migration {
+        version: 6,
+        description: "unique split invoice numbers in invoice_mappings",
+        up: invoice_mapping_index,
+    },
+    Migration {
+        version: 7,
+        description: "audit_logs entries without a company",
+        up: audit_log_company_optional,
+    },
+    Migration {
+        version: 8,
+        description: "column mapping profiles for imports",
+        up: column_mapping_profiles,
+    },
+    Migration {
+        version: 9,
+        description: "per-company RE year codes",
+        up: re_year_codes,
+    },
+    Migration {
+        version: 10,
+        description: "reconciliation tolerance per company",
+        up: reconcile_tolerance,
+    },
+    Migration {
+        version: 11,
+        description: "money columns as integer paise",
+        up: money_as_paise,
+    },
+    Migration {
+        version: 12,
+        description: "customer GSTIN state codes",
+        up: customer_gst_state_code,
+    },
+    Migration {
+        version: 13,
+        description: "company GSTIN and state",
+        up: company_gstin,
+    },
+    Migration {
+        version: 14,
+        description: "company address, fiscal year and archiving",
+        up: company_settings,
+    },
+];
+
+/// Schema version this build of the app expects.
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Apply every migration newer than the database's current version, in order.
+///
+/// Refuses to touch a database written by a newer version of the app.
+pub fn run(conn: &Connection) -> AppResult<()> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(AppError::SchemaTooNew {
+            message: format!(
+                "Database schema version {current} is newer than this app supports ({latest}). Please update the app."
+            ),
+            version: current,
+            supported: latest,
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        println!(
+            "Applying migration {}: {}",
+            migration.version, migration.description
+        );
+
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    let count: i32 = tx.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+// Schema as it shipped before versioned migrations. Every statement is
+// `IF NOT EXISTS` because unversioned databases already have some of these tables.
+fn initial_schema(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS companies (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            key TEXT NOT NULL UNIQUE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS categories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+            UNIQUE(company_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS customers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            customer_name TEXT NOT NULL,
+            tally_name TEXT NOT NULL,
+            gst_no TEXT,
+            category_id INTEGER,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+            FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE SET NULL,
+            UNIQUE(company_id, customer_name)
+        );
+
+        CREATE TABLE IF NOT EXISTS sales_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            cust_code TEXT NOT NULL,
+            cust_name TEXT NOT NULL,
+            inv_date TEXT NOT NULL,
+            RE TEXT NOT NULL,
+            invno TEXT NOT NULL,
+            part_code TEXT,
+            part_name TEXT,
+            tariff TEXT,
+            qty REAL NOT NULL,
+            bas_price REAL NOT NULL,
+            ass_val REAL NOT NULL,
+            c_gst REAL NOT NULL,
+            s_gst REAL NOT NULL,
+            igst REAL NOT NULL,
+            amot REAL NOT NULL,
+            inv_val REAL NOT NULL,
+            igst_yes_no TEXT NOT NULL,
+            percentage REAL NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+            UNIQUE(company_id, invno)
+        );
+
+        CREATE TABLE IF NOT EXISTS uploaded_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            filename TEXT NOT NULL,
+            uploaded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            status TEXT NOT NULL DEFAULT 'uploaded',
+            parsed_hash TEXT,
+            FOREIGN KEY (company_id) REFERENCES companies(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS report_rows (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uploaded_report_id INTEGER NOT NULL,
+            invoice_no TEXT NOT NULL,
+            invoice_date DATE NOT NULL,
+            customer_name_raw TEXT NOT NULL,
+            gst_rate DECIMAL(5,2) NOT NULL,
+            cgst_amt DECIMAL(10,2) NOT NULL,
+            sgst_amt DECIMAL(10,2) NOT NULL,
+            igst_amt DECIMAL(10,2) NOT NULL,
+            total_amount DECIMAL(10,2) NOT NULL,
+            extra_json TEXT,
+            FOREIGN KEY (uploaded_report_id) REFERENCES uploaded_reports(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS tally_exports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            uploaded_report_id INTEGER NOT NULL,
+            generated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            file_path TEXT NOT NULL,
+            notes TEXT,
+            FOREIGN KEY (company_id) REFERENCES companies(id),
+            FOREIGN KEY (uploaded_report_id) REFERENCES uploaded_reports(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS audit_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            user_action TEXT NOT NULL,
+            details_json TEXT NOT NULL,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS invoice_mappings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            original_invoice_no TEXT NOT NULL,
+            split_invoice_no TEXT NOT NULL,
+            gst_rate DECIMAL(5,2) NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_customers_company_id ON customers(company_id);
+        CREATE INDEX IF NOT EXISTS idx_customers_name ON customers(customer_name);
+        CREATE INDEX IF NOT EXISTS idx_categories_company_id ON categories(company_id);",
+    )?;
+
+    create_sales_report_indexes(tx)
+}
+
+fn create_sales_report_indexes(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_sales_reports_company_id ON sales_reports(company_id);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_inv_date ON sales_reports(inv_date);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_cust_code ON sales_reports(cust_code);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_invno ON sales_reports(invno);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_company_inv_date ON sales_reports(company_id, inv_date);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_company_cust ON sales_reports(company_id, cust_code);",
+    )
+}
+
+// Early databases stored the category name on each customer
+fn customer_categories(tx: &Transaction) -> Result<()> {
+    if !column_exists(tx, "customers", "category")? {
+        return Ok(());
+    }
+
+    if !column_exists(tx, "customers", "category_id")? {
+        tx.execute("ALTER TABLE customers ADD COLUMN category_id INTEGER", [])?;
+    }
+
+    let category_rows: Vec<(i32, String)> = tx
+        .prepare(
+            "SELECT DISTINCT company_id, category FROM customers WHERE category IS NOT NULL AND category != ''",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_>>()?;
+
+    for (company_id, category_name) in category_rows {
+        tx.execute(
+            "INSERT OR IGNORE INTO categories (company_id, name) VALUES (?, ?)",
+            rusqlite::params![company_id, category_name],
+        )?;
+        tx.execute(
+            "UPDATE customers SET category_id = (
+                 SELECT id FROM categories WHERE company_id = ?1 AND name = ?2
+             )
+             WHERE company_id = ?1 AND category = ?2",
+            rusqlite::params![company_id, category_name],
+        )?;
+    }
+
+    // The old category column is left in place; SQLite could not drop columns
+    // when this schema shipped and nothing reads it any more.
+    Ok(())
+}
+
+// Key sales_reports on (company_id, invno, line_no) so an invoice can hold several
+// part lines. SQLite cannot drop the old UNIQUE(company_id, invno) in place.
+fn sales_report_lines(tx: &Transaction) -> Result<()> {
+    if !column_exists(tx, "sales_reports", "line_no")? {
+        tx.execute_batch(
+            "CREATE TABLE sales_reports_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                company_id INTEGER NOT NULL,
+                cust_code TEXT NOT NULL,
+                cust_name TEXT NOT NULL,
+                inv_date TEXT NOT NULL,
+                RE TEXT NOT NULL,
+                invno TEXT NOT NULL,
+                line_no INTEGER NOT NULL DEFAULT 1,
+                part_code TEXT,
+                part_name TEXT,
+                tariff TEXT,
+                qty REAL NOT NULL,
+                bas_price REAL NOT NULL,
+                ass_val REAL NOT NULL,
+                c_gst REAL NOT NULL,
+                s_gst REAL NOT NULL,
+                igst REAL NOT NULL,
+                amot REAL NOT NULL,
+                inv_val REAL NOT NULL,
+                igst_yes_no TEXT NOT NULL,
+                percentage REAL NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+                UNIQUE(company_id, invno, line_no)
+            );
+
+            INSERT INTO sales_reports_new (
+                id, company_id, cust_code, cust_name, inv_date, RE, invno, line_no,
+                part_code, part_name, tariff, qty, bas_price, ass_val,
+                c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at
+            )
+            SELECT id, company_id, cust_code, cust_name, inv_date, RE, invno, 1,
+                   part_code, part_name, tariff, qty, bas_price, ass_val,
+                   c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at
+            FROM sales_reports;
+
+            DROP TABLE sales_reports;
+            ALTER TABLE sales_reports_new RENAME TO sales_reports;",
+        )?;
+    }
+
+    create_sales_report_indexes(tx)?;
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_sales_reports_company_invno ON sales_reports(company_id, invno)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Sales lines remember the upload they came from so an upload can be rolled back
+// or re-processed from its raw rows.
+fn upload_tracking(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE sales_reports ADD COLUMN uploaded_report_id INTEGER REFERENCES uploaded_reports(id);
+        ALTER TABLE report_rows ADD COLUMN row_number INTEGER;
+        ALTER TABLE report_rows ADD COLUMN raw_json TEXT;
+
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_upload ON sales_reports(uploaded_report_id);
+        CREATE INDEX IF NOT EXISTS idx_report_rows_upload ON report_rows(uploaded_report_id, row_number);
+        CREATE INDEX IF NOT EXISTS idx_uploaded_reports_company ON uploaded_reports(company_id);",
+    )
+}
+
+// Re-imports are detected by looking up an upload with the same content hash.
+fn upload_hash_index(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_uploaded_reports_hash ON uploaded_reports(company_id, parsed_hash);",
+    )
+}
+
+// A split invoice number maps back to exactly one original invoice.
+fn invoice_mapping_index(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_invoice_mappings_split ON invoice_mappings(company_id, split_invoice_no);
+        CREATE INDEX IF NOT EXISTS idx_invoice_mappings_original ON invoice_mappings(company_id, original_invoice_no);",
+    )
+}
+
+// Actions such as clearing the database are not tied to one company, and an
+// audit entry must outlive the company it refers to.
+fn audit_log_company_optional(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE audit_logs_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER,
+            user_action TEXT NOT NULL,
+            details_json TEXT NOT NULL,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE SET NULL
+        );
+
+        INSERT INTO audit_logs_new (id, company_id, user_action, details_json, timestamp)
+        SELECT id, company_id, user_action, details_json, timestamp FROM audit_logs;
+
+        DROP TABLE audit_logs;
+        ALTER TABLE audit_logs_new RENAME TO audit_logs;
+
+        CREATE INDEX IF NOT EXISTS idx_audit_logs_company ON audit_logs(company_id, timestamp);
+        CREATE INDEX IF NOT EXISTS idx_audit_logs_action ON audit_logs(user_action);",
+    )
+}
+
+// Per-company header names for sales report fields, so a renamed column in a source
+// system does not need a code change. An upload remembers the profile it was read with.
+fn column_mapping_profiles(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS column_mapping_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            mappings_json TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+            UNIQUE(company_id, name)
+        );
+
+        ALTER TABLE uploaded_reports ADD COLUMN mapping_profile_id INTEGER
+            REFERENCES column_mapping_profiles(id) ON DELETE SET NULL;",
+    )
+}
+
+// The year letter of RE codes used to be hardcoded in the frontend. Existing companies
+// get the codes it knew, continued one letter per year.
+fn re_year_codes(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS re_year_codes (
+            company_id INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            code TEXT NOT NULL,
+            PRIMARY KEY (company_id, year),
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
+        );
+
+        INSERT OR IGNORE INTO re_year_codes (company_id, year, code)
+        SELECT c.id, d.year, d.code
+        FROM companies c
+        CROSS JOIN (
+            SELECT 2021 AS year, 'N' AS code
+            UNION ALL SELECT 2022, 'O'
+            UNION ALL SELECT 2023, 'P'
+            UNION ALL SELECT 2024, 'Q'
+            UNION ALL SELECT 2025, 'R'
+            UNION ALL SELECT 2026, 'S'
+            UNION ALL SELECT 2027, 'T'
+            UNION ALL SELECT 2028, 'U'
+            UNION ALL SELECT 2029, 'V'
+            UNION ALL SELECT 2030, 'W'
+        ) d;",
+    )
+}
+
+// Rounding differences in rupees accepted when checking that sales lines add up
+fn reconcile_tolerance(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE companies ADD COLUMN reconcile_tolerance REAL NOT NULL DEFAULT 1.0;",
+    )
+}
+
+// REAL amounts drift by a paisa when summed, so GST totals stopped matching Tally.
+// Amounts become INTEGER paise, rounded half away from zero. bas_price is a unit rate
+// with more than two decimals (0.125) and stays REAL. SQLite cannot change a column
+// type in place, so both tables are rebuilt.
+fn money_as_paise(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE sales_reports_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            company_id INTEGER NOT NULL,
+            cust_code TEXT NOT NULL,
+            cust_name TEXT NOT NULL,
+            inv_date TEXT NOT NULL,
+            RE TEXT NOT NULL,
+            invno TEXT NOT NULL,
+            line_no INTEGER NOT NULL DEFAULT 1,
+            part_code TEXT,
+            part_name TEXT,
+            tariff TEXT,
+            qty REAL NOT NULL,
+            bas_price REAL NOT NULL,
+            ass_val INTEGER NOT NULL,
+            c_gst INTEGER NOT NULL,
+            s_gst INTEGER NOT NULL,
+            igst INTEGER NOT NULL,
+            amot INTEGER NOT NULL,
+            inv_val INTEGER NOT NULL,
+            igst_yes_no TEXT NOT NULL,
+            percentage REAL NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            uploaded_report_id INTEGER REFERENCES uploaded_reports(id),
+            FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE,
+            UNIQUE(company_id, invno, line_no)
+        );
+
+        INSERT INTO sales_reports_new (
+            id, company_id, cust_code, cust_name, inv_date, RE, invno, line_no,
+            part_code, part_name, tariff, qty, bas_price, ass_val,
+            c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage, created_at,
+            uploaded_report_id
+        )
+        SELECT id, company_id, cust_code, cust_name, inv_date, RE, invno, line_no,
+               part_code, part_name, tariff, qty, bas_price,
+               CAST(ROUND(ass_val * 100) AS INTEGER),
+               CAST(ROUND(c_gst * 100) AS INTEGER),
+               CAST(ROUND(s_gst * 100) AS INTEGER),
+               CAST(ROUND(igst * 100) AS INTEGER),
+               CAST(ROUND(amot * 100) AS INTEGER),
+               CAST(ROUND(inv_val * 100) AS INTEGER),
+               igst_yes_no, percentage, created_at, uploaded_report_id
+        FROM sales_reports;
+
+        DROP TABLE sales_reports;
+        ALTER TABLE sales_reports_new RENAME TO sales_reports;
+
+        CREATE TABLE report_rows_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uploaded_report_id INTEGER NOT NULL,
+            invoice_no TEXT NOT NULL,
+            invoice_date DATE NOT NULL,
+            customer_name_raw TEXT NOT NULL,
+            gst_rate REAL NOT NULL,
+            cgst_amt INTEGER NOT NULL,
+            sgst_amt INTEGER NOT NULL,
+            igst_amt INTEGER NOT NULL,
+            total_amount INTEGER NOT NULL,
+            extra_json TEXT,
+            row_number INTEGER,
+            raw_json TEXT,
+            FOREIGN KEY (uploaded_report_id) REFERENCES uploaded_reports(id)
+        );
+
+        INSERT INTO report_rows_new (
+            id, uploaded_report_id, invoice_no, invoice_date, customer_name_raw, gst_rate,
+            cgst_amt, sgst_amt, igst_amt, total_amount, extra_json, row_number, raw_json
+        )
+        SELECT id, uploaded_report_id, invoice_no, invoice_date, customer_name_raw, gst_rate,
+               CAST(ROUND(cgst_amt * 100) AS INTEGER),
+               CAST(ROUND(sgst_amt * 100) AS INTEGER),
+               CAST(ROUND(igst_amt * 100) AS INTEGER),
+               CAST(ROUND(total_amount * 100) AS INTEGER),
+               extra_json, row_number, raw_json
+        FROM report_rows;
+
+        DROP TABLE report_rows;
+        ALTER TABLE report_rows_new RENAME TO report_rows;
+
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_company_invno ON sales_reports(company_id, invno);
+        CREATE INDEX IF NOT EXISTS idx_sales_reports_upload ON sales_reports(uploaded_report_id);
+        CREATE INDEX IF NOT EXISTS idx_report_rows_upload ON report_rows(uploaded_report_id, row_number);",
+    )?;
+
+    create_sales_report_indexes(tx)
+}
+
+// Place-of-supply checks need the customer's state, which is the first two digits of
+// a valid GSTIN. Existing valid GSTINs are normalized and get their state code;
+// invalid ones are left for the user to fix. The GSTIN rules are copied here as they
+// stood, so later changes to GSTIN validation do not change this migration.
+fn customer_gst_state_code(tx: &Transaction) -> Result<()> {
+    fn normalized_gstin(gst_no: &str) -> Option<String> {
+        const CHARS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let gstin = gst_no.trim().to_ascii_uppercase();
+        let bytes = gstin.as_bytes();
+        if bytes.len() != 15 || !bytes.iter().all(u8::is_ascii_alphanumeric) {
+            return None;
+        }
+        let state: u32 = gstin[..2].parse().ok()?;
+        let pan_valid = bytes[2..12].iter().enumerate().all(|(i, c)| match i {
+            0..=4 | 9 => c.is_ascii_uppercase(),
+            _ => c.is_ascii_digit(),
+        });
+        if !(1..=38).contains(&state) || !pan_valid || bytes[12] == b'0' || bytes[13] != b'Z' {
+            return None;
+        }
+        let sum: usize = bytes[..14]
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let product = CHARS.iter().position(|x| x == c).unwrap_or(0) * (1 + i % 2);
+                product / 36 + product % 36
+            })
+            .sum();
+        (bytes[14] == CHARS[(36 - sum % 36) % 36]).then_some(gstin)
+    }
+
+    tx.execute("ALTER TABLE customers ADD COLUMN gst_state_code TEXT", [])?;
+
+    let customers: Vec<(i32, String)> = {
+        let mut stmt = tx.prepare("SELECT id, gst_no FROM customers WHERE gst_no IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    for (id, gst_no) in customers {
+        if let Some(gstin) = normalized_gstin(&gst_no) {
+            tx.execute(
+                "UPDATE customers SET gst_no = ?, gst_state_code = ? WHERE id = ?",
+                rusqlite::params![gstin, &gstin[..2], id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Whether an invoice is intra-state (CGST+SGST) or inter-state (IGST) depends on the
+// company's state as well as the customer's
+fn company_gstin(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE companies ADD COLUMN gstin TEXT;
+        ALTER TABLE companies ADD COLUMN state_code TEXT;",
+    )
+}
+
+// Companies are created and archived from the app now. Fiscal years start in April
+// unless the company says otherwise.
+fn company_settings(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE companies ADD COLUMN address TEXT;
+        ALTER TABLE companies ADD COLUMN fiscal_year_start INTEGER NOT NULL DEFAULT 4;
+        ALTER TABLE companies ADD COLUMN archived_at DATETIME;",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_database_reaches_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        run(&conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        // Running again is a no-op
+        run(&conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let err = run(&conn).unwrap_err();
+        assert!(err.to_string().contains("newer than this app supports"));
+        assert!(matches!(
+            err,
+            AppError::SchemaTooNew { version, .. } if version == latest_version() + 1
+        ));
+    }
+
+    #[test]
+    fn test_migrates_legacy_customer_category() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE companies (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                key TEXT NOT NULL UNIQUE,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO companies (name, key) VALUES ('Company A', 'company_a');
+            CREATE TABLE customers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                company_id INTEGER NOT NULL,
+                customer_name TEXT NOT NULL,
+                tally_name TEXT NOT NULL,
+                gst_no TEXT,
+                category TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(company_id, customer_name)
+            );
+            INSERT INTO customers (company_id, customer_name, tally_name, gst_no, category)
+            VALUES (1, 'Alpha', 'ALPHA', '27aapfu0939f1zv', 'General'),
+                   (1, 'Beta', 'BETA', '27AAPFU0939F1ZX', 'General'),
+                   (1, 'Gamma', 'GAMMA', '99AAPFU0939F1ZV', 'General');",
+        )
+        .unwrap();
+
+        run(&conn).unwrap();
+
+        let linked: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM customers c JOIN categories cat ON c.category_id = cat.id
+                 WHERE cat.name = 'General'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(linked, 3);
+
+        // Valid GSTINs are normalized and get a state code, invalid ones are kept
+        let gstins: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT gst_no, gst_state_code FROM customers ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            gstins,
+            vec![
+                ("27AAPFU0939F1ZV".to_string(), Some("27".to_string())),
+                ("27AAPFU0939F1ZX".to_string(), None),
+                ("99AAPFU0939F1ZV".to_string(), None),
+            ]
+        );
+
+        // Existing companies get the default RE year codes
+        let year_code: String = conn
+            .query_row(
+                "SELECT code FROM re_year_codes WHERE company_id = 1 AND year = 2025",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(year_code, "R");
+    }
+
+    #[test]
+    fn test_money_columns_become_paise() {
+        let conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 11) {
+            let tx = conn.unchecked_transaction().unwrap();
+            (migration.up)(&tx).unwrap();
+            tx.pragma_update(None, "user_version", migration.version)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO companies (name, key) VALUES ('Company A', 'company_a');
+            INSERT INTO uploaded_reports (company_id, filename) VALUES (1, 'may.csv');
+            INSERT INTO sales_reports (
+                company_id, cust_code, cust_name, inv_date, RE, invno, qty, bas_price,
+                ass_val, c_gst, s_gst, igst, amot, inv_val, igst_yes_no, percentage,
+                uploaded_report_id
+            )
+            VALUES (1, 'C1', 'Alpha', '2025-05-01', 'RE', 'I1', 3, 33.333,
+                    99.999, 9.125, 9.125, 0, -0.125, 117.99, 'no', 18, 1);
+            INSERT INTO report_rows (
+                uploaded_report_id, invoice_no, invoice_date, customer_name_raw, gst_rate,
+                cgst_amt, sgst_amt, igst_amt, total_amount
+            )
+            VALUES (1, 'I1', '2025-05-01', 'Alpha', 18, 9.125, 9.125, 0, 117.99);",
+        )
+        .unwrap();
+
+        run(&conn).unwrap();
+
+        let amounts: (f64, i64, i64, i64, i64, f64) = conn
+            .query_row(
+                "SELECT bas_price, ass_val, c_gst, amot, inv_val, qty FROM sales_reports",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(amounts, (33.333, 10000, 913, -13, 11799, 3.0));
+
+        let total: (i64, i64) = conn
+            .query_row(
+                "SELECT cgst_amt, total_amount FROM report_rows",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(total, (913, 11799));
+    }
+}
@@ -0,0 +1,185 @@
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub};
+
+/// An amount in rupees, held as a whole number of paise so sums are exact. Stored as
+/// an INTEGER column and serialized to the frontend as a number of rupees with two
+/// decimals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+    pub const RUPEE: Money = Money(100);
+
+    /// Convert a rupee amount through its shortest decimal form, so 1.005 is rounded
+    /// as written (to 1.01) rather than as its binary value. Non-finite amounts are 0.
+    pub fn from_rupees(rupees: f64) -> Self {
+        Money::parse(&rupees.to_string()).unwrap_or(Money::ZERO)
+    }
+
+    /// Parse a decimal rupee amount such as "-1234.565" exactly, rounding to the
+    /// nearest paisa with halves away from zero. `None` unless the text is an
+    /// optional sign, digits and an optional fraction.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut paise: i64 = 0;
+        for digit in whole.bytes() {
+            paise = paise
+                .checked_mul(10)?
+                .checked_add(i64::from(digit - b'0'))?;
+        }
+        let mut fraction = fraction.bytes().map(|digit| i64::from(digit - b'0'));
+        let tenths = fraction.next().unwrap_or(0);
+        let hundredths = fraction.next().unwrap_or(0);
+        let round_up = fraction.next().is_some_and(|digit| digit >= 5);
+        paise = paise
+            .checked_mul(100)?
+            .checked_add(tenths * 10 + hundredths + i64::from(round_up))?;
+
+        Some(Money(if negative { -paise } else { paise }))
+    }
+
+    pub fn paise(self) -> i64 {
+        self.0
+    }
+
+    pub fn rupees(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let paise = self.0.unsigned_abs();
+        write!(f, "{sign}{}.{:02}", paise / 100, paise % 100)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        Money(iter.map(|m| m.0).sum())
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.rupees())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from_rupees)
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0))
+    }
+}
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        i64::column_result(value).map(Money)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_to_paise() {
+        assert_eq!(Money::from_rupees(0.125).paise(), 13);
+        assert_eq!(Money::from_rupees(-0.125).paise(), -13);
+        assert_eq!(Money::from_rupees(1234.56).paise(), 123_456);
+        assert_eq!(Money::from_rupees(0.1) + Money::from_rupees(0.2), Money(30));
+        assert_eq!(Money(123_456).to_string(), "1234.56");
+        assert_eq!(Money(-5).to_string(), "-0.05");
+    }
+
+    #[test]
+    fn test_parses_exactly() {
+        let paise = |text: &str| Money::parse(text).map(Money::paise);
+        assert_eq!(paise("1.005"), Some(101));
+        assert_eq!(paise("1.004999"), Some(100));
+        assert_eq!(paise("-2.675"), Some(-268));
+        assert_eq!(paise("+12"), Some(1200));
+        assert_eq!(paise(".5"), Some(50));
+        assert_eq!(paise("7."), Some(700));
+        assert_eq!(paise("90071992547409.93"), Some(9_007_199_254_740_993));
+        assert_eq!(paise(""), None);
+        assert_eq!(paise("."), None);
+        assert_eq!(paise("1.2.3"), None);
+        assert_eq!(paise("1e3"), None);
+        assert_eq!(paise("99999999999999999999"), None);
+        assert_eq!(Money::from_rupees(1.005).paise(), 101);
+        assert_eq!(Money::from_rupees(f64::NAN), Money::ZERO);
+    }
+
+    #[test]
+    fn test_serializes_as_rupees() {
+        let amount: Money = serde_json::from_str("99.999").unwrap();
+        assert_eq!(amount.paise(), 10000);
+        let amount: Money = serde_json::from_str("1.005").unwrap();
+        assert_eq!(amount.paise(), 101);
+        assert_eq!(serde_json::to_string(&Money(1050)).unwrap(), "10.5");
+    }
+}
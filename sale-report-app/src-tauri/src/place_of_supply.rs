@@ -0,0 +1,64 @@
+use crate::money::Money;
+use serde::Serialize;
+
+/// Which GST an invoice should charge
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxType {
+    /// Supply within the company's state
+    CgstSgst,
+    /// Supply to another state
+    Igst,
+}
+
+/// Tax type for a supply from the company's state to the customer's state
+pub fn expected_tax_type(company_state: &str, customer_state: &str) -> TaxType {
+    if company_state == customer_state {
+        TaxType::CgstSgst
+    } else {
+        TaxType::Igst
+    }
+}
+
+/// Why an invoice's taxes contradict its place of supply, or `None` when they fit.
+/// Invoices without GST are not checked.
+pub fn check(expected: TaxType, cgst_sgst: Money, igst: Money) -> Option<String> {
+    match expected {
+        TaxType::CgstSgst if !igst.is_zero() => {
+            Some(format!("Charges IGST {igst} on a supply within the state"))
+        }
+        TaxType::Igst if !cgst_sgst.is_zero() => Some(format!(
+            "Charges CGST/SGST {cgst_sgst} on a supply to another state"
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_tax_type() {
+        assert_eq!(expected_tax_type("27", "27"), TaxType::CgstSgst);
+        assert_eq!(expected_tax_type("27", "29"), TaxType::Igst);
+    }
+
+    #[test]
+    fn test_check() {
+        let rs = Money::from_rupees;
+        assert_eq!(check(TaxType::CgstSgst, rs(18.0), Money::ZERO), None);
+        assert_eq!(check(TaxType::Igst, Money::ZERO, rs(18.0)), None);
+        assert_eq!(check(TaxType::Igst, Money::ZERO, Money::ZERO), None);
+
+        assert_eq!(
+            check(TaxType::CgstSgst, Money::ZERO, rs(18.0)).as_deref(),
+            Some("Charges IGST 18.00 on a supply within the state")
+        );
+        assert_eq!(
+            check(TaxType::Igst, rs(18.0), Money::ZERO).as_deref(),
+            Some("Charges CGST/SGST 18.00 on a supply to another state")
+        );
+        assert!(check(TaxType::Igst, rs(9.0), rs(9.0)).is_some());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Year letters for a new company. Each year is one letter after the previous one,
+/// continuing the codes used before 2025.
+pub const DEFAULT_YEAR_CODES: &[(i32, char)] = &[
+    (2021, 'N'),
+    (2022, 'O'),
+    (2023, 'P'),
+    (2024, 'Q'),
+    (2025, 'R'),
+    (2026, 'S'),
+    (2027, 'T'),
+    (2028, 'U'),
+    (2029, 'V'),
+    (2030, 'W'),
+];
+
+/// Month letters, January = A to December = L
+const MONTH_CODES: [char; 12] = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L'];
+
+/// The letter a company uses for an invoice year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YearCode {
+    pub year: i32,
+    pub code: String,
+}
+
+/// RE code of an invoice date (YYYY-MM-DD): the year letter followed by the month
+/// letter, e.g. 2025-05-14 is "RE". `None` when the date is unreadable or the year has
+/// no letter.
+pub fn re_code(inv_date: &str, year_codes: &BTreeMap<i32, String>) -> Option<String> {
+    let mut parts = inv_date.splitn(3, '-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: usize = parts.next()?.parse().ok()?;
+    let month_code = MONTH_CODES.get(month.checked_sub(1)?)?;
+
+    let year_code = year_codes.get(&year)?;
+    Some(format!("{year_code}{month_code}"))
+}
+
+/// Check a year map before it is stored: one uppercase letter per year, no year twice
+pub fn validate_year_codes(codes: &[YearCode]) -> AppResult<()> {
+    let mut seen = std::collections::HashSet::new();
+    for code in codes {
+        if !(1900..=9999).contains(&code.year) {
+            return Err(AppError::validation(
+                "year",
+                None,
+                format!("Invalid year {}", code.year),
+            ));
+        }
+        let mut chars = code.code.chars();
+        let valid =
+            matches!((chars.next(), chars.next()), (Some(c), None) if c.is_ascii_uppercase());
+        if !valid {
+            return Err(AppError::validation(
+                "code",
+                None,
+                format!(
+                    "Code for {} must be a single letter A-Z, got '{}'",
+                    code.year, code.code
+                ),
+            ));
+        }
+        if !seen.insert(code.year) {
+            return Err(AppError::validation(
+                "year",
+                None,
+                format!("Year {} is listed twice", code.year),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> BTreeMap<i32, String> {
+        DEFAULT_YEAR_CODES
+            .iter()
+            .map(|(year, code)| (*year, code.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_re_code() {
+        let codes = defaults();
+        assert_eq!(re_code("2025-05-14", &codes).as_deref(), Some("RE"));
+        assert_eq!(re_code("2021-01-01", &codes).as_deref(), Some("NA"));
+        assert_eq!(re_code("2024-12-31", &codes).as_deref(), Some("QL"));
+        assert_eq!(re_code("2019-05-14", &codes), None);
+        assert_eq!(re_code("2025-13-01", &codes), None);
+        assert_eq!(re_code("14/05/2025", &codes), None);
+        assert_eq!(re_code("", &codes), None);
+    }
+
+    #[test]
+    fn test_validate_year_codes() {
+        let code = |year, code: &str| YearCode {
+            year,
+            code: code.to_string(),
+        };
+        assert!(validate_year_codes(&[code(2025, "R"), code(2026, "S")]).is_ok());
+        assert!(validate_year_codes(&[code(2025, "r")]).is_err());
+        assert!(validate_year_codes(&[code(2025, "RS")]).is_err());
+        assert!(validate_year_codes(&[code(2025, "R"), code(2025, "S")]).is_err());
+        assert!(validate_year_codes(&[code(25, "R")]).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::database::{CreateSalesReportRequest, SalesReport};
+use crate::money::Money;
+use serde::Serialize;
+
+/// Largest difference in rupees that still counts as equal, unless the company
+/// configures another one
+pub const DEFAULT_TOLERANCE: f64 = 1.0;
+
+/// An arithmetic rule a sales line can break
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// ass_val = qty × bas_price
+    AssessableValue,
+    /// inv_val = ass_val + c_gst + s_gst + igst, with or without amot, per line or
+    /// for the whole invoice
+    InvoiceValue,
+    /// c_gst = s_gst
+    CgstSgstMismatch,
+    /// CGST/SGST and IGST on the same line
+    MixedGst,
+}
+
+impl Rule {
+    /// Field reported for import and validation errors
+    pub fn field(self) -> &'static str {
+        match self {
+            Rule::AssessableValue => "ass_val",
+            Rule::InvoiceValue => "inv_val",
+            Rule::CgstSgstMismatch => "s_gst",
+            Rule::MixedGst => "igst",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Discrepancy {
+    pub rule: Rule,
+    pub message: String,
+}
+
+/// The amounts of a sales line that the rules look at
+#[derive(Debug, Clone, Copy)]
+pub struct LineAmounts {
+    pub qty: f64,
+    pub bas_price: f64,
+    pub ass_val: Money,
+    pub c_gst: Money,
+    pub s_gst: Money,
+    pub igst: Money,
+    pub amot: Money,
+    pub inv_val: Money,
+}
+
+impl From<&SalesReport> for LineAmounts {
+    fn from(line: &SalesReport) -> Self {
+        LineAmounts {
+            qty: line.qty,
+            bas_price: line.bas_price,
+            ass_val: line.ass_val,
+            c_gst: line.c_gst,
+            s_gst: line.s_gst,
+            igst: line.igst,
+            amot: line.amot,
+            inv_val: line.inv_val,
+        }
+    }
+}
+
+impl From<&CreateSalesReportRequest> for LineAmounts {
+    fn from(line: &CreateSalesReportRequest) -> Self {
+        LineAmounts {
+            qty: line.qty,
+            bas_price: line.bas_price,
+            ass_val: line.ass_val,
+            c_gst: line.c_gst,
+            s_gst: line.s_gst,
+            igst: line.igst,
+            amot: line.amot,
+            inv_val: line.inv_val,
+        }
+    }
+}
+
+/// Every rule of a single line the line breaks. `ass_val` is only checked when the
+/// line has a quantity and price, since source files often leave columns out.
+pub fn check_line(line: LineAmounts, tolerance: Money) -> Vec<Discrepancy> {
+    let mut found = Vec::new();
+    let differs = |a: Money, b: Money| (a - b).abs() > tolerance;
+
+    if line.qty != 0.0 && line.bas_price != 0.0 && !line.ass_val.is_zero() {
+        let expected = Money::from_rupees(line.qty * line.bas_price);
+        if differs(line.ass_val, expected) {
+            found.push(Discrepancy {
+                rule: Rule::AssessableValue,
+                message: format!(
+                    "Assessable value {} does not match qty × price {expected}",
+                    line.ass_val
+                ),
+            });
+        }
+    }
+
+    if differs(line.c_gst, line.s_gst) {
+        found.push(Discrepancy {
+            rule: Rule::CgstSgstMismatch,
+            message: format!("CGST {} and SGST {} differ", line.c_gst, line.s_gst),
+        });
+    }
+
+    if (!line.c_gst.is_zero() || !line.s_gst.is_zero()) && !line.igst.is_zero() {
+        found.push(Discrepancy {
+            rule: Rule::MixedGst,
+            message: "Line has both CGST/SGST and IGST".to_string(),
+        });
+    }
+
+    found
+}
+
+/// Check the invoice value of the lines of one invoice. Source systems either give
+/// each line its own value or repeat the invoice total on every line, so either is
+/// accepted. Invoices without values or assessable values are not checked.
+pub fn check_invoice(lines: &[LineAmounts], tolerance: Money) -> Option<Discrepancy> {
+    if lines.iter().any(|line| line.inv_val.is_zero())
+        || lines.iter().all(|line| line.ass_val.is_zero())
+    {
+        return None;
+    }
+    let matches = |inv_val: Money, without_amot: Money, amot: Money| {
+        (inv_val - without_amot).abs() <= tolerance
+            || (inv_val - without_amot - amot).abs() <= tolerance
+    };
+    let taxed = |line: &LineAmounts| line.ass_val + line.c_gst + line.s_gst + line.igst;
+
+    if lines
+        .iter()
+        .all(|line| matches(line.inv_val, taxed(line), line.amot))
+    {
+        return None;
+    }
+
+    let total: Money = lines.iter().map(taxed).sum();
+    let amot: Money = lines.iter().map(|line| line.amot).sum();
+    let inv_val = lines[0].inv_val;
+    let shared = lines
+        .iter()
+        .all(|line| (line.inv_val - inv_val).abs() <= tolerance);
+    if shared && matches(inv_val, total, amot) {
+        return None;
+    }
+
+    Some(Discrepancy {
+        rule: Rule::InvoiceValue,
+        message: format!("Invoice value {inv_val} does not match value plus taxes {total}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rs(rupees: f64) -> Money {
+        Money::from_rupees(rupees)
+    }
+
+    fn amounts(qty: f64, bas_price: f64, c_gst: f64, s_gst: f64, igst: f64) -> LineAmounts {
+        let ass_val = qty * bas_price;
+        LineAmounts {
+            qty,
+            bas_price,
+            ass_val: rs(ass_val),
+            c_gst: rs(c_gst),
+            s_gst: rs(s_gst),
+            igst: rs(igst),
+            amot: Money::ZERO,
+            inv_val: rs(ass_val + c_gst + s_gst + igst),
+        }
+    }
+
+    fn tolerance() -> Money {
+        rs(DEFAULT_TOLERANCE)
+    }
+
+    fn rules(line: LineAmounts) -> Vec<Rule> {
+        check_line(line, tolerance())
+            .into_iter()
+            .map(|d| d.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_lines_pass() {
+        assert!(rules(amounts(3.0, 100.0, 27.0, 27.0, 0.0)).is_empty());
+        assert!(rules(amounts(3.0, 100.0, 0.0, 0.0, 54.0)).is_empty());
+
+        // Differences within the tolerance and amortisation included in the total
+        let mut line = amounts(3.0, 33.33, 9.0, 9.0, 0.0);
+        line.amot = rs(5.0);
+        line.inv_val += rs(5.5);
+        assert!(rules(line).is_empty());
+        assert!(check_invoice(&[line], tolerance()).is_none());
+
+        // Unit rates finer than a paisa, on large quantities
+        assert!(rules(amounts(10_000.0, 0.125, 112.5, 112.5, 0.0)).is_empty());
+
+        // Missing columns are not checked
+        let mut line = amounts(1.0, 100.0, 0.0, 0.0, 0.0);
+        line.bas_price = 0.0;
+        line.inv_val = Money::ZERO;
+        assert!(rules(line).is_empty());
+        assert!(check_invoice(&[line], tolerance()).is_none());
+    }
+
+    #[test]
+    fn test_reports_each_broken_rule() {
+        let mut line = amounts(2.0, 100.0, 18.0, 18.0, 0.0);
+        line.ass_val = rs(150.0);
+        assert_eq!(rules(line), vec![Rule::AssessableValue]);
+
+        assert_eq!(
+            rules(amounts(1.0, 100.0, 9.0, 5.0, 0.0)),
+            vec![Rule::CgstSgstMismatch]
+        );
+        assert_eq!(
+            rules(amounts(1.0, 100.0, 9.0, 9.0, 18.0)),
+            vec![Rule::MixedGst]
+        );
+        assert!(check_line(amounts(1.0, 100.0, 9.0, 5.0, 0.0), rs(5.0)).is_empty());
+    }
+
+    #[test]
+    fn test_invoice_value() {
+        let first = amounts(2.0, 100.0, 18.0, 18.0, 0.0);
+        let second = amounts(1.0, 50.0, 4.5, 4.5, 0.0);
+        assert!(check_invoice(&[first, second], tolerance()).is_none());
+
+        // The invoice total repeated on every line
+        let shared = |mut line: LineAmounts| {
+            line.inv_val = rs(295.0);
+            line
+        };
+        assert!(check_invoice(&[shared(first), shared(second)], tolerance()).is_none());
+
+        let mut wrong = second;
+        wrong.inv_val = rs(80.0);
+        let found = check_invoice(&[first, wrong], tolerance()).unwrap();
+        assert_eq!(found.rule, Rule::InvoiceValue);
+        let mut missing = shared(second);
+        missing.inv_val = Money::ZERO;
+        assert!(check_invoice(&[shared(first), missing], tolerance()).is_none());
+        assert!(check_invoice(&[shared(first)], tolerance()).is_some());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::error::{AppError, AppResult};
+use crate::gst_split::SplitInvoice;
+use crate::money::Money;
+use std::fmt::Write;
+
+/// Ledger names used for the non-party entries of a sales voucher
+#[derive(Debug, Clone)]
+pub struct TallyLedgers {
+    pub sales: String,
+    pub cgst: String,
+    pub sgst: String,
+    pub igst: String,
+    pub other_charges: String,
+    pub round_off: String,
+}
+
+impl Default for TallyLedgers {
+    fn default() -> Self {
+        TallyLedgers {
+            sales: "Sales".to_string(),
+            cgst: "CGST".to_string(),
+            sgst: "SGST".to_string(),
+            igst: "IGST".to_string(),
+            other_charges: "Other Charges".to_string(),
+            round_off: "Round Off".to_string(),
+        }
+    }
+}
+
+/// A split invoice together with the Tally ledger of its customer
+pub struct TallyVoucher<'a> {
+    pub invoice: &'a SplitInvoice,
+    pub party_ledger: &'a str,
+}
+
+pub fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Tally expects dates as YYYYMMDD
+fn tally_date(date: &str) -> String {
+    date.chars()
+        .filter(|c| c.is_ascii_digit())
+        .take(8)
+        .collect()
+}
+
+// Tally treats debits as negative amounts with ISDEEMEDPOSITIVE=Yes
+fn ledger_entry(xml: &mut String, ledger: &str, amount: Money, debit: bool) {
+    let amount = if debit { -amount } else { amount };
+    let _ = write!(
+        xml,
+        "          <ALLLEDGERENTRIES.LIST>
+            <LEDGERNAME>{}</LEDGERNAME>
+            <ISDEEMEDPOSITIVE>{}</ISDEEMEDPOSITIVE>
+            <ISPARTYLEDGER>{}</ISPARTYLEDGER>
+            <AMOUNT>{}</AMOUNT>
+          </ALLLEDGERENTRIES.LIST>
+",
+        escape_xml(ledger),
+        if debit { "Yes" } else { "No" },
+        if debit { "Yes" } else { "No" },
+        amount
+    );
+}
+
+fn write_voucher(
+    xml: &mut String,
+    voucher: &TallyVoucher,
+    ledgers: &TallyLedgers,
+) -> AppResult<()> {
+    let invoice = voucher.invoice;
+    // Only rounding of the invoice value may go to round off; a larger gap means the
+    // amounts are wrong and the voucher would misstate sales or tax
+    let difference = invoice.inv_val
+        - invoice.ass_val
+        - invoice.c_gst
+        - invoice.s_gst
+        - invoice.igst
+        - invoice.amot;
+    if difference.abs() >= Money::RUPEE {
+        return Err(AppError::validation(
+            "inv_val",
+            None,
+            format!(
+                "Invoice {}: value {} differs from its amounts by {}",
+                invoice.invno, invoice.inv_val, difference
+            ),
+        ));
+    }
+
+    let date = tally_date(&invoice.inv_date);
+    let _ = write!(
+        xml,
+        "      <TALLYMESSAGE xmlns:UDF=\"TallyUDF\">
+        <VOUCHER VCHTYPE=\"Sales\" ACTION=\"Create\" OBJVIEW=\"Accounting Voucher View\">
+          <DATE>{date}</DATE>
+          <EFFECTIVEDATE>{date}</EFFECTIVEDATE>
+          <VOUCHERTYPENAME>Sales</VOUCHERTYPENAME>
+          <VOUCHERNUMBER>{number}</VOUCHERNUMBER>
+          <REFERENCE>{reference}</REFERENCE>
+          <PARTYLEDGERNAME>{party}</PARTYLEDGERNAME>
+          <PERSISTEDVIEW>Accounting Voucher View</PERSISTEDVIEW>
+          <NARRATION>{narration}</NARRATION>
+",
+        number = escape_xml(&invoice.invno),
+        reference = escape_xml(&invoice.original_invno),
+        party = escape_xml(voucher.party_ledger),
+        narration = escape_xml(&format!(
+            "Invoice {} @ {}% GST",
+            invoice.original_invno, invoice.gst_rate
+        )),
+    );
+
+    ledger_entry(xml, voucher.party_ledger, invoice.inv_val, true);
+    ledger_entry(xml, &ledgers.sales, invoice.ass_val, false);
+    for (ledger, amount) in [
+        (&ledgers.cgst, invoice.c_gst),
+        (&ledgers.sgst, invoice.s_gst),
+        (&ledgers.igst, invoice.igst),
+        (&ledgers.other_charges, invoice.amot),
+    ] {
+        if !amount.is_zero() {
+            ledger_entry(xml, ledger, amount, false);
+        }
+    }
+
+    // Keep the voucher balanced when the invoice value was rounded
+    if !difference.is_zero() {
+        ledger_entry(xml, &ledgers.round_off, difference, false);
+    }
+
+    xml.push_str("        </VOUCHER>\n      </TALLYMESSAGE>\n");
+    Ok(())
+}
+
+/// Build a Tally Prime import document with one sales voucher per split invoice.
+/// Fails naming the first invoice whose value is off from its amounts by a rupee or more.
+pub fn sales_vouchers_xml(
+    company_name: &str,
+    vouchers: &[TallyVoucher],
+    ledgers: &TallyLedgers,
+) -> AppResult<String> {
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<ENVELOPE>
+  <HEADER>
+    <TALLYREQUEST>Import Data</TALLYREQUEST>
+  </HEADER>
+  <BODY>
+    <IMPORTDATA>
+      <REQUESTDESC>
+        <REPORTNAME>Vouchers</REPORTNAME>
+        <STATICVARIABLES>
+          <SVCURRENTCOMPANY>{}</SVCURRENTCOMPANY>
+        </STATICVARIABLES>
+      </REQUESTDESC>
+      <REQUESTDATA>
+",
+        escape_xml(company_name)
+    );
+
+    for voucher in vouchers {
+        write_voucher(&mut xml, voucher, ledgers)?;
+    }
+
+    xml.push_str(
+        "      </REQUESTDATA>
+    </IMPORTDATA>
+  </BODY>
+</ENVELOPE>
+",
+    );
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice(invno: &str, c_gst: f64, igst: f64, inv_val: f64) -> SplitInvoice {
+        SplitInvoice {
+            invno: invno.to_string(),
+            original_invno: "251".to_string(),
+            gst_rate: 18.0,
+            cust_code: "C1".to_string(),
+            cust_name: "Alpha".to_string(),
+            inv_date: "2025-05-01".to_string(),
+            RE: String::new(),
+            qty: 1.0,
+            ass_val: Money::from_rupees(100.0),
+            c_gst: Money::from_rupees(c_gst),
+            s_gst: Money::from_rupees(c_gst),
+            igst: Money::from_rupees(igst),
+            amot: Money::ZERO,
+            inv_val: Money::from_rupees(inv_val),
+            igst_yes_no: String::new(),
+            line_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_voucher_entries_balance() {
+        let local = invoice("251", 9.0, 0.0, 118.0);
+        let interstate = invoice("251A", 0.0, 18.0, 118.4);
+        let vouchers = [
+            TallyVoucher {
+                invoice: &local,
+                party_ledger: "Alpha & Sons",
+            },
+            TallyVoucher {
+                invoice: &interstate,
+                party_ledger: "Alpha & Sons",
+            },
+        ];
+        let xml = sales_vouchers_xml("Company <A>", &vouchers, &TallyLedgers::default()).unwrap();
+
+        assert!(xml.contains("<SVCURRENTCOMPANY>Company &lt;A&gt;</SVCURRENTCOMPANY>"));
+        assert_eq!(xml.matches("<VOUCHER ").count(), 2);
+        assert!(xml.contains("<DATE>20250501</DATE>"));
+        assert!(xml.contains("<VOUCHERNUMBER>251A</VOUCHERNUMBER>"));
+        assert!(xml.contains("<PARTYLEDGERNAME>Alpha &amp; Sons</PARTYLEDGERNAME>"));
+        assert!(xml.contains("<AMOUNT>-118.00</AMOUNT>"));
+        assert!(xml.contains("<LEDGERNAME>CGST</LEDGERNAME>"));
+        assert!(xml.contains("<LEDGERNAME>IGST</LEDGERNAME>"));
+        // The second voucher's 0.40 difference goes to round off
+        assert!(xml.contains("<LEDGERNAME>Round Off</LEDGERNAME>\n            <ISDEEMEDPOSITIVE>No</ISDEEMEDPOSITIVE>\n            <ISPARTYLEDGER>No</ISPARTYLEDGER>\n            <AMOUNT>0.40</AMOUNT>"));
+    }
+
+    #[test]
+    fn test_large_difference_is_rejected() {
+        let rounded = invoice("251", 9.0, 0.0, 118.99);
+        let wrong = invoice("251A", 9.0, 0.0, 119.0);
+        let vouchers = [
+            TallyVoucher {
+                invoice: &rounded,
+                party_ledger: "Alpha",
+            },
+            TallyVoucher {
+                invoice: &wrong,
+                party_ledger: "Alpha",
+            },
+        ];
+        let err = sales_vouchers_xml("Company", &vouchers, &TallyLedgers::default()).unwrap_err();
+
+        assert!(matches!(&err, AppError::Validation { field, .. } if field == "inv_val"));
+        assert_eq!(
+            err.message(),
+            "Invoice 251A: value 119.00 differs from its amounts by 1.00"
+        );
+        assert!(sales_vouchers_xml("Company", &vouchers[..1], &TallyLedgers::default()).is_ok());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::database::{Customer, SalesReport};
+use rust_xlsxwriter::{
+    utility::column_number_to_name, ExcelDateTime, Format, FormatBorder, Formula, Workbook,
+    Worksheet, XlsxError,
+};
+
+/// How a column is written. Money and quantity columns get a totals row entry.
+#[derive(Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Text,
+    Date,
+    Count,
+    Quantity,
+    /// Unit rate, with up to four decimals and no total
+    UnitPrice,
+    Money,
+    Rate,
+}
+
+enum Value {
+    Text(String),
+    Number(f64),
+}
+
+const SALES_REPORT_COLUMNS: &[(&str, ColumnKind)] = &[
+    ("cust_code", ColumnKind::Text),
+    ("cust_name", ColumnKind::Text),
+    ("inv_date", ColumnKind::Date),
+    ("RE", ColumnKind::Text),
+    ("invno", ColumnKind::Text),
+    ("line_no", ColumnKind::Count),
+    ("part_code", ColumnKind::Text),
+    ("part_name", ColumnKind::Text),
+    ("tariff", ColumnKind::Text),
+    ("qty", ColumnKind::Quantity),
+    ("bas_price", ColumnKind::UnitPrice),
+    ("ass_val", ColumnKind::Money),
+    ("c_gst", ColumnKind::Money),
+    ("s_gst", ColumnKind::Money),
+    ("igst", ColumnKind::Money),
+    ("amot", ColumnKind::Money),
+    ("inv_val", ColumnKind::Money),
+    ("igst_yes_no", ColumnKind::Text),
+    ("percentage", ColumnKind::Rate),
+];
+
+const CUSTOMER_COLUMNS: &[(&str, ColumnKind)] = &[
+    ("Customer Name", ColumnKind::Text),
+    ("Tally Name", ColumnKind::Text),
+    ("GST No", ColumnKind::Text),
+    ("Category", ColumnKind::Text),
+    ("Created At", ColumnKind::Text),
+];
+
+/// Sales report lines as a workbook with the same columns as the CSV export, plus a
+/// totals row
+pub fn sales_reports_workbook(reports: &[SalesReport]) -> Result<Workbook, XlsxError> {
+    let rows: Vec<Vec<Value>> = reports
+        .iter()
+        .map(|report| {
+            vec![
+                Value::Text(report.cust_code.clone()),
+                Value::Text(report.cust_name.clone()),
+                Value::Text(report.inv_date.clone()),
+                Value::Text(report.RE.clone()),
+                Value::Text(report.invno.clone()),
+                Value::Number(report.line_no.into()),
+                Value::Text(report.part_code.clone().unwrap_or_default()),
+                Value::Text(report.part_name.clone().unwrap_or_default()),
+                Value::Text(report.tariff.clone().unwrap_or_default()),
+                Value::Number(report.qty),
+                Value::Number(report.bas_price),
+                Value::Number(report.ass_val.rupees()),
+                Value::Number(report.c_gst.rupees()),
+                Value::Number(report.s_gst.rupees()),
+                Value::Number(report.igst.rupees()),
+                Value::Number(report.amot.rupees()),
+                Value::Number(report.inv_val.rupees()),
+                Value::Text(report.igst_yes_no.clone()),
+                Value::Number(report.percentage),
+            ]
+        })
+        .collect();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet().set_name("Sales Reports")?;
+    write_table(worksheet, SALES_REPORT_COLUMNS, &rows, true)?;
+    Ok(workbook)
+}
+
+pub fn customers_workbook(customers: &[Customer]) -> Result<Workbook, XlsxError> {
+    let rows: Vec<Vec<Value>> = customers
+        .iter()
+        .map(|customer| {
+            vec![
+                Value::Text(customer.customer_name.clone()),
+                Value::Text(customer.tally_name.clone()),
+                Value::Text(customer.gst_no.clone().unwrap_or_default()),
+                Value::Text(customer.category_name.clone().unwrap_or_default()),
+                Value::Text(customer.created_at.clone()),
+            ]
+        })
+        .collect();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet().set_name("Customers")?;
+    write_table(worksheet, CUSTOMER_COLUMNS, &rows, false)?;
+    Ok(workbook)
+}
+
+fn write_table(
+    worksheet: &mut Worksheet,
+    columns: &[(&str, ColumnKind)],
+    rows: &[Vec<Value>],
+    totals: bool,
+) -> Result<(), XlsxError> {
+    let header_format = Format::new()
+        .set_bold()
+        .set_border_bottom(FormatBorder::Thin);
+    let text_format = Format::new().set_num_format("@");
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    let count_format = Format::new().set_num_format("0");
+    let money_format = Format::new().set_num_format("#,##0.00");
+    let rate_format = Format::new().set_num_format("0.00");
+    let unit_price_format = Format::new().set_num_format("#,##0.00##");
+    let quantity_format = Format::new();
+    let format_of = |kind: ColumnKind| match kind {
+        ColumnKind::Text => &text_format,
+        ColumnKind::Date => &date_format,
+        ColumnKind::Count => &count_format,
+        ColumnKind::Quantity => &quantity_format,
+        ColumnKind::UnitPrice => &unit_price_format,
+        ColumnKind::Money => &money_format,
+        ColumnKind::Rate => &rate_format,
+    };
+
+    for (col, (header, _)) in columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &header_format)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+
+    for (index, values) in rows.iter().enumerate() {
+        let row = index as u32 + 1;
+        for (col, ((_, kind), value)) in columns.iter().zip(values).enumerate() {
+            let col = col as u16;
+            let format = format_of(*kind);
+            match value {
+                Value::Number(number) => {
+                    worksheet.write_number_with_format(row, col, *number, format)?;
+                }
+                // Dates the app could not normalize are kept as text
+                Value::Text(text) if *kind == ColumnKind::Date => {
+                    match ExcelDateTime::parse_from_str(text) {
+                        Ok(date) => worksheet.write_datetime_with_format(row, col, date, format)?,
+                        Err(_) => {
+                            worksheet.write_string_with_format(row, col, text, &text_format)?
+                        }
+                    };
+                }
+                Value::Text(text) => {
+                    worksheet.write_string_with_format(row, col, text, format)?;
+                }
+            }
+        }
+    }
+
+    if totals && !rows.is_empty() {
+        let total_row = rows.len() as u32 + 1;
+        let bold = Format::new().set_bold();
+        worksheet.write_string_with_format(total_row, 0, "Total", &bold)?;
+
+        for (col, (_, kind)) in columns.iter().enumerate() {
+            if !matches!(kind, ColumnKind::Quantity | ColumnKind::Money) {
+                continue;
+            }
+            let sum: f64 = rows
+                .iter()
+                .map(|values| match values.get(col) {
+                    Some(Value::Number(number)) => *number,
+                    _ => 0.0,
+                })
+                .sum();
+            let letter = column_number_to_name(col as u16);
+            // The cached result lets viewers that do not recalculate show the total
+            let formula = Formula::new(format!("=SUM({letter}2:{letter}{total_row})"))
+                .set_result(sum.to_string());
+            worksheet.write_formula_with_format(
+                total_row,
+                col as u16,
+                formula,
+                &format_of(*kind).clone().set_bold(),
+            )?;
+        }
+    }
+
+    worksheet.autofit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::excel;
+    use crate::money::Money;
+
+    fn report(cust_code: &str, inv_date: &str, ass_val: f64) -> SalesReport {
+        SalesReport {
+            id: 1,
+            company_id: 1,
+            cust_code: cust_code.to_string(),
+            cust_name: "Alpha".to_string(),
+            inv_date: inv_date.to_string(),
+            RE: String::new(),
+            invno: "251".to_string(),
+            line_no: 1,
+            part_code: Some("00123".to_string()),
+            part_name: None,
+            tariff: Some("8421.31.00".to_string()),
+            qty: 2.0,
+            bas_price: ass_val / 2.0,
+            ass_val: Money::from_rupees(ass_val),
+            c_gst: Money::ZERO,
+            s_gst: Money::ZERO,
+            igst: Money::ZERO,
+            amot: Money::ZERO,
+            inv_val: Money::from_rupees(ass_val),
+            igst_yes_no: "no".to_string(),
+            percentage: 0.0,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_sales_workbook_round_trip() {
+        let reports = vec![
+            report("007", "2025-05-01", 100.5),
+            report("C2", "not a date", 200.25),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales.xlsx");
+        sales_reports_workbook(&reports)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let sheet = excel::read_sheet(&path, None).unwrap();
+        assert_eq!(sheet.sheet_name, "Sales Reports");
+        assert_eq!(sheet.cells.len(), 4);
+        let first = &sheet.cells[1];
+        assert_eq!(first[0], "007");
+        assert_eq!(first[2], "2025-05-01");
+        assert_eq!(first[6], "00123");
+        assert_eq!(sheet.cells[2][2], "not a date");
+
+        let totals = &sheet.cells[3];
+        assert_eq!(totals[0], "Total");
+        assert_eq!(totals[9], "4");
+        assert_eq!(totals[11], "300.75");
+        assert_eq!(totals[1], "");
+    }
+}
@@ -155,7 +155,7 @@ export const CustomersList: React.FC<CustomersListProps> = ({
       const toastId = showToast.loading('Importing customers...');
       setLoading(true);
 
-      const importedCount = await dbManager.importCustomersCSV(
+      const { imported: importedCount } = await dbManager.importCustomersCSV(
         selectedCompany.id,
         csvData
       );
@@ -237,7 +237,7 @@ export const CustomersList: React.FC<CustomersListProps> = ({
       // Convert transformed data to CSV format for import
       const csvContent = generateCSVForImport(result.data);
 
-      const importedCount = await dbManager.importCustomersCSV(
+      const { imported: importedCount } = await dbManager.importCustomersCSV(
         selectedCompany.id,
         csvContent
       );
@@ -141,7 +141,7 @@ export const ImportTransformTable: React.FC<ImportTransformTableProps> = ({
       // Convert transformed data to CSV format for import
       const csvContent = generateCSVForImport(transformationResult.data);
 
-      const importedCount = await dbManager.importSalesReportsCSV(
+      const { imported: importedCount } = await dbManager.importSalesReportsCSV(
         selectedCompany.id,
         csvContent
       );
@@ -4,7 +4,7 @@ import type {
   SalesReportFilters,
   PaginatedResult,
 } from '../utils/database';
-import { dbManager } from '../utils/database';
+import { dbManager, getErrorMessage } from '../utils/database';
 import { useCompanyContext } from '../contexts/CompanyContext';
 import {
   Search,
@@ -298,9 +298,10 @@ export const OptimizedReports: React.FC = () => {
       const toastId = showToast.loading('Importing data directly...');
 
       try {
-        const importedCount = await dbManager.importSalesReportsCSV(
+        const { imported: importedCount } = await dbManager.importSalesReportsCSV(
           selectedCompany!.id,
-          csvContent
+          csvContent,
+          file.name
         );
 
         showToast.dismiss(toastId);
@@ -319,9 +320,7 @@ export const OptimizedReports: React.FC = () => {
       } catch (importError) {
         showToast.dismiss(toastId);
         console.error('Import error:', importError);
-        showToast.error(
-          `Import failed: ${importError instanceof Error ? importError.message : 'Unknown error'}`
-        );
+        showToast.error(`Import failed: ${getErrorMessage(importError)}`);
       }
     } catch (error) {
       console.error('Failed to import file directly:', error);
@@ -53,29 +53,31 @@ export const Settings: React.FC = () => {
   };
 
   const handleClearDatabase = async () => {
-    const confirmed = window.confirm(
-      '⚠️ WARNING: This will permanently delete ALL data from the database!\n\n' +
-        'This includes:\n' +
-        '• All companies\n' +
-        '• All customers\n' +
-        '• All sales reports\n' +
-        '• All categories\n' +
-        '• All uploaded files\n' +
-        '• All audit logs\n\n' +
-        'This action cannot be undone!\n\n' +
-        'Are you absolutely sure you want to continue?'
-    );
-
-    if (!confirmed) return;
-
     try {
       setLoading(true);
+      const preview = await dbManager.prepareClearData({ kind: 'everything' });
+      const confirmed = window.confirm(
+        '⚠️ WARNING: This will delete ALL companies and their data!\n\n' +
+          'Rows to delete:\n' +
+          preview.tables
+            .filter(t => t.rows > 0)
+            .map(t => `• ${t.table}: ${t.rows}`)
+            .join('\n') +
+          `\n\nTotal: ${preview.total_rows} rows. The audit log is kept.\n\n` +
+          'A backup is saved first and can be restored afterwards.\n\n' +
+          'Are you absolutely sure you want to continue?'
+      );
+
+      if (!confirmed) return;
+
       console.log('Starting database clear operation...');
-      await dbManager.clearAllData();
+      const result = await dbManager.clearData(preview.scope, preview.token);
       console.log('Database clear operation completed');
 
       showToast.success(
-        'All database data has been cleared successfully, including customers!'
+        result
+          ? `All database data has been cleared. Backup saved to ${result.backup_path}`
+          : 'All database data has been cleared successfully'
       );
 
       // Refresh companies list to reflect the cleared state
@@ -6,6 +6,65 @@ export interface Company {
   name: string;
   key: string;
   created_at: string;
+  reconcile_tolerance: number;
+  gstin?: string;
+  state_code?: string;
+  address?: string;
+  fiscal_year_start: number;
+  archived_at?: string;
+}
+
+export interface CreateCompanyRequest {
+  name: string;
+  key: string;
+  gstin?: string;
+  state_code?: string;
+  address?: string;
+  fiscal_year_start?: number;
+  reconcile_tolerance?: number;
+}
+
+export interface TableRowCount {
+  table: string;
+  rows: number;
+}
+
+export interface CompanyDeletionPreview {
+  company: Company;
+  tables: TableRowCount[];
+  total_rows: number;
+}
+
+export type ClearScope =
+  | { kind: 'everything' }
+  | { kind: 'company'; company_id: number }
+  | {
+      kind: 'sales';
+      company_id?: number | null;
+      date_from?: string | null;
+      date_to?: string | null;
+    };
+
+export interface ClearDataPreview {
+  scope: ClearScope;
+  tables: TableRowCount[];
+  total_rows: number;
+  token: string;
+}
+
+export interface ClearDataResult {
+  deleted: TableRowCount[];
+  backup_path: string;
+}
+
+export type BackupKind = 'daily' | 'before_clear' | 'before_restore' | 'other';
+
+export interface BackupInfo {
+  file_name: string;
+  path: string;
+  kind: BackupKind;
+  size_bytes: number;
+  modified_at: string;
 }
 
 export interface Customer {
@@ -14,11 +73,26 @@ export interface Customer {
   customer_name: string;
   tally_name: string;
   gst_no?: string;
+  gst_state_code?: string;
   category_id?: number;
   category_name?: string;
   created_at: string;
 }
 
+export interface Gstin {
+  gstin: string;
+  state_code: string;
+  state_name: string;
+  pan: string;
+}
+
+export interface InvalidGstin {
+  customer_id: number;
+  customer_name: string;
+  gst_no: string;
+  message: string;
+}
+
 export interface Category {
   id: number;
   company_id: number;
@@ -35,6 +109,7 @@ export interface SalesReport {
   inv_date: string;
   RE: string;
   invno: string;
+  line_no: number;
   part_code?: string;
   part_name?: string;
   tariff?: string;
@@ -60,6 +135,14 @@ export interface SalesReportFilters {
   max_amount?: number;
 }
 
+export interface AuditLogFilters {
+  company_id?: number;
+  user_action?: string;
+  search?: string;
+  date_from?: string;
+  date_to?: string;
+}
+
 export interface PaginatedResult<T> {
   data: T[];
   total: number;
@@ -75,11 +158,14 @@ export interface UploadedReport {
   uploaded_at: string;
   status: string;
   parsed_hash?: string;
+  row_count: number;
+  sales_line_count: number;
 }
 
 export interface ReportRow {
   id: number;
   uploaded_report_id: number;
+  row_number: number;
   invoice_no: string;
   invoice_date: string;
   customer_name_raw: string;
@@ -102,7 +188,7 @@ export interface TallyExport {
 
 export interface AuditLog {
   id: number;
-  company_id: number;
+  company_id: number | null;
   user_action: string;
   details_json: string;
   timestamp: string;
@@ -117,6 +203,169 @@ export interface InvoiceMapping {
   created_at: string;
 }
 
+export interface UnmappedCustomer {
+  cust_code: string;
+  cust_name: string;
+  invoice_count: number;
+  line_count: number;
+  total_inv_val: number;
+}
+
+export interface RejectedRow {
+  row_number: number;
+  field: string;
+  reason: string;
+  duplicate: boolean;
+  values: Record<string, string>;
+}
+
+export type ImportMode = 'best_effort' | 'all_or_nothing';
+
+export interface ImportReport {
+  imported: number;
+  skipped: number;
+  duplicates: number;
+  uploaded_report_id: number | null;
+  mapping_profile_id: number | null;
+  headers: string[];
+  rejected_rows: RejectedRow[];
+}
+
+export interface ColumnMapping {
+  field: string;
+  header: string | null;
+}
+
+export type SalesReportPreviewRow = Omit<
+  SalesReport,
+  'id' | 'created_at' | 'line_no'
+> & {
+  line_no: number | null;
+};
+
+export interface CustomerImportRow {
+  customer_name: string;
+  tally_name: string;
+  gst_no: string | null;
+  gst_state_code: string | null;
+  category: string | null;
+}
+
+export interface ImportPreview<T> {
+  column_mapping: ColumnMapping[];
+  unmapped_headers: string[];
+  rows: T[];
+  report: ImportReport;
+  duplicate_upload_id: number | null;
+}
+
+export interface ColumnMappingProfile {
+  id: number;
+  company_id: number;
+  name: string;
+  mappings: Record<string, string[]>;
+  created_at: string;
+  updated_at: string;
+}
+
+export type ReconciliationRule =
+  | 'assessable_value'
+  | 'invoice_value'
+  | 'cgst_sgst_mismatch'
+  | 'mixed_gst';
+
+export interface ReconciliationIssue {
+  sales_report_id: number | null;
+  invno: string;
+  line_no: number | null;
+  inv_date: string;
+  cust_name: string;
+  rule: ReconciliationRule;
+  message: string;
+}
+
+export type TaxType = 'cgst_sgst' | 'igst';
+
+export interface PlaceOfSupplyIssue {
+  invno: string;
+  inv_date: string;
+  cust_name: string;
+  customer_state_code: string;
+  expected: TaxType;
+  message: string;
+}
+
+export interface YearCode {
+  year: number;
+  code: string;
+}
+
+export interface SplitInvoice {
+  invno: string;
+  original_invno: string;
+  gst_rate: number;
+  cust_code: string;
+  cust_name: string;
+  inv_date: string;
+  RE: string;
+  qty: number;
+  ass_val: number;
+  c_gst: number;
+  s_gst: number;
+  igst: number;
+  amot: number;
+  inv_val: number;
+  igst_yes_no: string;
+  line_count: number;
+}
+
+// Error returned by backend commands, tagged by `kind`
+export type AppError =
+  | { kind: 'NotFound'; message: string }
+  | { kind: 'Duplicate'; message: string; existing_id: number | null }
+  | {
+      kind: 'Validation';
+      message: string;
+      field: string;
+      row: number | null;
+    }
+  | { kind: 'Constraint'; message: string }
+  | {
+      kind: 'SchemaTooNew';
+      message: string;
+      version: number;
+      supported: number;
+    }
+  | {
+      kind: 'UnmappedCustomers';
+      message: string;
+      customers: UnmappedCustomer[];
+    }
+  | {
+      kind: 'PlaceOfSupply';
+      message: string;
+      issues: PlaceOfSupplyIssue[];
+    }
+  | { kind: 'Io'; message: string }
+  | { kind: 'Csv'; message: string }
+  | { kind: 'Database'; message: string };
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    'kind' in error &&
+    'message' in error
+  );
+}
+
+export function getErrorMessage(error: unknown): string {
+  if (isAppError(error) || error instanceof Error) {
+    return error.message;
+  }
+  return typeof error === 'string' ? error : 'Unknown error';
+}
+
 class DatabaseManager {
   private initialized = false;
   private devCompanies: Company[] = [];
@@ -146,12 +395,16 @@ class DatabaseManager {
             key: 'demo-company-1',
             name: 'Demo Company 1',
             created_at: new Date().toISOString(),
+            reconcile_tolerance: 1,
+            fiscal_year_start: 4,
           },
           {
             id: 2,
             key: 'demo-company-2',
             name: 'Demo Company 2',
             created_at: new Date().toISOString(),
+            reconcile_tolerance: 1,
+            fiscal_year_start: 4,
           },
         ];
         this.saveDevCompaniesToStorage();
@@ -166,12 +419,16 @@ class DatabaseManager {
           key: 'demo-company-1',
           name: 'Demo Company 1',
           created_at: new Date().toISOString(),
+          reconcile_tolerance: 1,
+          fiscal_year_start: 4,
         },
         {
           id: 2,
           key: 'demo-company-2',
           name: 'Demo Company 2',
           created_at: new Date().toISOString(),
+          reconcile_tolerance: 1,
+          fiscal_year_start: 4,
         },
       ];
     }
@@ -197,12 +454,16 @@ class DatabaseManager {
         key: 'demo-company-1',
         name: 'Demo Company 1',
         created_at: new Date().toISOString(),
+        reconcile_tolerance: 1,
+        fiscal_year_start: 4,
       },
       {
         id: 2,
         key: 'demo-company-2',
         name: 'Demo Company 2',
         created_at: new Date().toISOString(),
+        reconcile_tolerance: 1,
+        fiscal_year_start: 4,
       },
     ];
     this.saveDevCompaniesToStorage();
@@ -260,13 +521,39 @@ class DatabaseManager {
     console.log('Cleared all sales reports from development storage');
   }
 
-  // Clear all data from database
-  async clearAllData(): Promise<void> {
+  // Add sample companies to an empty database
+  async seedSampleData(): Promise<boolean> {
+    try {
+      return await invoke('seed_sample_data');
+    } catch (error) {
+      console.error('Failed to seed sample data:', error);
+      throw error;
+    }
+  }
+
+  // Count what a clear would delete and get the token that confirms it
+  async prepareClearData(scope: ClearScope): Promise<ClearDataPreview> {
+    try {
+      return await invoke('prepare_clear_data', { scope });
+    } catch (error) {
+      console.error('Failed to prepare clearing data:', error);
+      throw error;
+    }
+  }
+
+  // Clear data after a backup snapshot; returns null in web mode
+  async clearData(
+    scope: ClearScope,
+    token: string
+  ): Promise<ClearDataResult | null> {
     try {
       if ((window as unknown as { __TAURI__?: unknown }).__TAURI__) {
-        console.log('Clearing all database data...');
-        await invoke('clear_all_data');
-        console.log('All data cleared from database successfully');
+        console.log('Clearing database data...', scope);
+        const result: ClearDataResult = await invoke('clear_data', {
+          scope,
+          token,
+        });
+        console.log('Database data cleared, backup at', result.backup_path);
 
         // Clear any localStorage data that might be cached
         localStorage.removeItem('selectedCompanyKey');
@@ -274,11 +561,13 @@ class DatabaseManager {
         localStorage.removeItem('customers');
         localStorage.removeItem('salesReports');
         console.log('LocalStorage cache cleared');
+        return result;
       } else {
         console.warn('Database clear not available in web mode');
         // In web mode, clear localStorage data
         localStorage.clear();
         console.log('LocalStorage cleared in web mode');
+        return null;
       }
     } catch (error) {
       console.error('Failed to clear database:', error);
@@ -286,6 +575,56 @@ class DatabaseManager {
     }
   }
 
+  // Write a consistent copy of the database to a full file path
+  async backupDatabase(path: string): Promise<BackupInfo> {
+    try {
+      return await invoke('backup_database', { path });
+    } catch (error) {
+      console.error('Failed to back up database:', error);
+      throw error;
+    }
+  }
+
+  // Automatic backups next to the database, newest first
+  async listBackups(): Promise<BackupInfo[]> {
+    try {
+      return await invoke('list_backups');
+    } catch (error) {
+      console.error('Failed to list backups:', error);
+      throw error;
+    }
+  }
+
+  // Replace the database contents with a backup
+  async restoreBackup(path: string): Promise<void> {
+    try {
+      await invoke('restore_backup', { path });
+    } catch (error) {
+      console.error('Failed to restore backup:', error);
+      throw error;
+    }
+  }
+
+  // Number of daily backups kept before the oldest are deleted
+  async getDailyBackupsKept(): Promise<number> {
+    try {
+      return await invoke('get_daily_backups_kept');
+    } catch (error) {
+      console.error('Failed to get daily backup setting:', error);
+      throw error;
+    }
+  }
+
+  // Save the number of daily backups to keep (at least 1); older ones are deleted now
+  async setDailyBackupsKept(keep: number): Promise<void> {
+    try {
+      await invoke('set_daily_backups_kept', { keep });
+    } catch (error) {
+      console.error('Failed to save daily backup setting:', error);
+      throw error;
+    }
+  }
+
   // Data migration utility to fix field names
   migrateFieldNames(): void {
     console.log('Starting field name migration...');
@@ -349,14 +688,16 @@ class DatabaseManager {
   }
 
   // Company operations
-  async getCompanies(): Promise<Company[]> {
+  async getCompanies(includeArchived?: boolean): Promise<Company[]> {
     try {
       // Check if we're running in Tauri
       if (
         typeof window !== 'undefined' &&
         (window as unknown as { __TAURI__?: unknown }).__TAURI__
       ) {
-        return await invoke('get_companies');
+        return await invoke('get_companies', {
+          includeArchived: includeArchived ?? null,
+        });
       } else {
         // Fallback for development mode - return demo companies
         console.warn('Running in development mode - returning demo companies');
@@ -370,7 +711,15 @@ class DatabaseManager {
 
   async updateCompany(
     id: number,
-    company: { name?: string; key?: string }
+    company: {
+      name?: string;
+      key?: string;
+      reconcile_tolerance?: number;
+      gstin?: string;
+      state_code?: string;
+      address?: string;
+      fiscal_year_start?: number;
+    }
   ): Promise<boolean> {
     try {
       // Check if we're running in Tauri
@@ -412,6 +761,56 @@ class DatabaseManager {
     }
   }
 
+  async createCompany(company: CreateCompanyRequest): Promise<number> {
+    try {
+      return await invoke('create_company', { company });
+    } catch (error) {
+      console.error('Failed to create company:', error);
+      throw error;
+    }
+  }
+
+  async archiveCompany(id: number): Promise<boolean> {
+    try {
+      return await invoke('archive_company', { id });
+    } catch (error) {
+      console.error('Failed to archive company:', error);
+      throw error;
+    }
+  }
+
+  async unarchiveCompany(id: number): Promise<boolean> {
+    try {
+      return await invoke('unarchive_company', { id });
+    } catch (error) {
+      console.error('Failed to unarchive company:', error);
+      throw error;
+    }
+  }
+
+  async getCompanyDeletionPreview(
+    id: number
+  ): Promise<CompanyDeletionPreview> {
+    try {
+      return await invoke('company_deletion_preview', { id });
+    } catch (error) {
+      console.error('Failed to preview company deletion:', error);
+      throw error;
+    }
+  }
+
+  async deleteCompany(
+    id: number,
+    confirmKey: string
+  ): Promise<TableRowCount[]> {
+    try {
+      return await invoke('delete_company', { id, confirmKey });
+    } catch (error) {
+      console.error('Failed to delete company:', error);
+      throw error;
+    }
+  }
+
   async getCompanyById(id: number): Promise<Company | null> {
     try {
       const companies = await this.getCompanies();
@@ -495,18 +894,67 @@ class DatabaseManager {
     }
   }
 
+  async exportCustomersXLSX(companyId: number, path: string): Promise<number> {
+    try {
+      return await invoke('export_customers_xlsx', { companyId, path });
+    } catch (error) {
+      console.error('Failed to export customers XLSX:', error);
+      throw error;
+    }
+  }
+
   async importCustomersCSV(
     companyId: number,
-    csvData: string
-  ): Promise<number> {
+    csvData: string,
+    mode?: ImportMode
+  ): Promise<ImportReport> {
     try {
-      return await invoke('import_customers_csv', { companyId, csvData });
+      return await invoke('import_customers_csv', {
+        companyId,
+        csvData,
+        mode: mode ?? null,
+      });
     } catch (error) {
       console.error('Failed to import customers CSV:', error);
       throw error;
     }
   }
 
+  async previewCustomersCSV(
+    companyId: number,
+    csvData: string,
+    previewRows?: number
+  ): Promise<ImportPreview<CustomerImportRow>> {
+    try {
+      return await invoke('preview_customers_csv', {
+        companyId,
+        csvData,
+        previewRows: previewRows ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to preview customers CSV:', error);
+      throw error;
+    }
+  }
+
+  async validateGstin(gstin: string): Promise<Gstin> {
+    try {
+      return await invoke('validate_gstin', { gstin });
+    } catch (error) {
+      console.error('Failed to validate GSTIN:', error);
+      throw error;
+    }
+  }
+
+  async findInvalidGstins(companyId: number): Promise<InvalidGstin[]> {
+    try {
+      return await invoke('find_invalid_gstins', { companyId });
+    } catch (error) {
+      console.error('Failed to find invalid GSTINs:', error);
+      throw error;
+    }
+  }
+
   // Category management methods
   async getCategoriesByCompany(companyId: number): Promise<Category[]> {
     try {
@@ -748,15 +1196,26 @@ class DatabaseManager {
 
   async importSalesReportsCSV(
     companyId: number,
-    csvData: string
-  ): Promise<number> {
+    csvData: string,
+    filename?: string,
+    force?: boolean,
+    mode?: ImportMode,
+    profileId?: number
+  ): Promise<ImportReport> {
     try {
       // Check if we're running in Tauri
       if (
         typeof window !== 'undefined' &&
         (window as unknown as { __TAURI__?: unknown }).__TAURI__
       ) {
-        return await invoke('import_sales_reports_csv', { companyId, csvData });
+        return await invoke('import_sales_reports_csv', {
+          companyId,
+          csvData,
+          filename: filename ?? null,
+          force: force ?? null,
+          mode: mode ?? null,
+          profileId: profileId ?? null,
+        });
       } else {
         // Fallback for development mode - actually import and store data
         console.warn(
@@ -1049,7 +1508,15 @@ class DatabaseManager {
         console.log(
           `Imported ${newReports.length} sales reports for company ${companyId}`
         );
-        return newReports.length;
+        return {
+          imported: newReports.length,
+          skipped: dataRows.length - newReports.length,
+          duplicates: 0,
+          uploaded_report_id: null,
+          mapping_profile_id: null,
+          headers,
+          rejected_rows: [],
+        };
       }
     } catch (error) {
       console.error('Failed to import sales reports CSV:', error);
@@ -1057,6 +1524,59 @@ class DatabaseManager {
     }
   }
 
+  async getExcelSheets(path: string): Promise<string[]> {
+    try {
+      return await invoke('get_excel_sheets', { path });
+    } catch (error) {
+      console.error('Failed to read Excel sheets:', error);
+      throw error;
+    }
+  }
+
+  async importSalesReportsExcel(
+    companyId: number,
+    path: string,
+    sheet?: string,
+    headerRow?: number,
+    force?: boolean,
+    mode?: ImportMode,
+    profileId?: number
+  ): Promise<ImportReport> {
+    try {
+      return await invoke('import_sales_reports_excel', {
+        companyId,
+        path,
+        sheet: sheet ?? null,
+        headerRow: headerRow ?? null,
+        force: force ?? null,
+        mode: mode ?? null,
+        profileId: profileId ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to import Excel sales reports:', error);
+      throw error;
+    }
+  }
+
+  async previewSalesReportsCSV(
+    companyId: number,
+    csvData: string,
+    previewRows?: number,
+    profileId?: number
+  ): Promise<ImportPreview<SalesReportPreviewRow>> {
+    try {
+      return await invoke('preview_sales_reports_csv', {
+        companyId,
+        csvData,
+        previewRows: previewRows ?? null,
+        profileId: profileId ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to preview sales reports CSV:', error);
+      throw error;
+    }
+  }
+
   async exportSalesReportsCSV(companyId: number): Promise<string> {
     try {
       return await invoke('export_sales_reports_csv', { companyId });
@@ -1066,14 +1586,283 @@ class DatabaseManager {
     }
   }
 
+  async exportSalesReportsXLSX(
+    companyId: number,
+    path: string
+  ): Promise<number> {
+    try {
+      return await invoke('export_sales_reports_xlsx', { companyId, path });
+    } catch (error) {
+      console.error('Failed to export sales reports XLSX:', error);
+      throw error;
+    }
+  }
+
+  async getImportErrorCSV(report: ImportReport): Promise<string> {
+    try {
+      return await invoke('get_import_error_csv', { report });
+    } catch (error) {
+      console.error('Failed to build import error CSV:', error);
+      throw error;
+    }
+  }
+
+  // Column mapping profile operations
+  async getColumnMappingProfiles(
+    companyId: number
+  ): Promise<ColumnMappingProfile[]> {
+    try {
+      return await invoke('get_column_mapping_profiles', { companyId });
+    } catch (error) {
+      console.error('Failed to get column mapping profiles:', error);
+      throw error;
+    }
+  }
+
+  async createColumnMappingProfile(profile: {
+    company_id: number;
+    name: string;
+    mappings: Record<string, string[]>;
+  }): Promise<number> {
+    try {
+      return await invoke('create_column_mapping_profile', { profile });
+    } catch (error) {
+      console.error('Failed to create column mapping profile:', error);
+      throw error;
+    }
+  }
+
+  async updateColumnMappingProfile(
+    id: number,
+    profile: { name?: string; mappings?: Record<string, string[]> }
+  ): Promise<boolean> {
+    try {
+      return await invoke('update_column_mapping_profile', { id, profile });
+    } catch (error) {
+      console.error('Failed to update column mapping profile:', error);
+      throw error;
+    }
+  }
+
+  async deleteColumnMappingProfile(id: number): Promise<boolean> {
+    try {
+      return await invoke('delete_column_mapping_profile', { id });
+    } catch (error) {
+      console.error('Failed to delete column mapping profile:', error);
+      throw error;
+    }
+  }
+
+  async detectColumnMappingProfile(
+    companyId: number,
+    headers: string[]
+  ): Promise<ColumnMappingProfile | null> {
+    try {
+      return await invoke('detect_column_mapping_profile', {
+        companyId,
+        headers,
+      });
+    } catch (error) {
+      console.error('Failed to detect column mapping profile:', error);
+      throw error;
+    }
+  }
+
+  // Reconciliation operations
+  async reconcileSalesReports(
+    companyId: number,
+    tolerance?: number
+  ): Promise<ReconciliationIssue[]> {
+    try {
+      return await invoke('reconcile_sales_reports', {
+        companyId,
+        tolerance: tolerance ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to reconcile sales reports:', error);
+      throw error;
+    }
+  }
+
+  async checkPlaceOfSupply(
+    companyId: number,
+    uploadedReportId?: number
+  ): Promise<PlaceOfSupplyIssue[]> {
+    try {
+      return await invoke('check_place_of_supply', {
+        companyId,
+        uploadedReportId: uploadedReportId ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to check place of supply:', error);
+      throw error;
+    }
+  }
+
+  // RE code operations
+  async getREYearCodes(companyId: number): Promise<YearCode[]> {
+    try {
+      return await invoke('get_re_year_codes', { companyId });
+    } catch (error) {
+      console.error('Failed to get RE year codes:', error);
+      throw error;
+    }
+  }
+
+  async setREYearCodes(companyId: number, codes: YearCode[]): Promise<void> {
+    try {
+      await invoke('set_re_year_codes', { companyId, codes });
+    } catch (error) {
+      console.error('Failed to save RE year codes:', error);
+      throw error;
+    }
+  }
+
+  async recomputeRECodes(companyId: number): Promise<number> {
+    try {
+      return await invoke('recompute_re_codes', { companyId });
+    } catch (error) {
+      console.error('Failed to recompute RE codes:', error);
+      throw error;
+    }
+  }
+
+  // Upload operations
+  async getUploadedReports(companyId: number): Promise<UploadedReport[]> {
+    try {
+      return await invoke('get_uploaded_reports', { companyId });
+    } catch (error) {
+      console.error('Failed to get uploaded reports:', error);
+      throw error;
+    }
+  }
+
+  async getReportRows(uploadedReportId: number): Promise<ReportRow[]> {
+    try {
+      return await invoke('get_report_rows', { uploadedReportId });
+    } catch (error) {
+      console.error('Failed to get report rows:', error);
+      throw error;
+    }
+  }
+
+  async reprocessUpload(uploadedReportId: number): Promise<ImportReport> {
+    try {
+      return await invoke('reprocess_upload', { uploadedReportId });
+    } catch (error) {
+      console.error('Failed to re-process upload:', error);
+      throw error;
+    }
+  }
+
+  async rollbackUpload(uploadedReportId: number): Promise<number> {
+    try {
+      return await invoke('rollback_upload', { uploadedReportId });
+    } catch (error) {
+      console.error('Failed to roll back upload:', error);
+      throw error;
+    }
+  }
+
+  async splitInvoices(
+    companyId: number,
+    uploadedReportId?: number
+  ): Promise<SplitInvoice[]> {
+    try {
+      return await invoke('split_invoices', {
+        companyId,
+        uploadedReportId: uploadedReportId ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to split invoices:', error);
+      throw error;
+    }
+  }
+
+  async getInvoiceMapping(
+    companyId: number,
+    invoiceNo: string
+  ): Promise<InvoiceMapping | null> {
+    try {
+      return await invoke('get_invoice_mapping', { companyId, invoiceNo });
+    } catch (error) {
+      console.error('Failed to look up invoice mapping:', error);
+      throw error;
+    }
+  }
+
+  async getUnmappedCustomers(
+    companyId: number,
+    uploadedReportId?: number
+  ): Promise<UnmappedCustomer[]> {
+    try {
+      return await invoke('get_unmapped_customers', {
+        companyId,
+        uploadedReportId: uploadedReportId ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to get unmapped customers:', error);
+      throw error;
+    }
+  }
+
+  async generateTallyExport(
+    uploadedReportId: number,
+    outputDir?: string
+  ): Promise<TallyExport> {
+    try {
+      return await invoke('generate_tally_export', {
+        uploadedReportId,
+        outputDir: outputDir ?? null,
+      });
+    } catch (error) {
+      console.error('Failed to generate Tally export:', error);
+      throw error;
+    }
+  }
+
+  async getTallyExports(companyId: number): Promise<TallyExport[]> {
+    try {
+      return await invoke('get_tally_exports', { companyId });
+    } catch (error) {
+      console.error('Failed to get Tally exports:', error);
+      throw error;
+    }
+  }
+
   // Audit logging
+  async getAuditLogs(
+    page: number = 1,
+    pageSize: number = 50,
+    filters?: AuditLogFilters
+  ): Promise<PaginatedResult<AuditLog>> {
+    try {
+      const [data, total] = (await invoke('get_audit_logs', {
+        page,
+        pageSize,
+        filters: filters || null,
+      })) as [AuditLog[], number];
+
+      return {
+        data,
+        total,
+        page,
+        pageSize,
+        totalPages: Math.ceil(total / pageSize),
+      };
+    } catch (error) {
+      console.error('Failed to get audit logs:', error);
+      throw error;
+    }
+  }
+
   async logAction(
     companyId: number,
     action: string,
     details: unknown
   ): Promise<void> {
     try {
-      // TODO: Implement audit logging command
+      // Data changes are audited by the backend; this only traces UI actions
       console.log('Logging action:', { companyId, action, details });
     } catch (error) {
       console.error('Failed to log action:', error);